@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ssolver::fuzz::Puzzle;
+use ssolver::solver::*;
+
+// Any sequence of moves keeps the pieces on distinct cells they can stand on.
+fuzz_target!(|input: (Puzzle, Vec<Move>)| {
+    let (Puzzle { board, mut state }, moves) = input;
+    for m in &moves {
+        let Some(next) = apply_move(&board, &state, m) else {
+            continue;
+        };
+        let pieces = [
+            next.main_pos(),
+            next.helper_one_pos(),
+            next.helper_two_pos(),
+        ];
+        for pos in pieces {
+            let (x, y) = (pos_to_x(&pos), pos_to_y(&pos));
+            assert!(
+                x < board.width() && y < board.height(),
+                "{pos} is off the board"
+            );
+            assert!(!matches!(
+                board.get(x, y),
+                BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+            ));
+        }
+        assert!(pieces[0] != pieces[1] && pieces[0] != pieces[2] && pieces[1] != pieces[2]);
+        state = next;
+    }
+});
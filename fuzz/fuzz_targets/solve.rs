@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ssolver::fuzz::Puzzle;
+use ssolver::solver::*;
+
+// Searches end within their budget, and solutions found replay to a solved state.
+fuzz_target!(|puzzle: Puzzle| {
+    let Puzzle { board, state } = puzzle;
+    let options = SolveOptions {
+        max_nodes: Some(100_000),
+        ..SolveOptions::default()
+    };
+    let Some(solution) = solve_puzzle_with(&board, state, &options).solution() else {
+        return;
+    };
+    let end = solution.moves.iter().fold(state, |state, m| {
+        apply_move(&board, &state, m).expect("Solution has an illegal move.")
+    });
+    assert_eq!(end, solution.final_state);
+    assert!(is_solved(&board, &end));
+});
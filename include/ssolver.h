@@ -0,0 +1,40 @@
+#ifndef SSOLVER_H
+#define SSOLVER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SS_OK 0
+#define SS_NULL_INPUT 1
+#define SS_INVALID_UTF8 2
+#define SS_PARSE_ERROR 3
+#define SS_UNSOLVABLE 4
+/* The puzzle was read but can't be solved as given, such as pieces off the board. */
+#define SS_INVALID_PUZZLE 5
+
+/* piece: 0 = Main, 1 = Helper1, 2 = Helper2. direction: 0 = Up, 1 = Down, 2 = Left, 3 = Right. */
+typedef struct SSMove {
+    uint8_t piece;
+    uint8_t direction;
+} SSMove;
+
+/* moves is NULL unless error is SS_OK. */
+typedef struct SSSolution {
+    int32_t error;
+    size_t length;
+    SSMove *moves;
+} SSSolution;
+
+/* Never returns NULL, release the result with ss_free_solution. */
+SSSolution *ss_solve(const char *puzzle);
+void ss_free_solution(SSSolution *solution);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,437 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::solver::*;
+
+// Stored for states the puzzle can't be solved from, and for slots that hold no state.
+pub(crate) const UNSOLVABLE: u16 = u16::MAX;
+const NO_CELL: u16 = u16::MAX;
+
+// Every state of a board: the three pieces on distinct cells they can stand on, with any number of
+// goals reached. States are numbered densely so tables over all of them fit in a vector, with
+// 'slots' entries. Slots with pieces on top of each other hold no state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSpace {
+    cells: Vec<Position>,
+    // Index into 'cells' of every position, NO_CELL where no piece can stand.
+    cell_index: Vec<u16>,
+    goals: u8,
+}
+
+impl StateSpace {
+    pub fn new(board: &Board) -> Self {
+        let mut cell_index = vec![NO_CELL; 256];
+        let cells: Vec<Position> = board
+            .cells()
+            .filter(|(_, _, cell)| {
+                !matches!(
+                    cell,
+                    BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+                )
+            })
+            .map(|(x, y, _)| xy_to_pos(x, y))
+            .collect();
+        for (i, pos) in cells.iter().enumerate() {
+            cell_index[*pos as usize] = i as u16;
+        }
+        Self {
+            cells,
+            cell_index,
+            goals: goal_count(board),
+        }
+    }
+
+    pub fn slots(&self) -> usize {
+        (self.goals as usize + 1) * self.cells.len().pow(3)
+    }
+
+    // Slot of 'state', None if a piece is off the board or on a cell it can't stand on.
+    pub fn index(&self, state: &State) -> Option<usize> {
+        if state.goals_reached() > self.goals {
+            return None;
+        }
+        let n = self.cells.len();
+        [
+            state.main_pos(),
+            state.helper_one_pos(),
+            state.helper_two_pos(),
+        ]
+        .into_iter()
+        .try_fold(state.goals_reached() as usize, |index, pos| {
+            match self.cell_index[pos as usize] {
+                NO_CELL => None,
+                cell => Some(index * n + cell as usize),
+            }
+        })
+    }
+
+    // State held in slot 'index', None if the pieces would be on top of each other.
+    pub fn state(&self, index: usize) -> Option<State> {
+        let n = self.cells.len();
+        let (helper_two, rest) = (index % n, index / n);
+        let (helper_one, rest) = (rest % n, rest / n);
+        let (main, goals) = (rest % n, rest / n);
+        if main == helper_one || main == helper_two || helper_one == helper_two {
+            return None;
+        }
+        let state = State::new(
+            self.cells[main],
+            self.cells[helper_one],
+            self.cells[helper_two],
+        );
+        Some(state.with_goals_reached(goals as u8))
+    }
+
+    // Every state with its slot, in slot order.
+    pub fn states(&self) -> impl Iterator<Item = (usize, State)> + '_ {
+        (0..self.slots()).filter_map(|index| Some((index, self.state(index)?)))
+    }
+}
+
+// Exact number of moves left to solve from every slot of 'space', UNSOLVABLE where it can't be.
+// Found with a breadth first search backwards from every solved state, over the moves between all
+// states turned around. That takes a few bytes per move of every state, so the state space has to
+// fit in memory several times over.
+pub(crate) fn distances(board: &Board, space: &StateSpace) -> Vec<u16> {
+    let win = WinCondition::new(board);
+    let order = TieBreak::default();
+    let successors = |state: &State| {
+        neighbourhood(board, state, &order)
+            .into_iter()
+            .filter_map(|(_, next)| space.index(&next))
+    };
+
+    // Predecessors of slot 'i' are 'predecessors[starts[i]..starts[i + 1]]'.
+    let mut starts = vec![0u32; space.slots() + 1];
+    for (_, state) in space.states() {
+        for next in successors(&state) {
+            starts[next + 1] += 1;
+        }
+    }
+    for i in 1..starts.len() {
+        starts[i] += starts[i - 1];
+    }
+    let mut filled = starts.clone();
+    let mut predecessors = vec![0u32; starts[space.slots()] as usize];
+    for (index, state) in space.states() {
+        for next in successors(&state) {
+            predecessors[filled[next] as usize] = index as u32;
+            filled[next] += 1;
+        }
+    }
+    drop(filled);
+
+    let mut distances = vec![UNSOLVABLE; space.slots()];
+    let mut queue = VecDeque::new();
+    for (index, state) in space.states() {
+        if win.is_met(board, &state) {
+            distances[index] = 0;
+            queue.push_back(index);
+        }
+    }
+    while let Some(to) = queue.pop_front() {
+        let distance = distances[to] + 1;
+        for &from in &predecessors[starts[to] as usize..starts[to + 1] as usize] {
+            if distances[from as usize] == UNSOLVABLE {
+                distances[from as usize] = distance;
+                queue.push_back(from as usize);
+            }
+        }
+    }
+    distances
+}
+
+// Piece positions as 'x,y' and the goals reached, for the reports.
+fn describe(state: &State) -> String {
+    let xy = |pos: Position| format!("{},{}", pos_to_x(&pos), pos_to_y(&pos));
+    let mut text = format!(
+        "main {}  helpers {} {}",
+        xy(state.main_pos()),
+        xy(state.helper_one_pos()),
+        xy(state.helper_two_pos())
+    );
+    if state.goals_reached() > 0 {
+        text += &format!("  goals reached {}", state.goals_reached());
+    }
+    text
+}
+
+// How far from solved every placement of the pieces on a board is, see 'analyze'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceReport {
+    // Placements of the three pieces, solvable or not.
+    pub states: usize,
+    // Number of placements by the fewest moves they are solved in, starting at 0 moves.
+    pub distribution: Vec<usize>,
+    pub unsolvable: usize,
+    // Placements needing the most moves, up to the number asked for.
+    pub hardest: Vec<State>,
+}
+
+impl SpaceReport {
+    // God's number: the most moves any solvable placement needs, None if none can be solved.
+    pub fn gods_number(&self) -> Option<usize> {
+        self.distribution.len().checked_sub(1)
+    }
+}
+
+// Optimal solution lengths over every placement of the three pieces with no goals reached, with
+// up to 'examples' of the hardest. The main piece is placed anywhere, not only on the start.
+pub fn analyze(board: &Board, examples: usize) -> SpaceReport {
+    let space = StateSpace::new(board);
+    let distances = distances(board, &space);
+    let placements = space
+        .states()
+        .filter(|(_, state)| state.goals_reached() == 0)
+        .map(|(index, state)| (distances[index], state));
+
+    let mut report = SpaceReport {
+        states: 0,
+        distribution: Vec::new(),
+        unsolvable: 0,
+        hardest: Vec::new(),
+    };
+    for (distance, state) in placements {
+        report.states += 1;
+        if distance == UNSOLVABLE {
+            report.unsolvable += 1;
+            continue;
+        }
+        let distance = distance as usize;
+        if distance >= report.distribution.len() {
+            report.distribution.resize(distance + 1, 0);
+            report.hardest.clear();
+        }
+        report.distribution[distance] += 1;
+        if distance + 1 == report.distribution.len() && report.hardest.len() < examples {
+            report.hardest.push(state);
+        }
+    }
+    report
+}
+
+impl fmt::Display for SpaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Placements: {}  solvable: {}  unsolvable: {}",
+            self.states,
+            self.states - self.unsolvable,
+            self.unsolvable
+        )?;
+        match self.gods_number() {
+            Some(moves) => writeln!(f, "God's number: {moves}")?,
+            None => writeln!(f, "No placement can be solved.")?,
+        }
+        let most = self.distribution.iter().max().copied().unwrap_or(1);
+        writeln!(f, "Placements by optimal solution length:")?;
+        for (length, count) in self.distribution.iter().enumerate() {
+            let bar = "#".repeat((count * 40).div_ceil(most));
+            writeln!(f, "{length:>4} {count:>8} {bar}")?;
+        }
+        writeln!(f, "Hardest placements:")?;
+        for state in &self.hardest {
+            writeln!(f, "  {}", describe(state))?;
+        }
+        Ok(())
+    }
+}
+
+// States reachable from one starting state, see 'reachability'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachabilityReport {
+    // Reachable states including the start, with any number of goals reached.
+    pub reachable: usize,
+    // Reachable states the puzzle can no longer be solved from.
+    pub dead: usize,
+    // Most moves needed to reach any of the reachable states.
+    pub depth: usize,
+    // Dead states in the order they were reached, up to the number asked for.
+    pub dead_examples: Vec<State>,
+}
+
+// Counts the states reachable from 'state' and how many of them are dead ends, listing up to
+// 'examples' of those. None if a piece is on a cell it can't stand on or on top of another.
+pub fn reachability(board: &Board, state: &State, examples: usize) -> Option<ReachabilityReport> {
+    let space = StateSpace::new(board);
+    let start = space.index(state)?;
+    // Slots with pieces on top of each other hold no state.
+    space.state(start)?;
+    let distances = distances(board, &space);
+    let order = TieBreak::default();
+
+    let mut report = ReachabilityReport {
+        reachable: 0,
+        dead: 0,
+        depth: 0,
+        dead_examples: Vec::new(),
+    };
+    let mut seen = vec![false; space.slots()];
+    seen[start] = true;
+    let mut layer = vec![start];
+    while !layer.is_empty() {
+        let mut next_layer = Vec::new();
+        for index in layer {
+            let state = space.state(index).unwrap();
+            report.reachable += 1;
+            if distances[index] == UNSOLVABLE {
+                report.dead += 1;
+                if report.dead_examples.len() < examples {
+                    report.dead_examples.push(state);
+                }
+            }
+            for (_, next) in neighbourhood(board, &state, &order) {
+                let Some(next) = space.index(&next) else {
+                    continue;
+                };
+                if !std::mem::replace(&mut seen[next], true) {
+                    next_layer.push(next);
+                }
+            }
+        }
+        if !next_layer.is_empty() {
+            report.depth += 1;
+        }
+        layer = next_layer;
+    }
+    Some(report)
+}
+
+impl fmt::Display for ReachabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Reachable states: {}  dead: {}  depth: {}",
+            self.reachable, self.dead, self.depth
+        )?;
+        if !self.dead_examples.is_empty() {
+            writeln!(f, "Dead states:")?;
+        }
+        for state in &self.dead_examples {
+            writeln!(f, "  {}", describe(state))?;
+        }
+        Ok(())
+    }
+}
+
+// Cells of a board indexed '[y][x]', as 'Board::get' takes them.
+pub type Grid<T> = Vec<Vec<T>>;
+
+// Fewest moves the main piece needs to stop on each cell from 'state', None for cells it can't get
+// to. Any piece may move on the way and goals are ignored, as they don't change how pieces move.
+pub fn distance_map(board: &Board, state: &State) -> Grid<Option<u32>> {
+    let order = TieBreak::default();
+    let mut map = vec![vec![None; board.width()]; board.height()];
+    let start = state.with_goals_reached(0);
+    let mut visited = Visited::new(board, 0);
+    visited.insert(&start);
+    let mut layer = vec![start];
+    let mut depth = 0;
+    while !layer.is_empty() {
+        let mut next_layer = Vec::new();
+        for state in layer {
+            let main = state.main_pos();
+            map[pos_to_y(&main)][pos_to_x(&main)].get_or_insert(depth);
+            for (_, next) in neighbourhood(board, &state, &order) {
+                let next = next.with_goals_reached(0);
+                if visited.insert(&next) {
+                    next_layer.push(next);
+                }
+            }
+        }
+        layer = next_layer;
+        depth += 1;
+    }
+    map
+}
+
+// How much cells are used over every optimal solution of a puzzle, see 'optimal_heatmap'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    // Optimal solutions counted.
+    pub solutions: u64,
+    // Times a piece slides over or onto each cell, over every move of every optimal solution.
+    pub traversed: Grid<u64>,
+    // Times a piece stops on each cell.
+    pub stopped: Grid<u64>,
+}
+
+// Counts the cells pieces slide over and stop on in every optimal solution, without listing the
+// solutions: the shortest paths to each state are counted going forward a layer at a time, and
+// the optimal ways on from it going back, so each move is weighed by the solutions making it.
+// Counts saturate at 'u64::MAX'. None if the puzzle can't be solved.
+pub fn optimal_heatmap(board: &Board, state: &State) -> Option<Heatmap> {
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    let mut heatmap = Heatmap {
+        solutions: 0,
+        traversed: vec![vec![0; board.width()]; board.height()],
+        stopped: vec![vec![0; board.width()]; board.height()],
+    };
+    // Shortest paths to each state of every layer up to the first with a solved state.
+    let mut layers = vec![HashMap::from([(*state, 1_u64)])];
+    let mut seen = HashSet::from([*state]);
+    while !layers[layers.len() - 1]
+        .keys()
+        .any(|state| win.is_met(board, state))
+    {
+        let mut next: HashMap<State, u64> = HashMap::new();
+        for (state, paths) in &layers[layers.len() - 1] {
+            for (_, reached) in neighbourhood(board, state, &order) {
+                if !seen.contains(&reached) {
+                    let count = next.entry(reached).or_default();
+                    *count = count.saturating_add(*paths);
+                }
+            }
+        }
+        if next.is_empty() {
+            return None;
+        }
+        seen.extend(next.keys());
+        layers.push(next);
+    }
+    // Optimal ways to finish from each state of the layer after the one being gone through.
+    let mut ahead: HashMap<State, u64> = layers[layers.len() - 1]
+        .keys()
+        .filter(|state| win.is_met(board, state))
+        .map(|state| (*state, 1))
+        .collect();
+    for layer in layers.iter().rev().skip(1) {
+        let mut finishing = HashMap::new();
+        for (state, paths) in layer {
+            for (m, reached) in neighbourhood(board, state, &order) {
+                let Some(ways) = ahead.get(&reached) else {
+                    continue;
+                };
+                let uses = paths.saturating_mul(*ways);
+                for pos in slide_path(board, state, &m.0, &m.1).iter().skip(1) {
+                    let cell = &mut heatmap.traversed[pos_to_y(pos)][pos_to_x(pos)];
+                    *cell = cell.saturating_add(uses);
+                }
+                let stop = reached.pos(&m.0);
+                let cell = &mut heatmap.stopped[pos_to_y(&stop)][pos_to_x(&stop)];
+                *cell = cell.saturating_add(uses);
+                let count: &mut u64 = finishing.entry(*state).or_default();
+                *count = count.saturating_add(*ways);
+            }
+        }
+        ahead = finishing;
+    }
+    heatmap.solutions = ahead
+        .values()
+        .fold(0, |sum, ways| sum.saturating_add(*ways));
+    Some(heatmap)
+}
+
+// Written as a JSON object with the number of solutions and the two grids as arrays of rows.
+#[cfg(feature = "json")]
+impl Heatmap {
+    pub fn to_json(&self) -> String {
+        json::object! {
+            solutions: self.solutions,
+            traversed: self.traversed.clone(),
+            stopped: self.stopped.clone(),
+        }
+        .dump()
+    }
+}
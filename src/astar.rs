@@ -0,0 +1,159 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::Instant;
+
+use crate::heuristic::{Heuristic, RicochetLowerBound, UNSOLVABLE};
+use crate::solver::*;
+
+struct Node {
+    state: State,
+    m: Option<Move>,
+    prev: usize,
+}
+
+fn path(nodes: &[Node], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(m) = nodes[index].m {
+        moves.push(m);
+        index = nodes[index].prev;
+    }
+    moves.reverse();
+    moves
+}
+
+fn astar<H: Heuristic + ?Sized>(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    heuristic: &H,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let win = WinCondition::new(board);
+    let estimate = heuristic.estimate(board, &state);
+    if estimate == UNSOLVABLE {
+        return Ok(None);
+    }
+    if options
+        .upper_bound
+        .is_some_and(|max| estimate as usize > max)
+    {
+        return Err(SolveError::BudgetExceeded {
+            lower_bound: estimate as usize,
+        });
+    }
+    // Whether children were dropped for going over 'upper_bound'.
+    let mut cut_off = false;
+    let mut best = HashMap::from([(state, 0)]);
+    let mut nodes = vec![Node {
+        state,
+        m: None,
+        prev: 0,
+    }];
+    // Lowest estimate first, then the deepest node, then in insertion order.
+    let mut heap = BinaryHeap::from([Reverse((estimate as usize, Reverse(0), 0))]);
+    let mut bound = estimate as usize;
+
+    while let Some(Reverse((estimate, Reverse(depth), index))) = heap.pop() {
+        let state = nodes[index].state;
+        if best[&state] < depth {
+            continue; // Stale entry, a shorter path was found after this was queued.
+        }
+        if let Some(on_progress) = &options.on_progress {
+            let interval_reached = options
+                .progress_interval
+                .is_some_and(|n| n > 0 && stats.nodes_expanded.is_multiple_of(n));
+            if estimate > bound || interval_reached {
+                on_progress(&SearchProgress {
+                    depth: estimate,
+                    frontier: heap.len() + 1,
+                    expanded: stats.nodes_expanded,
+                });
+            }
+        }
+        bound = estimate;
+
+        if win.is_met(board, &state) {
+            return Ok(Some((state, path(&nodes, index))));
+        }
+
+        // The estimate never decreases along a path, so no solution is shorter than this one.
+        let over_budget = options
+            .max_nodes
+            .is_some_and(|max| stats.nodes_expanded >= max)
+            || options.max_queue.is_some_and(|max| heap.len() >= max);
+        if over_budget {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: estimate,
+            });
+        }
+
+        stats.nodes_expanded += 1;
+        check_interrupted(options, deadline, stats, bound, heap.len())?;
+
+        for (m, next) in neighbourhood(board, &state, &options.tie_break) {
+            stats.nodes_generated += 1;
+            let rest = heuristic.estimate(board, &next);
+            if rest == UNSOLVABLE {
+                continue;
+            }
+            if options
+                .upper_bound
+                .is_some_and(|max| depth + 1 + rest as usize > max)
+            {
+                cut_off = true;
+                continue;
+            }
+            match best.entry(next) {
+                Entry::Occupied(mut entry) if *entry.get() > depth + 1 => {
+                    entry.insert(depth + 1);
+                }
+                Entry::Occupied(_) => {
+                    stats.duplicates_pruned += 1;
+                    continue;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(depth + 1);
+                }
+            }
+            nodes.push(Node {
+                state: next,
+                m: Some(m),
+                prev: index,
+            });
+            heap.push(Reverse((
+                depth + 1 + rest as usize,
+                Reverse(depth + 1),
+                nodes.len() - 1,
+            )));
+        }
+        stats.max_queue_len = stats.max_queue_len.max(heap.len());
+    }
+
+    if cut_off {
+        return Err(SolveError::BudgetExceeded {
+            lower_bound: options.upper_bound.unwrap_or_default() + 1,
+        });
+    }
+    Ok(None)
+}
+
+// A* search guided by 'RicochetLowerBound'. Gives optimal solutions like 'solve_puzzle_with' while
+// expanding fewer states, though not necessarily the first solution in 'tie_break' order. Progress
+// is reported each time the estimated solution length grows.
+pub fn solve_astar(board: &Board, state: State, options: &SolveOptions) -> SolveOutcome {
+    solve_astar_with(board, state, options, &RicochetLowerBound::new(board))
+}
+
+// Same as 'solve_astar' but guided by 'heuristic'.
+pub fn solve_astar_with<H: Heuristic + ?Sized>(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    heuristic: &H,
+) -> SolveOutcome {
+    run_search(board, state, options, |board, state, options, stats| {
+        astar(board, state, options, heuristic, stats)
+    })
+}
@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use crate::solver::{Board, Move, SearchStats, SolveError, State};
+use crate::tools::{puzzle_from_string, ParseError};
+use crate::validate::ValidationIssue;
+
+// Solving many puzzles on several threads, needs the 'parallel' feature.
+#[cfg(feature = "parallel")]
+mod pipeline;
+#[cfg(all(feature = "parallel", feature = "sqlite"))]
+pub use pipeline::solve_stream_cached;
+#[cfg(feature = "parallel")]
+pub use pipeline::{solve_batch, solve_stream, solve_stream_unique, LineOutcome};
+
+// Why a puzzle line has no answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineError {
+    // The line is not a puzzle, see 'puzzle_from_string'.
+    Parse(ParseError),
+    // The puzzle can't be searched, see 'validate'.
+    Invalid(Vec<ValidationIssue>),
+    // The search gave up before finding an answer.
+    Search(SolveError),
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineError::Parse(err) => write!(f, "{err}"),
+            LineError::Invalid(issues) => {
+                let issues: Vec<String> = issues.iter().map(ValidationIssue::to_string).collect();
+                write!(f, "{}", issues.join(", "))
+            }
+            LineError::Search(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+pub type Moves = Result<Option<Vec<Move>>, LineError>;
+
+// Everything known about one puzzle line once it has been solved.
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub line: usize,
+    // First line holding the same puzzle up to symmetry when this one was not solved again, see
+    // 'solve_stream_unique'. Its stats are then left empty.
+    pub duplicate_of: Option<usize>,
+    // Taken from a 'ResultCache' instead of searched, see 'solve_stream_cached'. Its stats are
+    // then left empty too.
+    pub cached: bool,
+    pub fingerprint: u64,
+    pub moves: Moves,
+    pub stats: SearchStats,
+}
+
+// Groups the line indices of 'input' that hold the same puzzle, ignoring the order entries are
+// listed in. Only groups with more than one line are given, each in line order. Lines that are not
+// puzzles are left out.
+pub fn duplicates<R: BufRead>(input: R) -> io::Result<Vec<Vec<usize>>> {
+    let mut groups: HashMap<(Board, State), Vec<usize>> = HashMap::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if let Ok(puzzle) = puzzle_from_string(&line) {
+            groups.entry(puzzle).or_default().push(index);
+        }
+    }
+    let mut groups: Vec<_> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort_unstable();
+    Ok(groups)
+}
+
+// Writes one CSV row per puzzle result, after a header naming the columns. Unsolvable puzzles
+// have no length, and puzzles the search gave up on leave 'solvable' empty as well.
+pub struct CsvReport<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CsvReport<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        writeln!(
+            out,
+            "line,fingerprint,solvable,length,solve_time_ms,nodes_expanded"
+        )?;
+        Ok(Self { out })
+    }
+
+    pub fn row(&mut self, result: &BatchResult) -> io::Result<()> {
+        let (solvable, length) = match &result.moves {
+            Ok(Some(moves)) => ("true", moves.len().to_string()),
+            Ok(None) => ("false", String::new()),
+            Err(_) => ("", String::new()),
+        };
+        writeln!(
+            self.out,
+            "{},{:016x},{solvable},{length},{:.3},{}",
+            result.line,
+            result.fingerprint,
+            result.stats.duration.as_secs_f64() * 1000.0,
+            result.stats.nodes_expanded
+        )
+    }
+}
+
+// Aggregate statistics over the results of a batch run, printed through 'Display'.
+#[derive(Debug, Clone, Default)]
+pub struct BatchSummary {
+    // Solve time and line of every puzzle that was searched, repeats, cached and invalid puzzles
+    // are left out.
+    times: Vec<(Duration, usize)>,
+    lengths: BTreeMap<usize, usize>,
+    unsolvable: usize,
+    // Lines that were not puzzles or failed 'validate'.
+    invalid: usize,
+    failed: usize,
+}
+
+impl BatchSummary {
+    pub fn add(&mut self, result: &BatchResult) {
+        let invalid = matches!(
+            result.moves,
+            Err(LineError::Parse(_) | LineError::Invalid(_))
+        );
+        if result.duplicate_of.is_none() && !result.cached && !invalid {
+            self.times.push((result.stats.duration, result.line));
+        }
+        match &result.moves {
+            Ok(Some(moves)) => *self.lengths.entry(moves.len()).or_default() += 1,
+            Ok(None) => self.unsolvable += 1,
+            Err(LineError::Parse(_) | LineError::Invalid(_)) => self.invalid += 1,
+            Err(LineError::Search(_)) => self.failed += 1,
+        }
+    }
+
+    // Solve time below which 'percent' of the searched puzzles finished, nearest rank.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        let mut times: Vec<Duration> = self.times.iter().map(|(time, _)| *time).collect();
+        times.sort_unstable();
+        let rank = (percent / 100.0 * times.len() as f64).ceil() as usize;
+        times
+            .get(rank.clamp(1, times.len().max(1)) - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    // Number of solved puzzles by solution length.
+    pub fn lengths(&self) -> &BTreeMap<usize, usize> {
+        &self.lengths
+    }
+
+    // Lines of the 'n' slowest puzzles with their solve times, slowest first.
+    pub fn slowest(&self, n: usize) -> Vec<(usize, Duration)> {
+        let mut times = self.times.clone();
+        times.sort_unstable_by(|a, b| b.cmp(a));
+        times
+            .into_iter()
+            .take(n)
+            .map(|(time, line)| (line, time))
+            .collect()
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        writeln!(
+            f,
+            "Solve time p50 {:.3}ms  p95 {:.3}ms  p99 {:.3}ms",
+            ms(self.percentile(50.0)),
+            ms(self.percentile(95.0)),
+            ms(self.percentile(99.0))
+        )?;
+        writeln!(
+            f,
+            "Unsolvable: {}  Invalid: {}  Search failed: {}",
+            self.unsolvable, self.invalid, self.failed
+        )?;
+        let most = self.lengths.values().max().copied().unwrap_or(1);
+        writeln!(f, "Solution lengths:")?;
+        for (length, count) in &self.lengths {
+            let bar = "#".repeat((count * 40).div_ceil(most));
+            writeln!(f, "{length:>4} {count:>6} {bar}")?;
+        }
+        writeln!(f, "Slowest puzzles:")?;
+        for (line, time) in self.slowest(5) {
+            writeln!(f, "  line {line:>6} {:.3}ms", ms(time))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::thread;
+
+use crossbeam::channel;
+use rayon::prelude::*;
+
+use super::{BatchResult, LineError};
+#[cfg(feature = "sqlite")]
+use crate::cache::ResultCache;
+use crate::canonical::{fingerprint_with_symmetry, Symmetry};
+use crate::parallel::in_pool;
+use crate::solver::{
+    solve_puzzle_with, Board, Move, SearchStats, SolveOptions, SolveOutcome, State,
+};
+use crate::tools::puzzle_from_string;
+use crate::validate::validate;
+
+// Never constructed, there is nothing to consult without the sqlite feature.
+#[cfg(not(feature = "sqlite"))]
+type ResultCache = core::convert::Infallible;
+
+// Outcome of one line of 'solve_batch', or what is wrong with the puzzle if it was not searched.
+// Searches that give up are outcomes too, so the error is never 'LineError::Search'.
+pub type LineOutcome = Result<SolveOutcome, LineError>;
+
+enum Outcome {
+    // With the symmetry taking the puzzle to its canonical form.
+    Solved(BatchResult, Symmetry),
+    // Same puzzle up to symmetry as the earlier line with this index.
+    Duplicate(usize, Symmetry),
+    // Line that was not searched.
+    Failed(BatchResult),
+}
+
+// Solves every puzzle line on 'options.threads' threads, giving the outcomes in the order of
+// 'lines'. Lines are validated first, so one bad puzzle fails only its own line.
+pub fn solve_batch<S: AsRef<str> + Sync>(lines: &[S], options: &SolveOptions) -> Vec<LineOutcome> {
+    in_pool(options.threads, || {
+        lines
+            .par_iter()
+            .map(|line| {
+                let (board, state) = puzzle_from_string(line.as_ref()).map_err(LineError::Parse)?;
+                let issues = validate(&board, &state);
+                if !issues.is_empty() {
+                    return Err(LineError::Invalid(issues));
+                }
+                Ok(solve_puzzle_with(&board, state, options))
+            })
+            .collect()
+    })
+}
+
+// Solves every puzzle line of 'input' on 'workers' threads, calling 'on_result' for each line as
+// it completes (in no particular order). At most 'capacity' lines are held in memory at a time so
+// inputs of any size run in constant memory. Lines that can't be read or fail 'validate' are
+// given as failed results without being searched.
+pub fn solve_stream<R: BufRead + Send>(
+    input: R,
+    options: &SolveOptions,
+    workers: usize,
+    capacity: usize,
+    on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    run(input, options, workers, capacity, false, None, on_result)
+}
+
+// Same as 'solve_stream' but solves each distinct puzzle only once, passing the solution on to
+// every repeat with 'duplicate_of' set. Puzzles with the same 'fingerprint' are repeats, so a
+// rotated, reflected or helper swapped copy gets the solution mapped to its own orientation. The
+// fingerprint and solution of every distinct puzzle are kept in memory until the end.
+pub fn solve_stream_unique<R: BufRead + Send>(
+    input: R,
+    options: &SolveOptions,
+    workers: usize,
+    capacity: usize,
+    on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    run(input, options, workers, capacity, true, None, on_result)
+}
+
+// Same as 'solve_stream' but looks every puzzle up in 'cache' first, searching only the ones
+// missing from it and storing their solutions. Results taken from the cache have 'cached' set.
+// The cache only saves work: failed lookups count as misses and failed stores are skipped.
+#[cfg(feature = "sqlite")]
+pub fn solve_stream_cached<R: BufRead + Send>(
+    input: R,
+    options: &SolveOptions,
+    workers: usize,
+    capacity: usize,
+    cache: &ResultCache,
+    on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    run(
+        input,
+        options,
+        workers,
+        capacity,
+        false,
+        Some(cache),
+        on_result,
+    )
+}
+
+// Failures of the cache only cost the work it would have saved.
+#[cfg(feature = "sqlite")]
+fn ignore_failure<T>(result: Result<T, crate::cache::Error>) -> Option<T> {
+    #[cfg(feature = "tracing")]
+    if let Err(err) = &result {
+        tracing::warn!(%err, "result cache failed");
+    }
+    result.ok()
+}
+
+// Solution stored for the puzzle, the inner None if it can't be solved.
+#[cfg(feature = "sqlite")]
+fn lookup(cache: &ResultCache, board: &Board, state: &State) -> Option<Option<Vec<Move>>> {
+    ignore_failure(cache.get(board, state))
+        .flatten()
+        .map(|record| record.into_moves())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn lookup(cache: &ResultCache, _board: &Board, _state: &State) -> Option<Option<Vec<Move>>> {
+    match *cache {}
+}
+
+#[cfg(feature = "sqlite")]
+fn store(cache: &ResultCache, board: &Board, state: &State, moves: Option<&[Move]>) {
+    ignore_failure(cache.insert(board, state, moves));
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn store(cache: &ResultCache, _board: &Board, _state: &State, _moves: Option<&[Move]>) {
+    match *cache {}
+}
+
+fn run<R: BufRead + Send>(
+    input: R,
+    options: &SolveOptions,
+    workers: usize,
+    capacity: usize,
+    unique: bool,
+    cache: Option<&ResultCache>,
+    mut on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    #[cfg(feature = "tracing")]
+    let batch_span = tracing::info_span!("batch", workers, capacity, unique);
+    #[cfg(feature = "tracing")]
+    let _entered = batch_span.enter();
+    let (send_puzzle, puzzles) = channel::bounded::<(usize, (Board, State))>(capacity);
+    let (send_result, results) = channel::bounded(capacity);
+
+    thread::scope(|scope| {
+        let send_early = send_result.clone();
+        let reader = scope.spawn(move || {
+            let mut submitted = 0;
+            let mut first_seen = HashMap::new();
+            for (index, line) in input.lines().enumerate() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                submitted += 1;
+                // Lines that can't be searched fail on their own without reaching the workers.
+                let checked = puzzle_from_string(&line)
+                    .map_err(LineError::Parse)
+                    .and_then(|(board, state)| {
+                        let issues = validate(&board, &state);
+                        if issues.is_empty() {
+                            Ok((board, state))
+                        } else {
+                            Err(LineError::Invalid(issues))
+                        }
+                    });
+                let puzzle = match checked {
+                    Ok(puzzle) => puzzle,
+                    Err(err) => {
+                        let failed = BatchResult {
+                            line: index,
+                            duplicate_of: None,
+                            cached: false,
+                            fingerprint: 0,
+                            moves: Err(err),
+                            stats: SearchStats::default(),
+                        };
+                        send_early
+                            .send((index, Outcome::Failed(failed)))
+                            .expect("Receiver stopped.");
+                        continue;
+                    }
+                };
+                if unique {
+                    let (fingerprint, symmetry) = fingerprint_with_symmetry(&puzzle.0, &puzzle.1);
+                    match first_seen.entry(fingerprint) {
+                        Entry::Occupied(first) => {
+                            let duplicate = Outcome::Duplicate(*first.get(), symmetry);
+                            send_early
+                                .send((index, duplicate))
+                                .expect("Receiver stopped.");
+                            continue;
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(index);
+                        }
+                    }
+                }
+                send_puzzle.send((index, puzzle)).expect("Workers stopped.");
+            }
+            Ok(submitted)
+        });
+
+        for _ in 0..workers.max(1) {
+            let puzzles = puzzles.clone();
+            let send_result = send_result.clone();
+            #[cfg(feature = "tracing")]
+            let batch_span = &batch_span;
+            scope.spawn(move || {
+                for (index, (board, state)) in puzzles {
+                    #[cfg(feature = "tracing")]
+                    let _span =
+                        tracing::debug_span!(parent: batch_span, "puzzle", line = index).entered();
+                    let (fingerprint, symmetry) = fingerprint_with_symmetry(&board, &state);
+                    let found = cache.and_then(|cache| lookup(cache, &board, &state));
+                    let result = match found {
+                        Some(moves) => BatchResult {
+                            line: index,
+                            duplicate_of: None,
+                            cached: true,
+                            fingerprint,
+                            moves: Ok(moves),
+                            stats: SearchStats::default(),
+                        },
+                        None => {
+                            let outcome = solve_puzzle_with(&board, state, options);
+                            let stats = outcome.stats().clone();
+                            let moves = outcome
+                                .into_result()
+                                .map(|solution| solution.map(|solution| solution.moves))
+                                .map_err(LineError::Search);
+                            if let (Some(cache), Ok(moves)) = (cache, &moves) {
+                                store(cache, &board, &state, moves.as_deref());
+                            }
+                            BatchResult {
+                                line: index,
+                                duplicate_of: None,
+                                cached: false,
+                                fingerprint,
+                                moves,
+                                stats,
+                            }
+                        }
+                    };
+                    send_result
+                        .send((index, Outcome::Solved(result, symmetry)))
+                        .expect("Receiver stopped.");
+                }
+            });
+        }
+        // Results close once the reader and every worker have finished and dropped their senders.
+        drop(send_result);
+
+        // Results of distinct puzzles, and repeats still waiting for theirs, each with the
+        // symmetry taking it to the canonical form.
+        let mut solved: HashMap<usize, (BatchResult, Symmetry)> = HashMap::new();
+        let mut waiting: HashMap<usize, Vec<(usize, Symmetry)>> = HashMap::new();
+        // Moves of the first line are taken through the canonical form to the repeat's
+        // orientation.
+        let repeat = |(line, symmetry): (usize, Symmetry),
+                      (first, first_symmetry): &(BatchResult, Symmetry)| {
+            let moves = first.moves.clone().map(|moves| {
+                moves.map(|moves| {
+                    moves
+                        .iter()
+                        .map(|m| symmetry.unmap_move(&first_symmetry.map_move(m)))
+                        .collect()
+                })
+            });
+            BatchResult {
+                line,
+                duplicate_of: Some(first.line),
+                moves,
+                stats: SearchStats::default(),
+                ..first.clone()
+            }
+        };
+        for (index, outcome) in results {
+            match outcome {
+                Outcome::Solved(result, symmetry) => {
+                    #[cfg(feature = "tracing")]
+                    if let Err(err) = &result.moves {
+                        tracing::warn!(line = index, %err, "puzzle not solved");
+                    }
+                    if unique {
+                        let first = (result.clone(), symmetry);
+                        for line in waiting.remove(&index).unwrap_or_default() {
+                            on_result(repeat(line, &first));
+                        }
+                        solved.insert(index, first);
+                    }
+                    on_result(result);
+                }
+                Outcome::Duplicate(first, symmetry) => match solved.get(&first) {
+                    Some(first) => on_result(repeat((index, symmetry), first)),
+                    None => waiting.entry(first).or_default().push((index, symmetry)),
+                },
+                Outcome::Failed(result) => on_result(result),
+            }
+        }
+        let submitted = reader.join().expect("Reader panicked.");
+        #[cfg(feature = "tracing")]
+        tracing::info!(submitted = submitted.as_ref().ok(), "batch finished");
+        submitted
+    })
+}
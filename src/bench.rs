@@ -0,0 +1,71 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::search::Algorithm;
+use crate::solver::*;
+
+// Totals of one algorithm over a set of puzzles, see 'bench'.
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    pub algorithm: Algorithm,
+    pub solved: usize,
+    // Puzzles the search gave up on or found no solution for.
+    pub failed: usize,
+    pub duration: Duration,
+    pub nodes_expanded: usize,
+    // Summed length of the solutions found, equal for every algorithm as they are all optimal.
+    pub moves: usize,
+}
+
+impl BenchReport {
+    pub fn nodes_per_sec(&self) -> f64 {
+        self.nodes_expanded as f64 / self.duration.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<14} solved {:>5}  failed {:>3}  moves {:>6}  time {:>10.1}ms  expanded {:>11}  {:>11.0} nodes/s",
+            self.algorithm.name(),
+            self.solved,
+            self.failed,
+            self.moves,
+            self.duration.as_secs_f64() * 1000.0,
+            self.nodes_expanded,
+            self.nodes_per_sec()
+        )
+    }
+}
+
+// Solves every puzzle with each of 'algorithms' in turn, one report per algorithm.
+pub fn bench(
+    puzzles: &[(Board, State)],
+    algorithms: &[Algorithm],
+    options: &SolveOptions,
+) -> Vec<BenchReport> {
+    algorithms
+        .iter()
+        .map(|algorithm| {
+            let search = algorithm.with_options(options.clone());
+            let mut report = BenchReport {
+                algorithm: *algorithm,
+                ..BenchReport::default()
+            };
+            for (board, state) in puzzles {
+                let outcome = search.search(board, *state);
+                report.duration += outcome.stats().duration;
+                report.nodes_expanded += outcome.stats().nodes_expanded;
+                match outcome.solution() {
+                    Some(solution) => {
+                        report.solved += 1;
+                        report.moves += solution.moves.len();
+                    }
+                    None => report.failed += 1,
+                }
+            }
+            report
+        })
+        .collect()
+}
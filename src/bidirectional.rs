@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::solver::*;
+
+// Every state that solves the puzzle. Helpers without a goal can be anywhere apart from the start
+// and each other, including where they started in case they never have to move.
+fn solved_states(board: &Board, state: &State) -> Vec<State> {
+    let win = WinCondition::new(board);
+    let Some(start) = board
+        .cells()
+        .find(|(_, _, cell)| **cell == BoardPiece::Start)
+        .map(|(x, y, _)| xy_to_pos(x, y))
+    else {
+        return Vec::new();
+    };
+    let free: Vec<Position> = board
+        .cells()
+        .filter(|(_, _, cell)| !is_blocked(cell))
+        .map(|(x, y, _)| xy_to_pos(x, y))
+        .collect();
+    let cells = |goal: Option<Position>, initial: Position| match goal {
+        Some(goal) => vec![goal],
+        None if free.contains(&initial) => free.clone(),
+        None => [free.as_slice(), &[initial]].concat(),
+    };
+    let mut states = Vec::new();
+    for helper_one in cells(win.helper_one, state.helper_one_pos()) {
+        for helper_two in cells(win.helper_two, state.helper_two_pos()) {
+            if helper_one == helper_two || start == helper_one || start == helper_two {
+                continue;
+            }
+            states.push(State::new(start, helper_one, helper_two).with_goals_reached(win.goals));
+        }
+    }
+    states
+}
+
+struct Node {
+    state: State,
+    m: Option<Move>,
+    // The node this one was reached from, towards the start going forwards and towards a solved
+    // state going backwards.
+    link: usize,
+    depth: usize,
+}
+
+// One direction of the search, expanded a whole layer at a time.
+struct Side {
+    nodes: Vec<Node>,
+    seen: HashMap<State, usize>,
+    frontier: Vec<usize>,
+    depth: usize,
+}
+
+impl Side {
+    fn new(states: Vec<State>) -> Self {
+        let mut side = Self {
+            nodes: Vec::new(),
+            seen: HashMap::new(),
+            frontier: Vec::new(),
+            depth: 0,
+        };
+        for state in states {
+            side.insert(state, None, 0);
+        }
+        side
+    }
+
+    fn insert(&mut self, state: State, m: Option<Move>, link: usize) -> Option<usize> {
+        if self.seen.contains_key(&state) {
+            return None;
+        }
+        let depth = if m.is_some() {
+            self.nodes[link].depth + 1
+        } else {
+            0
+        };
+        self.nodes.push(Node {
+            state,
+            m,
+            link,
+            depth,
+        });
+        let index = self.nodes.len() - 1;
+        self.seen.insert(state, index);
+        self.frontier.push(index);
+        Some(index)
+    }
+
+    // Moves along the links from 'index' to the root, in the order followed.
+    fn moves(&self, mut index: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(m) = self.nodes[index].m {
+            moves.push(m);
+            index = self.nodes[index].link;
+        }
+        moves
+    }
+
+    fn root(&self, mut index: usize) -> State {
+        while self.nodes[index].m.is_some() {
+            index = self.nodes[index].link;
+        }
+        self.nodes[index].state
+    }
+}
+
+fn bidirectional(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let mut forward = Side::new(vec![state]);
+    let mut backward = Side::new(solved_states(board, &state));
+    if let Some(&end) = backward.seen.get(&state) {
+        return Ok(Some((backward.nodes[end].state, Vec::new())));
+    }
+
+    while !forward.frontier.is_empty() && !backward.frontier.is_empty() {
+        if let Some(on_progress) = &options.on_progress {
+            on_progress(&SearchProgress {
+                depth: forward.depth + backward.depth,
+                frontier: forward.frontier.len() + backward.frontier.len(),
+                expanded: stats.nodes_expanded,
+            });
+        }
+        // Solutions found from here on are longer than the layers searched so far.
+        let searched = forward.depth + backward.depth;
+        if options.upper_bound.is_some_and(|bound| searched >= bound) {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: searched + 1,
+            });
+        }
+        // Grow the smaller side, the first meeting of the two in a layer might not be the closest.
+        let going_forward = forward.frontier.len() <= backward.frontier.len();
+        let (side, other) = if going_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        let mut best: Option<(usize, usize, usize)> = None;
+        for index in std::mem::take(&mut side.frontier) {
+            // Every shorter solution would have met between the layers searched already.
+            let over_budget = options
+                .max_nodes
+                .is_some_and(|max| stats.nodes_expanded >= max)
+                || options
+                    .max_queue
+                    .is_some_and(|max| side.frontier.len() + other.frontier.len() >= max);
+            if over_budget {
+                return Err(SolveError::BudgetExceeded {
+                    lower_bound: side.depth + other.depth + 1,
+                });
+            }
+            stats.nodes_expanded += 1;
+            check_interrupted(
+                options,
+                deadline,
+                stats,
+                side.depth + other.depth,
+                side.frontier.len() + other.frontier.len(),
+            )?;
+
+            let state = side.nodes[index].state;
+            let next_states = if going_forward {
+                neighbourhood(board, &state, &options.tie_break).to_vec()
+            } else {
+                predecessors(board, &state, &options.tie_break, true)
+            };
+            for (m, next) in next_states {
+                stats.nodes_generated += 1;
+                let Some(added) = side.insert(next, Some(m), index) else {
+                    stats.duplicates_pruned += 1;
+                    continue;
+                };
+                if let Some(&met) = other.seen.get(&next) {
+                    let length = side.depth + 1 + other.nodes[met].depth;
+                    if best.is_none_or(|(shortest, _, _)| length < shortest) {
+                        best = Some((length, added, met));
+                    }
+                }
+            }
+            stats.max_queue_len = stats
+                .max_queue_len
+                .max(side.frontier.len() + other.frontier.len());
+        }
+        side.depth += 1;
+
+        if let Some((_, added, met)) = best {
+            let (forward_end, backward_end) = if going_forward {
+                (added, met)
+            } else {
+                (met, added)
+            };
+            let mut moves = forward.moves(forward_end);
+            moves.reverse();
+            moves.extend(backward.moves(backward_end));
+            let solved = backward.root(backward_end);
+            return Ok(Some((solved, moves)));
+        }
+    }
+
+    Ok(None)
+}
+
+// Breadth first search from both ends at once, backwards from every solved state. Gives optimal
+// solutions, though not necessarily the first in 'tie_break' order. Stepping back a move means
+// trying every piece on every cell, so this pays off on puzzles with few solved states and long
+// solutions only.
+pub fn solve_bidirectional(board: &Board, state: State, options: &SolveOptions) -> SolveOutcome {
+    run_search(board, state, options, bidirectional)
+}
@@ -0,0 +1,212 @@
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell as OnceLock;
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::solver::*;
+
+// Direction index into 'Bitboard::stops', with the row or column a cell is on and its bit there.
+fn line(pos: &Position, dir: &Direction) -> (usize, usize, usize) {
+    let (x, y) = (pos_to_x(pos), pos_to_y(pos));
+    match dir {
+        Direction::Up => (0, x, y),
+        Direction::Down => (1, x, y),
+        Direction::Left => (2, y, x),
+        Direction::Right => (3, y, x),
+    }
+}
+
+// Cells a sliding piece stops on as one bitmask per row and column, for boards where every slide
+// runs in a straight line: sliding movement without mirrors or portals. Other pieces are added as
+// stops on the cell before them when moving, so a slide is a search for the nearest set bit.
+#[derive(Clone)]
+pub(crate) struct Bitboard {
+    width: usize,
+    height: usize,
+    toroidal: bool,
+    // Cells a piece moving in each direction stops on with no other piece on the board, from a
+    // wall, blocker or edge just ahead. Indexed like 'line', bit y of a column or bit x of a row.
+    stops: [[u16; 16]; 4],
+}
+
+impl Bitboard {
+    pub(crate) fn new(board: &Board) -> Option<Self> {
+        // Lava makes moves illegal rather than stopping them, which stops don't capture.
+        let bends = board.cells().any(|(_, _, cell)| {
+            matches!(
+                cell,
+                BoardPiece::Mirror(..) | BoardPiece::Portal(_) | BoardPiece::Lava(_)
+            )
+        });
+        if bends || board.movement() != Movement::Slide {
+            return None;
+        }
+        let mut stops = [[0; 16]; 4];
+        for (x, y, _) in board.cells() {
+            let pos = xy_to_pos(x, y);
+            let alone = State::new(pos, pos, pos);
+            for dir in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                if advance(board, &alone, &[], &PieceType::Main, &pos, dir).is_none() {
+                    let (index, line, bit) = line(&pos, &dir);
+                    stops[index][line] |= 1 << bit;
+                }
+            }
+        }
+        Some(Self {
+            width: board.width(),
+            height: board.height(),
+            toroidal: board.is_toroidal(),
+            stops,
+        })
+    }
+
+    // Where a piece at 'pos' stops sliding in direction 'dir' with pieces on 'pieces', which may
+    // include itself. None if it can't move.
+    pub(crate) fn slide(
+        &self,
+        pos: &Position,
+        dir: &Direction,
+        pieces: [Position; 3],
+    ) -> Option<Position> {
+        let (index, on, bit) = line(pos, dir);
+        let size = match dir {
+            Direction::Up | Direction::Down => self.height,
+            Direction::Left | Direction::Right => self.width,
+        };
+        let forward = matches!(dir, Direction::Down | Direction::Right);
+        let mut stops = self.stops[index][on];
+        for piece in &pieces {
+            let (_, piece_on, piece_bit) = line(piece, dir);
+            if piece_on != on {
+                continue;
+            }
+            // The cell before the piece, wrapping around on toroidal boards.
+            let before = if forward {
+                match piece_bit {
+                    0 => self.toroidal.then(|| size - 1),
+                    _ => Some(piece_bit - 1),
+                }
+            } else if piece_bit + 1 == size {
+                self.toroidal.then_some(0)
+            } else {
+                Some(piece_bit + 1)
+            };
+            if let Some(before) = before {
+                stops |= 1 << before;
+            }
+        }
+        if stops & 1 << bit != 0 {
+            return None;
+        }
+
+        // Nearest stop ahead, or after wrapping around. The piece itself is one of 'pieces', so
+        // there always is one on toroidal boards, and edges are stops on the others.
+        let ahead = if forward {
+            stops & u16::MAX.checked_shl(bit as u32 + 1).unwrap_or(0)
+        } else {
+            stops & ((1 << bit) - 1)
+        };
+        let stops = if ahead != 0 { ahead } else { stops };
+        if stops == 0 {
+            return None;
+        }
+        let stop = if forward {
+            stops.trailing_zeros()
+        } else {
+            15 - stops.leading_zeros()
+        } as usize;
+        let (x, y) = match dir {
+            Direction::Up | Direction::Down => (pos_to_x(pos), stop),
+            Direction::Left | Direction::Right => (stop, pos_to_y(pos)),
+        };
+        Some(xy_to_pos(x, y))
+    }
+}
+
+// Built the first time a board is searched and dropped whenever it changes. Left out of comparing
+// and hashing boards, as it only depends on the rest of the board.
+#[derive(Clone, Default)]
+pub(crate) struct BitboardCache(OnceLock<Option<Bitboard>>);
+
+impl BitboardCache {
+    pub(crate) fn get(&self, board: &Board) -> Option<&Bitboard> {
+        self.0.get_or_init(|| Bitboard::new(board)).as_ref()
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for BitboardCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for BitboardCache {}
+
+impl Hash for BitboardCache {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tools::puzzle_from_string;
+
+    // Every slide of the main piece, compared with the slide the bitboard stands in for.
+    fn assert_slides_match(puzzle: &str) {
+        let (board, state) = puzzle_from_string(puzzle).unwrap();
+        let bitboard = Bitboard::new(&board).unwrap();
+        let pieces = [
+            state.main_pos(),
+            state.helper_one_pos(),
+            state.helper_two_pos(),
+        ];
+        for (x, y, _) in board.cells() {
+            let pos = xy_to_pos(x, y);
+            if pieces[1..].contains(&pos) {
+                continue;
+            }
+            let state = state.with_pos(&PieceType::Main, pos);
+            let pieces = [pos, pieces[1], pieces[2]];
+            for dir in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                let expected = slide(&board, &state, &[], &PieceType::Main, &pos, &dir, |_| {})
+                    .map(|(pos, _)| pos);
+                assert_eq!(
+                    bitboard.slide(&pos, &dir, pieces),
+                    expected,
+                    "{x},{y} {dir:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn slides_off_the_last_cell_of_a_16_cell_side() {
+        assert_slides_match(
+            "map:16:4:toroidal:main_robot:15:0:helper_robot:3:1:helper_robot:8:2:blocker:5:0:\
+             goal:0:3:0",
+        );
+        assert_slides_match(
+            "map:4:16:toroidal:main_robot:0:15:helper_robot:1:3:helper_robot:2:8:blocker:0:5:\
+             goal:3:0:0",
+        );
+        assert_slides_match(
+            "map:16:16:main_robot:15:15:helper_robot:3:1:helper_robot:8:12:blocker:5:15:\
+             goal:0:3:0",
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+
+pub use rusqlite::Error;
+
+use crate::canonical::fingerprint_with_symmetry;
+use crate::solution_db::{decode_move, encode_move, unmap, Record};
+use crate::solver::*;
+
+// Solutions kept in a SQLite database, keyed by 'fingerprint' like 'SolutionDatabase' so puzzles
+// are found again in any orientation. Every lookup and insert goes straight to the database, so
+// several runs and processes can share one file.
+//
+// The table is 'solutions(fingerprint INTEGER PRIMARY KEY, length INTEGER, moves BLOB)', with
+// the fingerprint stored as a signed 64 bit integer, no length for unsolvable puzzles and moves of
+// the canonical form encoded as in 'SolutionDatabase::write'.
+pub struct ResultCache {
+    // Connections can't be shared between threads, the batch workers take turns.
+    connection: Mutex<Connection>,
+}
+
+impl ResultCache {
+    // Opens the cache at 'path', creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    // Cache that is gone once dropped.
+    pub fn in_memory() -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS solutions (
+                fingerprint INTEGER PRIMARY KEY,
+                length INTEGER,
+                moves BLOB NOT NULL
+            )",
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    // What is stored for the puzzle, with the moves of a solution fitting the puzzle as given.
+    pub fn get(&self, board: &Board, state: &State) -> Result<Option<Record>, Error> {
+        let (fingerprint, symmetry) = fingerprint_with_symmetry(board, state);
+        let connection = self.connection.lock().unwrap();
+        let row = connection
+            .query_row(
+                "SELECT length, moves FROM solutions WHERE fingerprint = ?1",
+                params![fingerprint as i64],
+                |row| Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()?;
+        let Some((length, moves)) = row else {
+            return Ok(None);
+        };
+        let record = match length {
+            Some(_) => Record::Solved(
+                moves
+                    .into_iter()
+                    .map(decode_move)
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| Error::FromSqlConversionFailure(1, Type::Blob, Box::new(err)))?,
+            ),
+            None => Record::Unsolvable,
+        };
+        Ok(Some(unmap(&record, &symmetry)))
+    }
+
+    // Stores an optimal solution for the puzzle, or None if it can't be solved, replacing what
+    // was stored for it before.
+    pub fn insert(
+        &self,
+        board: &Board,
+        state: &State,
+        moves: Option<&[Move]>,
+    ) -> Result<(), Error> {
+        let (fingerprint, symmetry) = fingerprint_with_symmetry(board, state);
+        let encoded: Vec<u8> = moves
+            .unwrap_or_default()
+            .iter()
+            .map(|m| encode_move(&symmetry.map_move(m)))
+            .collect();
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO solutions (fingerprint, length, moves) VALUES (?1, ?2, ?3)",
+            params![
+                fingerprint as i64,
+                moves.map(|moves| moves.len() as i64),
+                encoded
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Number of puzzles stored.
+    pub fn count(&self) -> Result<usize, Error> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM solutions", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count as usize)
+    }
+}
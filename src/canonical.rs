@@ -0,0 +1,288 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::solver::*;
+
+// One of the 16 ways to redraw a puzzle without changing it: an optional left-right reflection,
+// followed by 'rotation' clockwise quarter turns, with the two helpers optionally trading places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Symmetry {
+    pub reflect: bool,
+    pub rotation: u8,
+    pub swap_helpers: bool,
+}
+
+impl Symmetry {
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        (0..16).map(|i| Symmetry {
+            reflect: i & 1 != 0,
+            rotation: (i >> 1) as u8 & 3,
+            swap_helpers: i & 8 != 0,
+        })
+    }
+
+    // Size of a 'width' x 'height' board after the transform.
+    pub fn map_size(&self, width: usize, height: usize) -> (usize, usize) {
+        if self.rotation.is_multiple_of(2) {
+            (width, height)
+        } else {
+            (height, width)
+        }
+    }
+
+    pub fn map_xy(&self, x: usize, y: usize, width: usize, height: usize) -> (usize, usize) {
+        let (mut x, mut y, mut width, mut height) = (x, y, width, height);
+        if self.reflect {
+            x = width - 1 - x;
+        }
+        for _ in 0..self.rotation {
+            (x, y) = (height - 1 - y, x);
+            (width, height) = (height, width);
+        }
+        (x, y)
+    }
+
+    pub fn map_dir(&self, dir: &Direction) -> Direction {
+        let mut dir = match (self.reflect, dir) {
+            (true, Direction::Left) => Direction::Right,
+            (true, Direction::Right) => Direction::Left,
+            _ => *dir,
+        };
+        for _ in 0..self.rotation {
+            dir = match dir {
+                Direction::Up => Direction::Right,
+                Direction::Right => Direction::Down,
+                Direction::Down => Direction::Left,
+                Direction::Left => Direction::Up,
+            };
+        }
+        dir
+    }
+
+    pub fn map_piece(&self, piece: &PieceType) -> PieceType {
+        match piece {
+            PieceType::HelperOne if self.swap_helpers => PieceType::HelperTwo,
+            PieceType::HelperTwo if self.swap_helpers => PieceType::HelperOne,
+            piece => *piece,
+        }
+    }
+
+    fn map_slant(&self, slant: &Slant) -> Slant {
+        // A reflection and every quarter turn each swap the two diagonals.
+        let flips = self.reflect as u8 + self.rotation;
+        match slant {
+            _ if flips.is_multiple_of(2) => *slant,
+            Slant::Slash => Slant::Backslash,
+            Slant::Backslash => Slant::Slash,
+        }
+    }
+
+    pub fn map_move(&self, m: &Move) -> Move {
+        (self.map_piece(&m.0), self.map_dir(&m.1))
+    }
+
+    // Takes a move on the transformed puzzle back to the original one.
+    pub fn unmap_move(&self, m: &Move) -> Move {
+        let dir = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .into_iter()
+        .find(|dir| self.map_dir(dir) == m.1)
+        .unwrap();
+        // Swapping the helpers is its own inverse.
+        (self.map_piece(&m.0), dir)
+    }
+
+    // Redraws the puzzle. Portals are renumbered in reading order on the new board.
+    pub fn apply(&self, board: &Board, state: &State) -> (Board, State) {
+        let (width, height) = (board.width(), board.height());
+        let (new_width, new_height) = self.map_size(width, height);
+        let map_pos = |pos: &Position| {
+            let (x, y) = self.map_xy(pos_to_x(pos), pos_to_y(pos), width, height);
+            xy_to_pos(x, y)
+        };
+
+        let mut mapped = Board::new(new_width, new_height);
+        mapped.set_toroidal(board.is_toroidal());
+        mapped.set_movement(board.movement());
+        for (x, y, piece) in board.cells() {
+            let (new_x, new_y) = self.map_xy(x, y, width, height);
+            mapped.set(
+                new_x,
+                new_y,
+                match piece {
+                    BoardPiece::HelperGoal(helper) => {
+                        BoardPiece::HelperGoal(self.map_piece(helper))
+                    }
+                    BoardPiece::Mirror(slant, key) => BoardPiece::Mirror(
+                        self.map_slant(slant),
+                        key.as_ref().map(|key| self.map_piece(key)),
+                    ),
+                    BoardPiece::OneWay(dir) => BoardPiece::OneWay(self.map_dir(dir)),
+                    BoardPiece::Lava(key) => {
+                        BoardPiece::Lava(key.as_ref().map(|key| self.map_piece(key)))
+                    }
+                    // Added back below once every portal has moved.
+                    BoardPiece::Portal(_) => BoardPiece::Empty,
+                    piece => piece.clone(),
+                },
+            );
+            for side in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                if board.has_wall(x, y, &side) {
+                    mapped.add_wall(new_x, new_y, self.map_dir(&side));
+                }
+            }
+        }
+
+        let mut portals: Vec<(Position, Position)> = board
+            .portals()
+            .iter()
+            .map(|(a, b)| {
+                let (a, b) = (map_pos(a), map_pos(b));
+                (a.min(b), a.max(b))
+            })
+            .collect();
+        // Positions are x major, reading order is y major.
+        portals.sort_unstable_by_key(|(a, _)| (pos_to_y(a), pos_to_x(a)));
+        for (a, b) in portals {
+            mapped.add_portal(a, b);
+        }
+
+        let (one, two) = if self.swap_helpers {
+            (state.helper_two_pos(), state.helper_one_pos())
+        } else {
+            (state.helper_one_pos(), state.helper_two_pos())
+        };
+        let state = State::new(map_pos(&state.main_pos()), map_pos(&one), map_pos(&two))
+            .with_goals_reached(state.goals_reached());
+        (mapped, state)
+    }
+}
+
+fn piece_code(piece: &Option<PieceType>) -> u8 {
+    match piece {
+        None => 0,
+        Some(PieceType::Main) => 1,
+        Some(PieceType::HelperOne) => 2,
+        Some(PieceType::HelperTwo) => 3,
+    }
+}
+
+fn dir_code(dir: &Direction) -> u8 {
+    match dir {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+// Byte encoding of a puzzle, equal for equal puzzles. Fingerprints are computed over it, so it
+// must never change, new board features get new codes instead. The layout is:
+// - width, height, toroidal (0 or 1), movement (0 slide, 1 step, 2 sticky)
+// - for every cell in reading order: kind, detail and the cell's wall bits (see 'wall_bit')
+//   - kind 0 empty, 1 start, 2 goal, 3 helper goal, 4 mirror, 5 one-way, 6 portal, 7 blocker,
+//     8 pushable, 9 void, 10 lava
+//   - detail is the goal index, the helper (2 HelperOne, 3 HelperTwo), the one-way direction
+//     (0 up, 1 down, 2 left, 3 right), the portal id, for mirrors 4 for '\' plus the piece
+//     passing through (0 none, 1 main, 2 HelperOne, 3 HelperTwo), for lava the piece it is fatal
+//     to in the same codes with 0 for every piece, 0 otherwise
+// - both positions of every portal pair in id order
+// - main, HelperOne and HelperTwo positions as 'x << 4 | y', then the goals reached
+pub(crate) fn encode(board: &Board, state: &State) -> Vec<u8> {
+    let mut bytes = vec![
+        board.width() as u8,
+        board.height() as u8,
+        board.is_toroidal() as u8,
+        match board.movement() {
+            Movement::Slide => 0,
+            Movement::Step => 1,
+            Movement::Sticky => 2,
+        },
+    ];
+    for (x, y, piece) in board.cells() {
+        let (kind, detail) = match piece {
+            BoardPiece::Empty | BoardPiece::BoardHelper | BoardPiece::BoardMain => (0, 0),
+            BoardPiece::Start => (1, 0),
+            BoardPiece::Goal(index) => (2, *index),
+            BoardPiece::HelperGoal(helper) => (3, piece_code(&Some(*helper))),
+            BoardPiece::Mirror(slant, key) => {
+                let slant = match slant {
+                    Slant::Slash => 0,
+                    Slant::Backslash => 4,
+                };
+                (4, slant | piece_code(key))
+            }
+            BoardPiece::OneWay(dir) => (5, dir_code(dir)),
+            BoardPiece::Portal(id) => (6, *id),
+            BoardPiece::Blocker => (7, 0),
+            BoardPiece::Pushable => (8, 0),
+            BoardPiece::Void => (9, 0),
+            BoardPiece::Lava(key) => (10, piece_code(key)),
+        };
+        bytes.extend([kind, detail, board.walls(x, y)]);
+    }
+    for (a, b) in board.portals() {
+        bytes.extend([*a, *b]);
+    }
+    bytes.extend([
+        state.main_pos(),
+        state.helper_one_pos(),
+        state.helper_two_pos(),
+        state.goals_reached(),
+    ]);
+    bytes
+}
+
+// Maps a puzzle to the representative of all its rotations, reflections and helper swaps, along
+// with the symmetry taking the given puzzle to it. Solutions found for the representative are
+// taken back with 'Symmetry::unmap_move'.
+pub fn canonicalize(board: &Board, state: &State) -> (Board, State, Symmetry) {
+    Symmetry::all()
+        .map(|symmetry| {
+            let (board, state) = symmetry.apply(board, state);
+            (encode(&board, &state), board, state, symmetry)
+        })
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, board, state, symmetry)| (board, state, symmetry))
+        .unwrap()
+}
+
+const FINGERPRINT_VERSION: u8 = 1;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Identifies a puzzle up to symmetry, for use as a cache or database key. It is the 64 bit FNV-1a
+// hash of the version byte 1 followed by 'encode' of the canonical form, and stays the same across
+// crate versions, platforms and runs (unlike 'std::hash').
+pub fn fingerprint(board: &Board, state: &State) -> u64 {
+    fingerprint_with_symmetry(board, state).0
+}
+
+// The fingerprint along with the symmetry taking the puzzle to its canonical form, for storing
+// solutions in the canonical orientation.
+pub(crate) fn fingerprint_with_symmetry(board: &Board, state: &State) -> (u64, Symmetry) {
+    let (board, state, symmetry) = canonicalize(board, state);
+    let hash = fnv1a(core::iter::once(FINGERPRINT_VERSION).chain(encode(&board, &state)));
+    (hash, symmetry)
+}
+
+// Identifies the board a table was built for, in its own orientation.
+#[cfg(feature = "std")]
+pub(crate) fn board_hash(board: &Board) -> u64 {
+    fnv1a(encode(board, &State::default()))
+}
+
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
@@ -0,0 +1,281 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::solver::*;
+
+const VERSION: u32 = 0;
+// The 64 characters of unpadded base64url, in order.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    // Writes the low 'bits' bits of 'value', most significant first.
+    fn write(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> i & 1 != 0 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl BitReader<'_> {
+    // Reads 'bits' bits written by 'BitWriter::write', None past the end.
+    fn read(&mut self, bits: usize) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..bits {
+            let byte = self.bytes.get(self.at / 8)?;
+            value = value << 1 | u32::from(byte & 0x80 >> (self.at % 8) != 0);
+            self.at += 1;
+        }
+        Some(value)
+    }
+
+    fn read_pos(&mut self, width: usize, height: usize) -> Option<Position> {
+        let pos = self.read(8)? as Position;
+        (pos_to_x(&pos) < width && pos_to_y(&pos) < height).then_some(pos)
+    }
+}
+
+fn piece_bits(piece: &PieceType) -> u32 {
+    match piece {
+        PieceType::HelperOne => 0,
+        PieceType::HelperTwo => 1,
+        PieceType::Main => 2,
+    }
+}
+
+fn piece_from_bits(bits: u32) -> Option<PieceType> {
+    [PieceType::HelperOne, PieceType::HelperTwo, PieceType::Main]
+        .get(bits as usize)
+        .copied()
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+fn direction_bits(dir: &Direction) -> u32 {
+    DIRECTIONS.iter().position(|d| d == dir).unwrap() as u32
+}
+
+// Writes a cell as '0' for an empty cell, '10' for a blocker and '11' followed by a 4 bit kind and
+// its details for anything else. Portal cells are written empty, the portals follow the cells.
+fn write_cell(out: &mut BitWriter, cell: &BoardPiece) {
+    match cell {
+        BoardPiece::Empty | BoardPiece::Portal(_) => out.write(0, 1),
+        BoardPiece::Blocker => out.write(0b10, 2),
+        cell => {
+            out.write(0b11, 2);
+            match cell {
+                BoardPiece::Start => out.write(0, 4),
+                BoardPiece::Goal(index) => {
+                    out.write(1, 4);
+                    out.write(u32::from(*index), 8);
+                }
+                BoardPiece::HelperGoal(helper) => {
+                    out.write(2, 4);
+                    out.write(piece_bits(helper), 2);
+                }
+                BoardPiece::Mirror(slant, key) => {
+                    out.write(3, 4);
+                    out.write(u32::from(*slant == Slant::Backslash), 1);
+                    out.write(key.as_ref().map_or(3, piece_bits), 2);
+                }
+                BoardPiece::OneWay(dir) => {
+                    out.write(4, 4);
+                    out.write(direction_bits(dir), 2);
+                }
+                BoardPiece::Pushable => out.write(5, 4),
+                BoardPiece::Void => out.write(6, 4),
+                BoardPiece::BoardHelper => out.write(7, 4),
+                BoardPiece::BoardMain => out.write(8, 4),
+                BoardPiece::Lava(key) => {
+                    out.write(9, 4);
+                    out.write(key.as_ref().map_or(3, piece_bits), 2);
+                }
+                BoardPiece::Empty | BoardPiece::Portal(_) | BoardPiece::Blocker => unreachable!(),
+            }
+        }
+    }
+}
+
+fn read_cell(input: &mut BitReader) -> Option<BoardPiece> {
+    if input.read(1)? == 0 {
+        return Some(BoardPiece::Empty);
+    }
+    if input.read(1)? == 0 {
+        return Some(BoardPiece::Blocker);
+    }
+    Some(match input.read(4)? {
+        0 => BoardPiece::Start,
+        1 => BoardPiece::Goal(input.read(8)? as u8),
+        2 => BoardPiece::HelperGoal(piece_from_bits(input.read(2)?)?),
+        3 => {
+            let slant = match input.read(1)? {
+                0 => Slant::Slash,
+                _ => Slant::Backslash,
+            };
+            let key = match input.read(2)? {
+                3 => None,
+                bits => Some(piece_from_bits(bits)?),
+            };
+            BoardPiece::Mirror(slant, key)
+        }
+        4 => BoardPiece::OneWay(DIRECTIONS[input.read(2)? as usize]),
+        5 => BoardPiece::Pushable,
+        6 => BoardPiece::Void,
+        7 => BoardPiece::BoardHelper,
+        8 => BoardPiece::BoardMain,
+        9 => BoardPiece::Lava(match input.read(2)? {
+            3 => None,
+            bits => Some(piece_from_bits(bits)?),
+        }),
+        _ => return None,
+    })
+}
+
+// Walls on the sides of each cell, each written once: the bottom and right sides, and the top
+// and left ones only on the edges of boards that don't wrap around.
+fn walls(board: &Board) -> Vec<(Position, Direction)> {
+    let mut walls = Vec::new();
+    for (x, y, _) in board.cells() {
+        for dir in DIRECTIONS {
+            let owned = match dir {
+                Direction::Down | Direction::Right => true,
+                Direction::Up | Direction::Left => board.step(x, y, &dir).is_none(),
+            };
+            if owned && board.has_wall(x, y, &dir) {
+                walls.push((xy_to_pos(x, y), dir));
+            }
+        }
+    }
+    walls
+}
+
+// The bits of a version, the board size, whether it wraps around, its movement, every cell, the
+// walls, the portals and the state. The board can be at most 16 cells wide and high, as for any
+// 'Position'.
+fn write_puzzle(board: &Board, state: &State) -> BitWriter {
+    let mut out = BitWriter {
+        bytes: Vec::new(),
+        len: 0,
+    };
+    out.write(VERSION, 2);
+    out.write(board.width() as u32 - 1, 4);
+    out.write(board.height() as u32 - 1, 4);
+    out.write(u32::from(board.is_toroidal()), 1);
+    out.write(
+        match board.movement() {
+            Movement::Slide => 0,
+            Movement::Step => 1,
+            Movement::Sticky => 2,
+        },
+        2,
+    );
+    for (_, _, cell) in board.cells() {
+        write_cell(&mut out, cell);
+    }
+    let walls = walls(board);
+    out.write(walls.len() as u32, 10);
+    for (pos, dir) in &walls {
+        out.write(u32::from(*pos), 8);
+        out.write(direction_bits(dir), 2);
+    }
+    out.write(board.portals().len() as u32, 8);
+    for (a, b) in board.portals() {
+        out.write(u32::from(*a), 8);
+        out.write(u32::from(*b), 8);
+    }
+    out.write(state.to_bits(), 32);
+    out
+}
+
+// The puzzle bits of 'encode_compact', the last byte padded with zeros.
+pub(crate) fn to_bytes(board: &Board, state: &State) -> Vec<u8> {
+    write_puzzle(board, state).bytes
+}
+
+// Writes a puzzle as a short URL safe string, the puzzle bits as unpadded base64url. A plain 8x8
+// puzzle takes about 25 characters.
+pub fn encode_compact(board: &Board, state: &State) -> String {
+    let mut out = write_puzzle(board, state);
+    // Zeros up to a whole number of characters, which decoding leaves unread.
+    out.write(0, (6 - out.len % 6) % 6);
+    let mut bits = BitReader {
+        bytes: &out.bytes,
+        at: 0,
+    };
+    (0..out.len / 6)
+        .map(|_| ALPHABET[bits.read(6).unwrap() as usize] as char)
+        .collect()
+}
+
+// Reads a puzzle written by 'encode_compact', None if it isn't one.
+pub fn decode_compact(input: &str) -> Option<(Board, State)> {
+    let mut out = BitWriter {
+        bytes: Vec::new(),
+        len: 0,
+    };
+    for c in input.bytes() {
+        let index = ALPHABET.iter().position(|a| *a == c)?;
+        out.write(index as u32, 6);
+    }
+    from_bytes(&out.bytes)
+}
+
+// Reads the puzzle bits of 'to_bytes', None if they aren't a puzzle.
+pub(crate) fn from_bytes(bytes: &[u8]) -> Option<(Board, State)> {
+    let mut input = BitReader { bytes, at: 0 };
+    if input.read(2)? != VERSION {
+        return None;
+    }
+    let (width, height) = (input.read(4)? as usize + 1, input.read(4)? as usize + 1);
+    let mut board = Board::new(width, height);
+    board.set_toroidal(input.read(1)? != 0);
+    board.set_movement(match input.read(2)? {
+        0 => Movement::Slide,
+        1 => Movement::Step,
+        2 => Movement::Sticky,
+        _ => return None,
+    });
+    for y in 0..height {
+        for x in 0..width {
+            board.set(x, y, read_cell(&mut input)?);
+        }
+    }
+    for _ in 0..input.read(10)? {
+        let pos = input.read_pos(width, height)?;
+        let dir = DIRECTIONS[input.read(2)? as usize];
+        board.add_wall(pos_to_x(&pos), pos_to_y(&pos), dir);
+    }
+    for _ in 0..input.read(8)? {
+        let a = input.read_pos(width, height)?;
+        let b = input.read_pos(width, height)?;
+        board.add_portal(a, b);
+    }
+    let state = State::from_bits(input.read(32)?);
+    let on_board = [PieceType::Main, PieceType::HelperOne, PieceType::HelperTwo]
+        .iter()
+        .all(|piece| {
+            let pos = state.pos(piece);
+            pos_to_x(&pos) < width && pos_to_y(&pos) < height
+        });
+    on_board.then_some((board, state))
+}
@@ -0,0 +1,155 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+
+// What playing a move sequence from the start does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Solves,
+    // Move, counting from 0, that can't be made or comes after the puzzle is solved.
+    IllegalAt(usize),
+    // Every move can be made but the puzzle isn't solved at the end.
+    Unfinished,
+}
+
+// One of the move sequences compared by 'diff_solutions'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolutionSummary {
+    pub length: usize,
+    // Moves made with the main piece, helper one and helper two.
+    pub piece_moves: [usize; 3],
+    pub outcome: Outcome,
+    // Whether it solves the puzzle in as few moves as possible.
+    pub optimal: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolutionDiff {
+    pub first: SolutionSummary,
+    pub second: SolutionSummary,
+    // Moves both start with, before they first differ.
+    pub common_prefix: usize,
+    // First state both pass through again after they differ, as the number of moves of the
+    // first and second sequence leading to it. None if they are the same or never meet again.
+    pub rejoin: Option<(usize, usize)>,
+    pub optimal_length: Option<usize>,
+}
+
+fn piece_index(piece: &PieceType) -> usize {
+    match piece {
+        PieceType::Main => 0,
+        PieceType::HelperOne => 1,
+        PieceType::HelperTwo => 2,
+    }
+}
+
+// The states 'moves' goes through from 'state', up to the first illegal move, with the outcome.
+fn play(board: &Board, state: State, moves: &[Move]) -> (Vec<State>, Outcome) {
+    let mut states = vec![state];
+    for (i, m) in moves.iter().enumerate() {
+        let current = states[states.len() - 1];
+        let next = apply_move(board, &current, m).filter(|_| !is_solved(board, &current));
+        let Some(next) = next else {
+            return (states, Outcome::IllegalAt(i));
+        };
+        states.push(next);
+    }
+    let outcome = if is_solved(board, &states[states.len() - 1]) {
+        Outcome::Solves
+    } else {
+        Outcome::Unfinished
+    };
+    (states, outcome)
+}
+
+fn summary(
+    board: &Board,
+    state: State,
+    moves: &[Move],
+    optimal_length: Option<usize>,
+) -> (Vec<State>, SolutionSummary) {
+    let (states, outcome) = play(board, state, moves);
+    let mut piece_moves = [0; 3];
+    for (piece, _) in moves {
+        piece_moves[piece_index(piece)] += 1;
+    }
+    let summary = SolutionSummary {
+        length: moves.len(),
+        piece_moves,
+        outcome,
+        optimal: outcome == Outcome::Solves && optimal_length == Some(moves.len()),
+    };
+    (states, summary)
+}
+
+// Compares two move sequences for the puzzle 'state' on 'board', such as the solutions of two
+// algorithms or a player's against the solver's. Searches the puzzle once for its optimal length.
+pub fn diff_solutions(
+    board: &Board,
+    state: State,
+    first: &[Move],
+    second: &[Move],
+) -> SolutionDiff {
+    let optimal_length = solve_puzzle(board, state).map(|solution| solution.moves.len());
+    let (first_states, first_summary) = summary(board, state, first, optimal_length);
+    let (second_states, second_summary) = summary(board, state, second, optimal_length);
+    let common_prefix = first.iter().zip(second).take_while(|(a, b)| a == b).count();
+    // States after the sequences first differ.
+    let first_after = first_states.get(common_prefix + 1..).unwrap_or_default();
+    let second_after = second_states.get(common_prefix + 1..).unwrap_or_default();
+    let rejoin = first_after.iter().enumerate().find_map(|(i, state)| {
+        let j = second_after.iter().position(|other| other == state)?;
+        Some((common_prefix + 1 + i, common_prefix + 1 + j))
+    });
+    SolutionDiff {
+        first: first_summary,
+        second: second_summary,
+        common_prefix,
+        rejoin,
+        optimal_length,
+    }
+}
+
+impl fmt::Display for SolutionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [main, helper_one, helper_two] = self.piece_moves;
+        write!(
+            f,
+            "{} moves (main {main}, helper 1 {helper_one}, helper 2 {helper_two}), ",
+            self.length
+        )?;
+        match self.outcome {
+            Outcome::Solves if self.optimal => write!(f, "solves it optimally"),
+            Outcome::Solves => write!(f, "solves it"),
+            Outcome::IllegalAt(i) => write!(f, "move {} is illegal", i + 1),
+            Outcome::Unfinished => write!(f, "doesn't solve it"),
+        }
+    }
+}
+
+impl fmt::Display for SolutionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "First: {}", self.first)?;
+        writeln!(f, "Second: {}", self.second)?;
+        match self.optimal_length {
+            Some(length) => writeln!(f, "Optimal: {length} moves")?,
+            None => writeln!(f, "Optimal: no solution")?,
+        }
+        if self.common_prefix == self.first.length.max(self.second.length) {
+            return write!(f, "Same moves");
+        }
+        if self.common_prefix == self.first.length.min(self.second.length) {
+            return write!(f, "One starts with all the moves of the other");
+        }
+        write!(f, "Same first {} moves", self.common_prefix)?;
+        match self.rejoin {
+            Some((first, second)) => write!(
+                f,
+                ", then the same state after move {first} of the first and {second} of the second"
+            ),
+            None => write!(f, ", never in the same state again"),
+        }
+    }
+}
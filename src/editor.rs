@@ -0,0 +1,223 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+use crate::validate::{validate, ValidationIssue};
+
+// Why an edit was refused, leaving the puzzle as it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditError {
+    OutOfBounds,
+    // The cell holds something other than what the edit needs, such as a goal where a blocker
+    // should go.
+    CellTaken,
+    Occupied(PieceType),
+    NotABlocker,
+    // Goals are numbered from 0 without gaps, so a new one has to take the next index.
+    GoalIndex(u8),
+    // Sides have to be 1 to 16 cells.
+    InvalidSize,
+    // Shrinking the board would leave a piece or portal off it.
+    CutOff,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::OutOfBounds => write!(f, "cell is outside the board"),
+            EditError::CellTaken => write!(f, "cell is not empty"),
+            EditError::Occupied(piece) => write!(f, "{piece:?} is on the cell"),
+            EditError::NotABlocker => write!(f, "cell has no blocker"),
+            EditError::GoalIndex(index) => write!(f, "goal {index} would leave a gap"),
+            EditError::InvalidSize => write!(f, "board sides must be 1 to {MAX_SIDE} cells"),
+            EditError::CutOff => write!(f, "a piece or portal would be left off the board"),
+        }
+    }
+}
+
+// Puzzle being edited. Every edit is checked before it is made, so the pieces are always on the
+// board, on cells they can stand on and apart, and the main piece on the start cell. Whether the
+// puzzle can be played is left to 'issues'.
+#[derive(Clone)]
+pub struct Editor {
+    board: Board,
+    state: State,
+    // The puzzle before each edit, the latest last.
+    history: Vec<(Board, State)>,
+}
+
+impl Editor {
+    pub fn new(board: Board, state: State) -> Self {
+        Self {
+            board,
+            state,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    // Problems that keep the puzzle from being solved, such as a missing goal.
+    pub fn issues(&self) -> Vec<ValidationIssue> {
+        validate(&self.board, &self.state)
+    }
+
+    // Takes back the last edit, false if there are none.
+    pub fn undo(&mut self) -> bool {
+        let Some((board, state)) = self.history.pop() else {
+            return false;
+        };
+        (self.board, self.state) = (board, state);
+        true
+    }
+
+    fn cell(&self, x: usize, y: usize) -> Result<Position, EditError> {
+        if x >= self.board.width() || y >= self.board.height() {
+            return Err(EditError::OutOfBounds);
+        }
+        Ok(xy_to_pos(x, y))
+    }
+
+    fn piece_at(&self, pos: Position) -> Option<PieceType> {
+        [PieceType::Main, PieceType::HelperOne, PieceType::HelperTwo]
+            .into_iter()
+            .find(|piece| self.state.pos(piece) == pos)
+    }
+
+    fn empty_cell(&self, x: usize, y: usize) -> Result<Position, EditError> {
+        let pos = self.cell(x, y)?;
+        if let Some(piece) = self.piece_at(pos) {
+            return Err(EditError::Occupied(piece));
+        }
+        if *self.board.get(x, y) != BoardPiece::Empty {
+            return Err(EditError::CellTaken);
+        }
+        Ok(pos)
+    }
+
+    fn commit(&mut self, board: Board, state: State) {
+        let before = (
+            core::mem::replace(&mut self.board, board),
+            core::mem::replace(&mut self.state, state),
+        );
+        self.history.push(before);
+    }
+
+    pub fn add_blocker(&mut self, x: usize, y: usize) -> Result<(), EditError> {
+        self.empty_cell(x, y)?;
+        let mut board = self.board.clone();
+        board.set(x, y, BoardPiece::Blocker);
+        self.commit(board, self.state);
+        Ok(())
+    }
+
+    pub fn remove_blocker(&mut self, x: usize, y: usize) -> Result<(), EditError> {
+        self.cell(x, y)?;
+        if *self.board.get(x, y) != BoardPiece::Blocker {
+            return Err(EditError::NotABlocker);
+        }
+        let mut board = self.board.clone();
+        board.set(x, y, BoardPiece::Empty);
+        self.commit(board, self.state);
+        Ok(())
+    }
+
+    // Puts 'piece' on x,y. The start cell moves along with the main piece, which can only go to
+    // an empty cell. Helpers can stand on anything but blockers, voids and lava fatal to them.
+    pub fn move_piece(&mut self, piece: PieceType, x: usize, y: usize) -> Result<(), EditError> {
+        let pos = self.cell(x, y)?;
+        match self.piece_at(pos) {
+            Some(other) if other != piece => return Err(EditError::Occupied(other)),
+            Some(_) => return Ok(()),
+            None => {}
+        }
+        let mut board = self.board.clone();
+        if piece == PieceType::Main {
+            self.empty_cell(x, y)?;
+            let from = self.state.main_pos();
+            board.set(pos_to_x(&from), pos_to_y(&from), BoardPiece::Empty);
+            board.set(x, y, BoardPiece::Start);
+        } else if matches!(
+            board.get(x, y),
+            BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+        ) || board.get(x, y).is_fatal_to(&piece)
+        {
+            return Err(EditError::CellTaken);
+        }
+        self.commit(board, self.state.with_pos(&piece, pos));
+        Ok(())
+    }
+
+    // Puts goal 'index' on x,y, moving it if it is already on the board. Goals go on empty cells,
+    // helpers may stand on them.
+    pub fn set_goal(&mut self, index: u8, x: usize, y: usize) -> Result<(), EditError> {
+        let pos = self.cell(x, y)?;
+        if *self.board.get(x, y) != BoardPiece::Empty {
+            return Err(EditError::CellTaken);
+        }
+        if index > goal_count(&self.board) {
+            return Err(EditError::GoalIndex(index));
+        }
+        let mut board = self.board.clone();
+        let old = self
+            .board
+            .cells()
+            .find(|(_, _, cell)| **cell == BoardPiece::Goal(index));
+        if let Some((old_x, old_y, _)) = old {
+            board.set(old_x, old_y, BoardPiece::Empty);
+        }
+        board.set(pos_to_x(&pos), pos_to_y(&pos), BoardPiece::Goal(index));
+        self.commit(board, self.state);
+        Ok(())
+    }
+
+    // Changes the size of the board, keeping what is on the cells that are left and adding
+    // empty ones on the right and bottom.
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), EditError> {
+        if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+            return Err(EditError::InvalidSize);
+        }
+        let fits = |pos: &Position| pos_to_x(pos) < width && pos_to_y(pos) < height;
+        let pieces = [
+            self.state.main_pos(),
+            self.state.helper_one_pos(),
+            self.state.helper_two_pos(),
+        ];
+        let portals = self.board.portals().iter().flat_map(|(a, b)| [a, b]);
+        if !pieces.iter().chain(portals).all(fits) {
+            return Err(EditError::CutOff);
+        }
+        let mut board = Board::new(width, height);
+        board.set_toroidal(self.board.is_toroidal());
+        board.set_movement(self.board.movement());
+        for (x, y, cell) in self.board.cells() {
+            if x >= width || y >= height {
+                continue;
+            }
+            if !matches!(cell, BoardPiece::Portal(_)) {
+                board.set(x, y, cell.clone());
+            }
+            for side in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                if self.board.has_wall(x, y, &side) {
+                    board.add_wall(x, y, side);
+                }
+            }
+        }
+        for (a, b) in self.board.portals() {
+            board.add_portal(*a, *b);
+        }
+        self.commit(board, self.state);
+        Ok(())
+    }
+}
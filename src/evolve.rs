@@ -0,0 +1,137 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use crate::canonical::fingerprint;
+use crate::generate::{generate, mutate_puzzle, MutationKind, PuzzleParams, Rng};
+use crate::solutions::count_optimal;
+use crate::solver::*;
+
+// What evolved puzzles are selected for, see 'EvolveParams::aims'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Aim {
+    LongestSolution,
+    // A single optimal solution, so the puzzle has one intended answer.
+    UniqueSolution,
+    FewestBlockers,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvolveParams {
+    // Puzzles kept from one generation to the next.
+    pub population: usize,
+    pub generations: usize,
+    // Puzzles are ranked by the first aim, ties broken by the next one and so on.
+    pub aims: Vec<Aim>,
+    // The first generation, generated at random. Its 'moves' and 'max_nodes' only apply to it.
+    pub start: PuzzleParams,
+    // Expansions allowed when solving a mutated puzzle, ones that take more are thrown away.
+    pub max_nodes: usize,
+}
+
+impl Default for EvolveParams {
+    fn default() -> Self {
+        Self {
+            population: 20,
+            generations: 50,
+            aims: vec![
+                Aim::LongestSolution,
+                Aim::UniqueSolution,
+                Aim::FewestBlockers,
+            ],
+            start: PuzzleParams {
+                moves: 1..=usize::MAX,
+                ..PuzzleParams::default()
+            },
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+// A puzzle of the population, with what it is ranked by.
+#[derive(Clone)]
+pub struct Evolved {
+    pub board: Board,
+    pub state: State,
+    pub optimal: usize,
+    // Optimal solutions, counted up to 2 and only if 'Aim::UniqueSolution' is asked for.
+    pub optimal_solutions: Option<usize>,
+    pub blockers: usize,
+    score: Vec<i64>,
+}
+
+impl Evolved {
+    fn new(board: Board, state: State, params: &EvolveParams) -> Option<Self> {
+        let options = SolveOptions {
+            max_nodes: Some(params.max_nodes),
+            ..SolveOptions::default()
+        };
+        let optimal = solve_puzzle_with(&board, state, &options)
+            .solution()?
+            .moves
+            .len();
+        let optimal_solutions = params
+            .aims
+            .contains(&Aim::UniqueSolution)
+            .then(|| count_optimal(&board, state));
+        let blockers = board
+            .cells()
+            .filter(|(_, _, cell)| **cell == BoardPiece::Blocker)
+            .count();
+        let score = params
+            .aims
+            .iter()
+            .map(|aim| match aim {
+                Aim::LongestSolution => optimal as i64,
+                Aim::UniqueSolution => i64::from(optimal_solutions == Some(1)),
+                Aim::FewestBlockers => -(blockers as i64),
+            })
+            .collect();
+        Some(Self {
+            board,
+            state,
+            optimal,
+            optimal_solutions,
+            blockers,
+            score,
+        })
+    }
+}
+
+// Evolves puzzles toward 'params.aims' from 'seed': each generation every puzzle of the population
+// picks the better of two random ones to mutate, and the best of the parents and their children
+// that are solvable within 'max_nodes' survive, one per puzzle up to symmetry. Gives the final
+// population, best first, empty if no first generation could be generated.
+pub fn evolve(seed: u64, params: &EvolveParams) -> Vec<Evolved> {
+    let mut rng = Rng::new(seed);
+    let mut population: Vec<Evolved> = (0..params.population)
+        .map_while(|_| generate(&mut rng, &params.start))
+        .filter_map(|(board, state)| Evolved::new(board, state, params))
+        .collect();
+    if population.is_empty() {
+        return population;
+    }
+    for _ in 0..params.generations {
+        let mut children = Vec::with_capacity(population.len());
+        for _ in 0..population.len() {
+            let (a, b) = (rng.below(population.len()), rng.below(population.len()));
+            let parent = &population[if population[a].score >= population[b].score {
+                a
+            } else {
+                b
+            }];
+            let kind = MutationKind::ALL[rng.below(MutationKind::ALL.len())];
+            let Some((board, state)) = mutate_puzzle(&parent.board, &parent.state, kind, &mut rng)
+            else {
+                continue;
+            };
+            children.extend(Evolved::new(board, state, params));
+        }
+        population.extend(children);
+        // Stable, so parents stay ahead of children as good.
+        population.sort_by_key(|puzzle| Reverse(puzzle.score.clone()));
+        let mut seen = HashSet::new();
+        population.retain(|puzzle| seen.insert(fingerprint(&puzzle.board, &puzzle.state)));
+        population.truncate(params.population);
+    }
+    population
+}
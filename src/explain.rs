@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+use crate::tools::format_move;
+
+// What ended a slide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    // The edge of the board.
+    Edge,
+    // A wall on the side of the cell the piece stopped on.
+    Wall,
+    // A blocker, a void or a one way cell that can't be entered going this way, on the given cell.
+    Blocker(Position),
+    // Another piece, on the given cell. On boards with sticky movement, the piece stopped next to.
+    Piece(PieceType, Position),
+    // Pieces only move one cell at a time on the board.
+    Step,
+}
+
+// A move of a solution with what made it stop and what it is for, see 'explain'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Annotation {
+    pub m: Move,
+    pub from: Position,
+    pub to: Position,
+    pub stop: Stop,
+    // The goal the main piece reached with this move, counting from 0.
+    pub goal: Option<u8>,
+    pub solves: bool,
+    // The first later move stopped by this piece before it moves again, by index in the solution,
+    // with the piece that move is of. The piece was put there to act as a wall for it.
+    pub wall_for: Option<(usize, PieceType)>,
+}
+
+fn xy(pos: &Position) -> (usize, usize) {
+    (pos_to_x(pos), pos_to_y(pos))
+}
+
+fn piece_name(piece: &PieceType) -> &'static str {
+    match piece {
+        PieceType::Main => "Main",
+        PieceType::HelperOne => "Helper1",
+        PieceType::HelperTwo => "Helper2",
+    }
+}
+
+// Why 'piece' of 'state', moving in direction 'dir', can't go on from 'pos'.
+fn stop_at(
+    board: &Board,
+    state: &State,
+    piece: &PieceType,
+    pos: &Position,
+    dir: &Direction,
+) -> Stop {
+    let others = [PieceType::Main, PieceType::HelperOne, PieceType::HelperTwo]
+        .into_iter()
+        .filter(|other| other != piece);
+    let (x, y) = xy(pos);
+    match board.movement() {
+        Movement::Step => return Stop::Step,
+        Movement::Sticky => {
+            let touching = others.clone().find_map(|other| {
+                let at = state.pos(&other);
+                let beside = [
+                    Direction::Up,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                ]
+                .iter()
+                .filter_map(|dir| board.step(x, y, dir))
+                .any(|(x, y)| xy_to_pos(x, y) == at);
+                beside.then_some(Stop::Piece(other, at))
+            });
+            if let Some(stop) = touching {
+                return stop;
+            }
+        }
+        Movement::Slide => {}
+    }
+    if board.has_wall(x, y, dir) {
+        return Stop::Wall;
+    }
+    let Some((x, y)) = board.step(x, y, dir) else {
+        return Stop::Edge;
+    };
+    let next = xy_to_pos(x, y);
+    // The piece's own starting cell counts too, it is still there until the move ends.
+    let blocking = others
+        .chain([*piece])
+        .find(|other| state.pos(other) == next);
+    match blocking {
+        Some(other) => Stop::Piece(other, next),
+        None => Stop::Blocker(next),
+    }
+}
+
+// Annotates each move of 'moves' from 'state', None if one of them is illegal. Every move stops
+// against something, and a piece stopping another one later on was put there to act as a wall.
+pub fn explain(board: &Board, state: State, moves: &[Move]) -> Option<Vec<Annotation>> {
+    let mut annotations: Vec<Annotation> = Vec::with_capacity(moves.len());
+    let mut current = state;
+    for m in moves {
+        let (piece, dir) = m;
+        let from = current.pos(piece);
+        let (to, last_dir) = slide(board, &current, &[], piece, &from, dir, |_| {})?;
+        let stop = stop_at(board, &current, piece, &to, &last_dir);
+        let next = apply_move(board, &current, m)?;
+        annotations.push(Annotation {
+            m: *m,
+            from,
+            to,
+            stop,
+            goal: (next.goals_reached() > current.goals_reached())
+                .then_some(current.goals_reached()),
+            solves: is_solved(board, &next),
+            wall_for: None,
+        });
+        current = next;
+    }
+    for i in 0..annotations.len() {
+        let piece = annotations[i].m.0;
+        annotations[i].wall_for = annotations[i + 1..]
+            .iter()
+            .position(|later| {
+                later.m.0 == piece || matches!(later.stop, Stop::Piece(p, _) if p == piece)
+            })
+            .map(|offset| (i + 1 + offset, annotations[i + 1 + offset].m.0))
+            .filter(|(_, stopped)| *stopped != piece);
+    }
+    Some(annotations)
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stop::Edge => write!(f, "the edge"),
+            Stop::Wall => write!(f, "a wall"),
+            Stop::Blocker(pos) => write!(f, "a blocker at {:?}", xy(pos)),
+            Stop::Piece(piece, pos) => write!(f, "{} at {:?}", piece_name(piece), xy(pos)),
+            Stop::Step => write!(f, "moving a single cell"),
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} to {:?}, stopped by {}",
+            format_move(&self.m),
+            xy(&self.to),
+            self.stop
+        )?;
+        if let Some(goal) = self.goal {
+            write!(f, ", reaching goal {}", goal + 1)?;
+        }
+        if self.solves {
+            write!(f, ", solving the puzzle")?;
+        }
+        if let Some((later, piece)) = self.wall_for {
+            write!(
+                f,
+                ", to act as a wall at {:?} for {} in move {}",
+                xy(&self.to),
+                piece_name(&piece),
+                later + 1
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use crate::solver::*;
+use crate::tools::puzzle_from_string;
+use crate::validate::validate;
+
+pub const SS_OK: i32 = 0;
+pub const SS_NULL_INPUT: i32 = 1;
+pub const SS_INVALID_UTF8: i32 = 2;
+pub const SS_PARSE_ERROR: i32 = 3;
+pub const SS_UNSOLVABLE: i32 = 4;
+// Read but failing 'validate', such as pieces on top of each other.
+pub const SS_INVALID_PUZZLE: i32 = 5;
+
+// Pieces are encoded as 0 = Main, 1 = Helper1, 2 = Helper2,
+// directions as 0 = Up, 1 = Down, 2 = Left, 3 = Right.
+#[repr(C)]
+pub struct SSMove {
+    pub piece: u8,
+    pub direction: u8,
+}
+
+// Result of 'ss_solve', 'moves' is null unless 'error' is SS_OK.
+#[repr(C)]
+pub struct SSSolution {
+    pub error: i32,
+    pub length: usize,
+    pub moves: *mut SSMove,
+}
+
+impl From<&Move> for SSMove {
+    fn from(m: &Move) -> Self {
+        let piece = match m.0 {
+            PieceType::Main => 0,
+            PieceType::HelperOne => 1,
+            PieceType::HelperTwo => 2,
+        };
+        let direction = match m.1 {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        };
+        Self { piece, direction }
+    }
+}
+
+fn solution(error: i32, moves: Vec<SSMove>) -> *mut SSSolution {
+    let length = moves.len();
+    let moves = if moves.is_empty() {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(moves.into_boxed_slice()) as *mut SSMove
+    };
+    Box::into_raw(Box::new(SSSolution {
+        error,
+        length,
+        moves,
+    }))
+}
+
+/// Solves a puzzle in the colon separated map format.
+///
+/// # Safety
+///
+/// `puzzle` must be null or point to a nul terminated string. The returned solution is never
+/// null and must be released with `ss_free_solution`.
+#[no_mangle]
+pub unsafe extern "C" fn ss_solve(puzzle: *const c_char) -> *mut SSSolution {
+    if puzzle.is_null() {
+        return solution(SS_NULL_INPUT, Vec::new());
+    }
+    let Ok(puzzle) = CStr::from_ptr(puzzle).to_str() else {
+        return solution(SS_INVALID_UTF8, Vec::new());
+    };
+    let Ok((board, state)) = puzzle_from_string(puzzle) else {
+        return solution(SS_PARSE_ERROR, Vec::new());
+    };
+    if !validate(&board, &state).is_empty() {
+        return solution(SS_INVALID_PUZZLE, Vec::new());
+    }
+    match solve_puzzle(&board, state) {
+        Some(found) => solution(SS_OK, found.moves.iter().map(SSMove::from).collect()),
+        None => solution(SS_UNSOLVABLE, Vec::new()),
+    }
+}
+
+/// Releases a solution returned by `ss_solve`.
+///
+/// # Safety
+///
+/// `solution` must be null or a pointer returned by `ss_solve` that has not been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn ss_free_solution(solution: *mut SSSolution) {
+    if solution.is_null() {
+        return;
+    }
+    let solution = Box::from_raw(solution);
+    if !solution.moves.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            solution.moves,
+            solution.length,
+        )));
+    }
+}
@@ -0,0 +1,131 @@
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use crate::solver::*;
+pub use crate::tools::Puzzle;
+use crate::validate::validate;
+
+impl<'a> Arbitrary<'a> for Direction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for PieceType {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[PieceType::Main, PieceType::HelperOne, PieceType::HelperTwo])?)
+    }
+}
+
+// Cells 'x', 'y' of a 'width' wide board that are not in 'taken' yet, picked at random.
+fn free_cell(
+    u: &mut Unstructured,
+    width: usize,
+    height: usize,
+    taken: &mut Vec<usize>,
+) -> Result<(usize, usize)> {
+    let free: Vec<usize> = (0..width * height)
+        .filter(|cell| !taken.contains(cell))
+        .collect();
+    let cell = *u.choose(&free)?;
+    taken.push(cell);
+    Ok((cell % width, cell / width))
+}
+
+// Boards up to 10x10 with a start, one to three goals in order and any other cell kind, walls and
+// portals on the rest.
+impl<'a> Arbitrary<'a> for Board {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let (width, height) = (u.int_in_range(1..=10)?, u.int_in_range(1..=10)?);
+        let mut board = Board::new(width, height);
+        board.set_toroidal(u.arbitrary()?);
+        board.set_movement(*u.choose(&[Movement::Slide, Movement::Step, Movement::Sticky])?);
+        for y in 0..height {
+            for x in 0..width {
+                let piece = match u.int_in_range(0..=15)? {
+                    0 => BoardPiece::Blocker,
+                    1 => BoardPiece::Pushable,
+                    2 => BoardPiece::Void,
+                    3 => {
+                        let slant = *u.choose(&[Slant::Slash, Slant::Backslash])?;
+                        BoardPiece::Mirror(slant, u.arbitrary()?)
+                    }
+                    4 => BoardPiece::OneWay(u.arbitrary()?),
+                    5 => BoardPiece::HelperGoal(u.arbitrary()?),
+                    6 => BoardPiece::Lava(u.arbitrary()?),
+                    _ => BoardPiece::Empty,
+                };
+                board.set(x, y, piece);
+            }
+        }
+        for _ in 0..u.int_in_range(0..=width * height / 4)? {
+            let (x, y) = (
+                u.int_in_range(0..=width - 1)?,
+                u.int_in_range(0..=height - 1)?,
+            );
+            board.add_wall(x, y, u.arbitrary()?);
+        }
+
+        // Cells of portals, goals and the start are kept apart so none overwrites another.
+        let mut taken = Vec::new();
+        let goals = u.int_in_range(1..=3)?;
+        if width * height < goals as usize + 1 {
+            return Err(Error::IncorrectFormat);
+        }
+        for goal in 0..goals {
+            let (x, y) = free_cell(u, width, height, &mut taken)?;
+            board.set(x, y, BoardPiece::Goal(goal));
+        }
+        let (x, y) = free_cell(u, width, height, &mut taken)?;
+        board.set(x, y, BoardPiece::Start);
+        for _ in 0..u.int_in_range(0..=2)? {
+            if width * height < taken.len() + 2 {
+                break;
+            }
+            let (ax, ay) = free_cell(u, width, height, &mut taken)?;
+            let (bx, by) = free_cell(u, width, height, &mut taken)?;
+            board.add_portal(xy_to_pos(ax, ay), xy_to_pos(bx, by));
+        }
+        Ok(board)
+    }
+}
+
+// Three distinct positions on a 10x10 board, without goals reached. Use 'Puzzle' for a state that
+// fits its board.
+impl<'a> Arbitrary<'a> for State {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut taken = Vec::new();
+        let mut pos = || free_cell(u, 10, 10, &mut taken).map(|(x, y)| xy_to_pos(x, y));
+        Ok(State::new(pos()?, pos()?, pos()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Puzzle {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let board: Board = u.arbitrary()?;
+        let mut free = Vec::new();
+        let mut start = None;
+        for (x, y, cell) in board.cells() {
+            match cell {
+                BoardPiece::Start => start = Some(xy_to_pos(x, y)),
+                BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void => {}
+                _ => free.push(xy_to_pos(x, y)),
+            }
+        }
+        let main = start.ok_or(Error::IncorrectFormat)?;
+        if free.len() < 2 {
+            return Err(Error::NotEnoughData);
+        }
+        let helper_one = free.swap_remove(u.choose_index(free.len())?);
+        let helper_two = *u.choose(&free)?;
+        let state = State::new(main, helper_one, helper_two);
+        if !validate(&board, &state).is_empty() {
+            return Err(Error::IncorrectFormat);
+        }
+        Ok(Self { board, state })
+    }
+}
@@ -0,0 +1,441 @@
+use core::ops::RangeInclusive;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::solver::*;
+use crate::validate::validate;
+
+// Puzzles thrown away before 'generate' gives up.
+const MAX_ATTEMPTS: usize = 1000;
+
+// Seeded random number generator (SplitMix64), so the same seed gives the same puzzles on every
+// platform and run. Everything random in the crate, puzzles, rounds, mutations and evolution,
+// takes a seed or one of these and nothing else.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..n, n has to be above 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// Shape and difficulty of generated puzzles: a board with blockers, one goal and both helpers.
+// Board sides can be up to 'MAX_SIDE' cells, the most a 'Position' holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleParams {
+    pub width: usize,
+    pub height: usize,
+    pub blockers: usize,
+    // Optimal solution lengths accepted, other puzzles are thrown away.
+    pub moves: RangeInclusive<usize>,
+    // Expansions allowed when solving a candidate, ones that take more are thrown away.
+    pub max_nodes: usize,
+}
+
+impl Default for PuzzleParams {
+    fn default() -> Self {
+        Self {
+            width: 8,
+            height: 8,
+            blockers: 10,
+            moves: 6..=10,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+// Random puzzle within 'params', None if none was found in MAX_ATTEMPTS tries.
+pub fn generate(rng: &mut Rng, params: &PuzzleParams) -> Option<(Board, State)> {
+    let cells = params.width * params.height;
+    if params.width > MAX_SIDE || params.height > MAX_SIDE || cells < params.blockers + 4 {
+        return None;
+    }
+    let options = SolveOptions {
+        max_nodes: Some(params.max_nodes),
+        ..SolveOptions::default()
+    };
+    for _ in 0..MAX_ATTEMPTS {
+        // Distinct cells for the blockers, then the goal, the main piece and the two helpers.
+        let mut picked = Vec::with_capacity(params.blockers + 4);
+        while picked.len() < params.blockers + 4 {
+            let cell = rng.below(cells);
+            if !picked.contains(&cell) {
+                picked.push(cell);
+            }
+        }
+        let xy = |cell: usize| (cell % params.width, cell / params.width);
+        let pos = |cell: usize| {
+            let (x, y) = xy(cell);
+            xy_to_pos(x, y)
+        };
+        let (blockers, pieces) = picked.split_at(params.blockers);
+
+        let mut board = Board::new(params.width, params.height);
+        for (cell, piece) in blockers
+            .iter()
+            .map(|cell| (cell, BoardPiece::Blocker))
+            .chain([
+                (&pieces[0], BoardPiece::Goal(0)),
+                (&pieces[1], BoardPiece::Start),
+            ])
+        {
+            let (x, y) = xy(*cell);
+            board.set(x, y, piece);
+        }
+        let state = State::new(pos(pieces[1]), pos(pieces[2]), pos(pieces[3]));
+        if !validate(&board, &state).is_empty() {
+            continue;
+        }
+        let solution = solve_puzzle_with(&board, state, &options).solution();
+        if solution.is_some_and(|solution| params.moves.contains(&solution.moves.len())) {
+            return Some((board, state));
+        }
+    }
+    None
+}
+
+// Puzzle from a seed of its own, so any puzzle of a set made this way can be made again alone.
+pub fn generate_seeded(seed: u64, params: &PuzzleParams) -> Option<(Board, State)> {
+    generate(&mut Rng::new(seed), params)
+}
+
+// 'count' puzzles from 'seed', fewer if 'params' can't be met.
+pub fn generate_many(seed: u64, count: usize, params: &PuzzleParams) -> Vec<(Board, State)> {
+    let mut rng = Rng::new(seed);
+    (0..count)
+        .map_while(|_| generate(&mut rng, params))
+        .collect()
+}
+
+// Rounds on a fixed board, as in the board game: each one puts the pieces down at random and picks
+// a goal, throwing away rounds that are solved in fewer than 'min_moves' moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundParams {
+    // Cells the goal is picked from, any empty cell if there are none.
+    pub goals: Vec<Position>,
+    pub min_moves: usize,
+    // Expansions allowed when solving a round, ones that take more are thrown away.
+    pub max_nodes: usize,
+}
+
+impl Default for RoundParams {
+    fn default() -> Self {
+        Self {
+            goals: Vec::new(),
+            min_moves: 2,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+// Random round on 'board', which should have no start cell or goals of its own, None if none was
+// found in MAX_ATTEMPTS tries. The pieces and the goal go on empty cells, the main piece on the
+// start cell it has to get back to.
+pub fn generate_round(
+    rng: &mut Rng,
+    board: &Board,
+    params: &RoundParams,
+) -> Option<(Board, State)> {
+    let empty: Vec<Position> = board
+        .cells()
+        .filter(|(_, _, cell)| **cell == BoardPiece::Empty)
+        .map(|(x, y, _)| xy_to_pos(x, y))
+        .collect();
+    let goals: Vec<Position> = if params.goals.is_empty() {
+        empty.clone()
+    } else {
+        params
+            .goals
+            .iter()
+            .copied()
+            .filter(|goal| empty.contains(goal))
+            .collect()
+    };
+    if goals.is_empty() || empty.len() < 4 {
+        return None;
+    }
+    let options = SolveOptions {
+        max_nodes: Some(params.max_nodes),
+        ..SolveOptions::default()
+    };
+    for _ in 0..MAX_ATTEMPTS {
+        // The goal, then distinct cells for the main piece and the two helpers.
+        let mut picked = Vec::with_capacity(4);
+        picked.push(goals[rng.below(goals.len())]);
+        while picked.len() < 4 {
+            let pos = empty[rng.below(empty.len())];
+            if !picked.contains(&pos) {
+                picked.push(pos);
+            }
+        }
+        let mut round = board.clone();
+        for (pos, piece) in [
+            (picked[0], BoardPiece::Goal(0)),
+            (picked[1], BoardPiece::Start),
+        ] {
+            round.set(pos_to_x(&pos), pos_to_y(&pos), piece);
+        }
+        let state = State::new(picked[1], picked[2], picked[3]);
+        if !validate(&round, &state).is_empty() {
+            continue;
+        }
+        let solution = solve_puzzle_with(&round, state, &options).solution();
+        if solution.is_some_and(|solution| solution.moves.len() >= params.min_moves) {
+            return Some((round, state));
+        }
+    }
+    None
+}
+
+// Endless rounds on 'board' from 'seed', ending early only if 'params' can't be met.
+pub fn rounds<'a>(
+    board: &'a Board,
+    seed: u64,
+    params: &'a RoundParams,
+) -> impl Iterator<Item = (Board, State)> + 'a {
+    let mut rng = Rng::new(seed);
+    core::iter::from_fn(move || generate_round(&mut rng, board, params))
+}
+
+// A small change to a puzzle, see 'mutate'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MutationKind {
+    AddBlocker,
+    RemoveBlocker,
+    // Moves a piece one cell, the start cell going along with the main piece.
+    NudgePiece,
+    // Moves a goal to another empty cell, keeping its index.
+    MoveGoal,
+}
+
+impl MutationKind {
+    pub const ALL: [MutationKind; 4] = [
+        MutationKind::AddBlocker,
+        MutationKind::RemoveBlocker,
+        MutationKind::NudgePiece,
+        MutationKind::MoveGoal,
+    ];
+}
+
+// A mutated puzzle, with its optimal length and how much longer that is than the original's. The
+// change is None if either puzzle can't be solved.
+#[derive(Clone)]
+pub struct Mutation {
+    pub board: Board,
+    pub state: State,
+    pub optimal: Option<usize>,
+    pub delta: Option<isize>,
+}
+
+// Cells of 'board' that are empty and have no piece on them.
+fn free_cells(board: &Board, state: &State) -> Vec<Position> {
+    board
+        .cells()
+        .map(|(x, y, cell)| (xy_to_pos(x, y), cell))
+        .filter(|(pos, cell)| **cell == BoardPiece::Empty && !state.is_occupied(*pos))
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+fn mutate_once(
+    board: &Board,
+    state: &State,
+    kind: MutationKind,
+    rng: &mut Rng,
+) -> Option<(Board, State)> {
+    let mut board = board.clone();
+    let mut state = *state;
+    let set = |board: &mut Board, pos: Position, piece: BoardPiece| {
+        board.set(pos_to_x(&pos), pos_to_y(&pos), piece);
+    };
+    let pick = |rng: &mut Rng, cells: &[Position]| {
+        (!cells.is_empty()).then(|| cells[rng.below(cells.len())])
+    };
+    match kind {
+        MutationKind::AddBlocker => {
+            let pos = pick(rng, &free_cells(&board, &state))?;
+            set(&mut board, pos, BoardPiece::Blocker);
+        }
+        MutationKind::RemoveBlocker => {
+            let blockers: Vec<Position> = board
+                .cells()
+                .filter(|(_, _, cell)| **cell == BoardPiece::Blocker)
+                .map(|(x, y, _)| xy_to_pos(x, y))
+                .collect();
+            let pos = pick(rng, &blockers)?;
+            set(&mut board, pos, BoardPiece::Empty);
+        }
+        MutationKind::NudgePiece => {
+            let piece = [PieceType::Main, PieceType::HelperOne, PieceType::HelperTwo][rng.below(3)];
+            let dir = [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ][rng.below(4)];
+            let from = state.pos(&piece);
+            let (x, y) = board.step(pos_to_x(&from), pos_to_y(&from), &dir)?;
+            let to = xy_to_pos(x, y);
+            if *board.at(&to) != BoardPiece::Empty || state.is_occupied(to) {
+                return None;
+            }
+            if piece == PieceType::Main {
+                set(&mut board, from, BoardPiece::Empty);
+                set(&mut board, to, BoardPiece::Start);
+            }
+            state = state.with_pos(&piece, to);
+        }
+        MutationKind::MoveGoal => {
+            let goals: Vec<(Position, BoardPiece)> = board
+                .cells()
+                .filter(|(_, _, cell)| matches!(cell, BoardPiece::Goal(_)))
+                .map(|(x, y, cell)| (xy_to_pos(x, y), cell.clone()))
+                .collect();
+            let (from, goal) = goals.get(rng.below(goals.len().max(1)))?.clone();
+            let to = pick(rng, &free_cells(&board, &state))?;
+            set(&mut board, from, BoardPiece::Empty);
+            set(&mut board, to, goal);
+        }
+    }
+    validate(&board, &state)
+        .is_empty()
+        .then_some((board, state))
+}
+
+// Applies a random mutation of 'kind' to the puzzle, without solving it. Tries MAX_ATTEMPTS times
+// for a mutated puzzle that passes 'validate', None if there was none, such as when there is no
+// blocker to remove.
+pub fn mutate_puzzle(
+    board: &Board,
+    state: &State,
+    kind: MutationKind,
+    rng: &mut Rng,
+) -> Option<(Board, State)> {
+    (0..MAX_ATTEMPTS).find_map(|_| mutate_once(board, state, kind, rng))
+}
+
+// Same as 'mutate_puzzle', solving the puzzle before and after.
+pub fn mutate(board: &Board, state: &State, kind: MutationKind, rng: &mut Rng) -> Option<Mutation> {
+    let (mutated, mutated_state) = mutate_puzzle(board, state, kind, rng)?;
+    let optimal = |board: &Board, state: State| {
+        solve_puzzle(board, state).map(|solution| solution.moves.len())
+    };
+    let before = optimal(board, *state);
+    let after = optimal(&mutated, mutated_state);
+    Some(Mutation {
+        delta: before
+            .zip(after)
+            .map(|(before, after)| after as isize - before as isize),
+        board: mutated,
+        state: mutated_state,
+        optimal: after,
+    })
+}
+
+// Random puzzle solvable in at most 'moves' moves, found without searching. Puts the pieces down
+// on 'board', which should have no start cell or goals, and makes up to 'moves' random moves
+// backwards from there, never going back to a state already seen. If the main piece ends up back
+// where it began, that cell becomes the start and a cell it stopped on in between the goal, and
+// playing the moves forwards solves the puzzle. Gives the puzzle with that solution, which may not
+// be the shortest, None if no walk came back in MAX_ATTEMPTS tries.
+pub fn reverse_shuffle(
+    rng: &mut Rng,
+    board: &Board,
+    moves: usize,
+) -> Option<(Board, State, Vec<Move>)> {
+    let free: Vec<Position> = board
+        .cells()
+        .filter(|(_, _, cell)| **cell == BoardPiece::Empty)
+        .map(|(x, y, _)| xy_to_pos(x, y))
+        .collect();
+    if free.len() < 4 {
+        return None;
+    }
+    let order = TieBreak::default();
+    for _ in 0..MAX_ATTEMPTS {
+        let mut picked = Vec::with_capacity(3);
+        while picked.len() < 3 {
+            let pos = free[rng.below(free.len())];
+            if !picked.contains(&pos) {
+                picked.push(pos);
+            }
+        }
+        let start = picked[0];
+        // The solved state first, then each state the previous one can be reached from.
+        let mut states = vec![State::new(start, picked[1], picked[2])];
+        let mut walk = Vec::with_capacity(moves);
+        for _ in 0..moves {
+            let last = &states[states.len() - 1];
+            let options: Vec<(Move, State)> = predecessors(board, last, &order, false)
+                .into_iter()
+                .filter(|(_, before)| !states.contains(before))
+                .collect();
+            if options.is_empty() {
+                break;
+            }
+            let (m, before) = options[rng.below(options.len())];
+            walk.push(m);
+            states.push(before);
+        }
+        let Some(end) = (2..states.len())
+            .rev()
+            .find(|i| states[*i].main_pos() == start)
+        else {
+            continue;
+        };
+        // Played forwards, the main piece last comes by the start at 'back' before finishing. A
+        // goal it doesn't stop on until after that can't be reached any sooner.
+        let back = (1..end)
+            .find(|i| states[*i].main_pos() == start)
+            .unwrap_or(end);
+        let before_back: Vec<Position> = states[back..=end].iter().map(State::main_pos).collect();
+        let stops: Vec<Position> = states[1..back]
+            .iter()
+            .map(State::main_pos)
+            .filter(|pos| !before_back.contains(pos))
+            .collect();
+        if stops.is_empty() {
+            continue;
+        }
+        let goal = stops[rng.below(stops.len())];
+        let mut puzzle = board.clone();
+        puzzle.set(pos_to_x(&start), pos_to_y(&start), BoardPiece::Start);
+        puzzle.set(pos_to_x(&goal), pos_to_y(&goal), BoardPiece::Goal(0));
+        let state = states[end];
+        if !validate(&puzzle, &state).is_empty() {
+            continue;
+        }
+        // The walk played forwards, cut short if the puzzle is solved on the way.
+        let mut solution = Vec::with_capacity(end);
+        let mut current = state;
+        for m in walk[..end].iter().rev() {
+            // Goals and the start cell don't change how pieces move, so every move still works.
+            current = apply_move(&puzzle, &current, m).expect("Walked move is legal.");
+            solution.push(*m);
+            if is_solved(&puzzle, &current) {
+                break;
+            }
+        }
+        if is_solved(&puzzle, &current) {
+            return Some((puzzle, state, solution));
+        }
+    }
+    None
+}
@@ -0,0 +1,185 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::solver::*;
+
+// Estimate of states the puzzle can't be solved from, they are never expanded.
+pub const UNSOLVABLE: u32 = u32::MAX;
+
+// Lower bound on the moves left to solve a puzzle, guiding 'solve_astar_with' and
+// 'solve_ida_star_with'. An estimate that is ever too high can make them miss optimal solutions.
+pub trait Heuristic {
+    fn estimate(&self, board: &Board, state: &State) -> u32;
+}
+
+// No guidance at all, A* then expands states in the same order as breadth first search.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zero;
+
+impl Heuristic for Zero {
+    fn estimate(&self, _board: &Board, _state: &State) -> u32 {
+        0
+    }
+}
+
+// Cells the main piece has to stop on from the given number of goals reached: the goals left in
+// order, then the start. None for cells missing from the board.
+pub(crate) fn route(board: &Board) -> Vec<Option<Position>> {
+    let find = |piece: BoardPiece| {
+        board
+            .cells()
+            .find(|(_, _, cell)| **cell == piece)
+            .map(|(x, y, _)| xy_to_pos(x, y))
+    };
+    (0..goal_count(board))
+        .map(|index| find(BoardPiece::Goal(index)))
+        .chain([find(BoardPiece::Start)])
+        .collect()
+}
+
+// Cheap bound from where the stops of the route are. Getting to a stop takes at least one move, and
+// two if it is in another row and column, as moves go in straight lines on boards without mirrors
+// or portals.
+#[derive(Debug, Clone)]
+pub struct GoalDistance {
+    route: Vec<Option<Position>>,
+    straight: bool,
+    // Moves needed between the stops of the route, from each stop on to the end.
+    rest: Vec<u32>,
+}
+
+impl GoalDistance {
+    pub fn new(board: &Board) -> Self {
+        let straight = !board
+            .cells()
+            .any(|(_, _, cell)| matches!(cell, BoardPiece::Mirror(..) | BoardPiece::Portal(_)));
+        let route = route(board);
+        let mut distance = Self {
+            rest: vec![0; route.len()],
+            route,
+            straight,
+        };
+        for i in (0..distance.route.len() - 1).rev() {
+            distance.rest[i] = match (distance.route[i], distance.route[i + 1]) {
+                (Some(from), Some(to)) => distance.rest[i + 1] + distance.leg(&from, &to),
+                _ => UNSOLVABLE,
+            };
+        }
+        distance
+    }
+
+    fn leg(&self, from: &Position, to: &Position) -> u32 {
+        let aligned = pos_to_x(from) == pos_to_x(to) || pos_to_y(from) == pos_to_y(to);
+        if from == to {
+            0
+        } else if aligned || !self.straight {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+impl Heuristic for GoalDistance {
+    fn estimate(&self, _board: &Board, state: &State) -> u32 {
+        let reached = state.goals_reached() as usize;
+        match self.route.get(reached) {
+            Some(Some(target)) if self.rest[reached] != UNSOLVABLE => {
+                self.leg(&state.main_pos(), target) + self.rest[reached]
+            }
+            _ => UNSOLVABLE,
+        }
+    }
+}
+
+// Cells 'piece' could stop on after a single move from 'pos' if a piece left out of 'state' could
+// be anywhere. Such a piece can end a slide on any cell passed, and one standing on the twin of a
+// portal keeps the moving piece going from the portal itself, so both ways through a portal are
+// followed. The left out pieces should be put on 'pos' in 'state', where they are never in the way.
+pub(crate) fn relaxed_stops(
+    board: &Board,
+    state: &State,
+    piece: &PieceType,
+    pos: Position,
+) -> Vec<Position> {
+    let mut seen = HashSet::new();
+    let mut stops = Vec::new();
+    let mut todo = vec![
+        (pos, Direction::Up),
+        (pos, Direction::Down),
+        (pos, Direction::Left),
+        (pos, Direction::Right),
+    ];
+    while let Some((from, dir)) = todo.pop() {
+        if !seen.insert((from, dir)) {
+            continue;
+        }
+        let Some((entered, next, next_dir)) = advance(board, state, &[], piece, &from, dir) else {
+            continue;
+        };
+        stops.push(entered);
+        todo.push((next, next_dir));
+        if next != entered {
+            stops.push(next);
+            todo.push((entered, next_dir));
+        }
+    }
+    stops
+}
+
+// The classic Ricochet Robots bound: the main piece still has to stop on each stop of the route,
+// each leg taking at least as many moves as it would if it could stop on any cell it passes.
+#[derive(Debug, Clone)]
+pub struct RicochetLowerBound {
+    // Indexed by goals reached and then main position.
+    to_finish: Vec<Vec<u32>>,
+}
+
+impl RicochetLowerBound {
+    pub fn new(board: &Board) -> Self {
+        let mut reverse = vec![Vec::new(); 256];
+        for (x, y, _) in board.cells() {
+            let pos = xy_to_pos(x, y);
+            let alone = State::new(pos, pos, pos);
+            for stop in relaxed_stops(board, &alone, &PieceType::Main, pos) {
+                reverse[stop as usize].push(pos);
+            }
+        }
+
+        // Built backwards, from the start cell that is the last stop of the route.
+        let route = route(board);
+        let mut to_finish = vec![Vec::new(); route.len()];
+        let mut rest = 0;
+        for (reached, target) in route.iter().enumerate().rev() {
+            let mut distances = vec![UNSOLVABLE; 256];
+            if let Some(target) = target.filter(|_| rest != UNSOLVABLE) {
+                distances[target as usize] = rest;
+                let mut queue = VecDeque::from([target]);
+                while let Some(pos) = queue.pop_front() {
+                    let next = distances[pos as usize] + 1;
+                    for from in &reverse[pos as usize] {
+                        if distances[*from as usize] == UNSOLVABLE {
+                            distances[*from as usize] = next;
+                            queue.push_back(*from);
+                        }
+                    }
+                }
+            }
+            // Stopping on the goal before this stop counts it, so the route on from there starts
+            // here.
+            rest = match reached.checked_sub(1).map(|before| route[before]) {
+                Some(Some(goal)) => distances[goal as usize],
+                _ => UNSOLVABLE,
+            };
+            to_finish[reached] = distances;
+        }
+        Self { to_finish }
+    }
+}
+
+impl Heuristic for RicochetLowerBound {
+    fn estimate(&self, _board: &Board, state: &State) -> u32 {
+        self.to_finish
+            .get(state.goals_reached() as usize)
+            .map_or(UNSOLVABLE, |distances| distances[state.main_pos() as usize])
+    }
+}
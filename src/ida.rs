@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::heuristic::{Heuristic, RicochetLowerBound, UNSOLVABLE};
+use crate::solver::*;
+
+// Depth first search below one bound of IDA*. Remembers the fewest moves each state was reached in
+// during this iteration, as getting there again in as many moves or more can't find anything new.
+struct Iteration<'a, H: ?Sized> {
+    board: &'a Board,
+    options: &'a SolveOptions,
+    deadline: Option<Instant>,
+    win: WinCondition,
+    heuristic: &'a H,
+    bound: usize,
+    // Smallest estimate found over the bound, the bound of the next iteration.
+    next_bound: Option<usize>,
+    reached: HashMap<State, usize>,
+    moves: Vec<Move>,
+}
+
+impl<H: Heuristic + ?Sized> Iteration<'_, H> {
+    fn dfs(
+        &mut self,
+        state: State,
+        estimate: u32,
+        stats: &mut SearchStats,
+    ) -> Result<Option<State>, SolveError> {
+        let depth = self.moves.len();
+        let next = depth + estimate as usize;
+        if next > self.bound {
+            self.next_bound = Some(self.next_bound.map_or(next, |bound| bound.min(next)));
+            return Ok(None);
+        }
+        if self.win.is_met(self.board, &state) {
+            return Ok(Some(state));
+        }
+        if self
+            .options
+            .max_nodes
+            .is_some_and(|max| stats.nodes_expanded >= max)
+        {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: self.bound,
+            });
+        }
+        stats.nodes_expanded += 1;
+        check_interrupted(self.options, self.deadline, stats, self.bound, 0)?;
+
+        for (m, next) in neighbourhood(self.board, &state, &self.options.tie_break) {
+            stats.nodes_generated += 1;
+            let rest = self.heuristic.estimate(self.board, &next);
+            if rest == UNSOLVABLE {
+                continue;
+            }
+            if self
+                .reached
+                .get(&next)
+                .is_some_and(|reached| *reached <= depth + 1)
+            {
+                stats.duplicates_pruned += 1;
+                continue;
+            }
+            self.reached.insert(next, depth + 1);
+            self.moves.push(m);
+            stats.max_queue_len = stats.max_queue_len.max(self.moves.len());
+            if let Some(found) = self.dfs(next, rest, stats)? {
+                return Ok(Some(found));
+            }
+            self.moves.pop();
+        }
+        Ok(None)
+    }
+}
+
+fn ida_star<H: Heuristic + ?Sized>(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    heuristic: &H,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let estimate = heuristic.estimate(board, &state);
+    if estimate == UNSOLVABLE {
+        return Ok(None);
+    }
+    let mut iteration = Iteration {
+        board,
+        options,
+        deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+        win: WinCondition::new(board),
+        heuristic,
+        bound: estimate as usize,
+        next_bound: None,
+        reached: HashMap::new(),
+        moves: Vec::new(),
+    };
+    loop {
+        if options.upper_bound.is_some_and(|max| iteration.bound > max) {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: iteration.bound,
+            });
+        }
+        if let Some(on_progress) = &options.on_progress {
+            on_progress(&SearchProgress {
+                depth: iteration.bound,
+                frontier: 0,
+                expanded: stats.nodes_expanded,
+            });
+        }
+        iteration.reached.clear();
+        iteration.reached.insert(state, 0);
+        if let Some(found) = iteration.dfs(state, estimate, stats)? {
+            return Ok(Some((found, iteration.moves)));
+        }
+        // Nothing went over the bound, so every reachable state has been tried.
+        let Some(bound) = iteration.next_bound.take() else {
+            return Ok(None);
+        };
+        iteration.bound = bound;
+    }
+}
+
+// Iterative deepening A*, depth first searches of growing length cut off by 'RicochetLowerBound'.
+// Gives the same solution as 'solve_puzzle_with', the first optimal one in 'tie_break' order.
+// Progress is reported at the start of each iteration, with the length searched up to as depth.
+// There is no queue, so 'max_queue' is ignored.
+pub fn solve_ida_star(board: &Board, state: State, options: &SolveOptions) -> SolveOutcome {
+    solve_ida_star_with(board, state, options, &RicochetLowerBound::new(board))
+}
+
+// Same as 'solve_ida_star' but cut off by 'heuristic'.
+pub fn solve_ida_star_with<H: Heuristic + ?Sized>(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    heuristic: &H,
+) -> SolveOutcome {
+    run_search(board, state, options, |board, state, options, stats| {
+        ida_star(board, state, options, heuristic, stats)
+    })
+}
+
+// Answer to whether a puzzle can be solved in a claimed number of moves, see 'verify_claim'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimResult {
+    // A solution of at most the claimed length, not necessarily the shortest.
+    Achievable(Vec<Move>),
+    // Every solution is longer than claimed.
+    TooShort,
+    Unsolvable,
+}
+
+// Checks whether the puzzle can be solved in at most 'claimed_moves' moves, as when bidding in
+// Ricochet Robots. A single depth first search below the claim, cut off by 'RicochetLowerBound',
+// that stops at the first solution it finds, so loose claims are settled without searching for the
+// shortest solution.
+pub fn verify_claim(board: &Board, state: State, claimed_moves: usize) -> ClaimResult {
+    let heuristic = RicochetLowerBound::new(board);
+    let estimate = heuristic.estimate(board, &state);
+    if estimate == UNSOLVABLE {
+        return ClaimResult::Unsolvable;
+    }
+    let options = SolveOptions::default();
+    let mut iteration = Iteration {
+        board,
+        options: &options,
+        deadline: None,
+        win: WinCondition::new(board),
+        heuristic: &heuristic,
+        bound: claimed_moves,
+        next_bound: None,
+        reached: HashMap::from([(state, 0)]),
+        moves: Vec::new(),
+    };
+    let found = iteration
+        .dfs(state, estimate, &mut SearchStats::default())
+        .unwrap_or_else(|err| panic!("Unlimited search failed: {err}."));
+    match (found, iteration.next_bound) {
+        (Some(_), _) => ClaimResult::Achievable(iteration.moves),
+        // Nothing went over the claim, so every reachable state has been tried.
+        (None, None) => ClaimResult::Unsolvable,
+        (None, Some(_)) => ClaimResult::TooShort,
+    }
+}
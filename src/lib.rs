@@ -0,0 +1,71 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod astar;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod bidirectional;
+mod bitboard;
+#[cfg(feature = "sqlite")]
+pub mod cache;
+pub mod canonical;
+pub mod compact;
+pub mod diff;
+pub mod editor;
+#[cfg(feature = "std")]
+pub mod evolve;
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod ffi;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod generate;
+#[cfg(feature = "std")]
+pub mod heuristic;
+#[cfg(feature = "std")]
+pub mod ida;
+#[cfg(feature = "std")]
+pub mod multi;
+#[cfg(feature = "std")]
+pub mod objective;
+#[cfg(feature = "std")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod pattern_db;
+#[cfg(feature = "tui")]
+pub mod play;
+#[cfg(feature = "std")]
+pub mod push;
+#[cfg(feature = "std")]
+pub mod render;
+pub mod replay;
+pub mod ricochet;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod session;
+pub mod share;
+#[cfg(feature = "std")]
+pub mod solution_db;
+#[cfg(feature = "std")]
+pub mod solutions;
+pub mod solver;
+#[cfg(feature = "std")]
+pub mod tablebase;
+#[cfg(feature = "proptest")]
+pub mod testing;
+pub mod tools;
+pub mod unsolvable;
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod weighted;
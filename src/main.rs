@@ -1,52 +1,916 @@
-use std::fs;
-use std::time::Instant;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::solver::{solve_puzzle, Board, Move, State};
-use crate::tools::puzzle_from_string;
-
-pub mod solver;
-pub mod tools;
+use ssolver::analysis::{analyze as analyze_space, distance_map, optimal_heatmap, reachability};
+#[cfg(feature = "sqlite")]
+use ssolver::batch::solve_stream_cached;
+use ssolver::batch::{
+    duplicates, solve_batch, solve_stream, solve_stream_unique, BatchResult, BatchSummary,
+    CsvReport,
+};
+use ssolver::bench::bench;
+#[cfg(feature = "sqlite")]
+use ssolver::cache::ResultCache;
+use ssolver::compact::{decode_compact, encode_compact};
+use ssolver::diff::diff_solutions;
+use ssolver::evolve::{evolve, EvolveParams};
+use ssolver::explain::explain;
+use ssolver::generate::{generate_many, generate_seeded, PuzzleParams};
+use ssolver::ida::{verify_claim, ClaimResult};
+use ssolver::pattern_db::PatternDatabase;
+use ssolver::push::solve_pushing;
+use ssolver::render::dot::search_graph_dot;
+use ssolver::render::svg::heatmap_to_svg;
+use ssolver::replay::Replay;
+use ssolver::search::{Algorithm, Solver};
+use ssolver::session::Session;
+use ssolver::share::{decode_share_code, encode_share_code};
+use ssolver::solutions::{count_solutions, disjoint_alternative, has_unique_optimal, solve_k_best};
+use ssolver::solver::{
+    hint, is_solved, solve_puzzle, solve_puzzle_with, solve_with_prefix, Board, BoardPiece,
+    Direction, Move, PieceType, SearchProgress, SolveError, SolveOptions, SolveOutcome, State,
+    TraceEvent,
+};
+use ssolver::tablebase::Tablebase;
+use ssolver::tools::{
+    format_move, parse_move, print_board, print_board_colored, print_moves, puzzle_from_string,
+    puzzle_to_string,
+};
+use ssolver::unsolvable::Unsolvability;
+use ssolver::validate::{validate, ValidationIssue};
 
 fn main() {
-    test1000();
+    #[cfg(feature = "tracing")]
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("solve") => solve(&load_puzzle(&args[2..]), &args[2..]),
+        Some("pdb") => build_pattern_db(
+            &load_puzzle(&args[2..]),
+            flag(&args[2..], "--out").expect("Missing --out path."),
+        ),
+        Some("tablebase") => build_tablebase(
+            &load_puzzle(&args[2..]),
+            flag(&args[2..], "--out").expect("Missing --out path."),
+        ),
+        Some("play") => play(&args[2..]),
+        Some("compare") => compare(
+            &load_puzzle(&args[2..]),
+            flag(&args[2..], "--first").expect("Missing --first path."),
+            flag(&args[2..], "--second").expect("Missing --second path."),
+        ),
+        Some("generate") => generate_puzzles(&args[2..]),
+        Some("evolve") => evolve_puzzles(&args[2..]),
+        Some("encode") => encode(&load_puzzle(&args[2..])),
+        Some("decode") => decode(
+            args.get(2).expect("Missing code argument."),
+            args.iter().any(|arg| arg == "--color"),
+        ),
+        Some("repl") => repl(),
+        Some("replay") => replay(args.get(2).expect("Missing file argument.")),
+        Some("duplicates") => print_duplicates(args.get(2).expect("Missing file argument.")),
+        Some("serve") => serve(args.get(2).map_or("127.0.0.1:3000", String::as_str)),
+        Some("batch") => batch(
+            args.get(2).expect("Missing file argument."),
+            flag(&args[3..], "--csv"),
+            flag(&args[3..], "--cache"),
+            threads(&args[3..]),
+            flag(&args[3..], "--timeout").map(|ms| {
+                Duration::from_millis(ms.parse().expect("Invalid timeout in milliseconds."))
+            }),
+            args[3..].iter().any(|arg| arg == "--unique"),
+        ),
+        Some("bench") => run_bench(&args[2..]),
+        Some("analyze") => analyze(&args[2..]),
+        _ => test1000(threads(&args[1..])),
+    }
 }
 
-fn test1000() {
-    const FILE_NAME: &str = "test_input/maps_moves.txt";
-    let input = fs::read_to_string(FILE_NAME).expect("File not found.");
+// Parses a puzzle given on the command line, stopping with the reason if it can't be read.
+fn read_puzzle(puzzle: &str) -> (Board, State) {
+    puzzle_from_string(puzzle).unwrap_or_else(|err| panic!("Invalid puzzle: {err}."))
+}
+
+// Reads a puzzle given either directly as a map string or as a file and line number (default 0).
+// Flags can follow either.
+fn load_puzzle(args: &[String]) -> String {
+    let source = args.first().expect("Missing puzzle or file argument.");
+    if source.starts_with("map:") {
+        return source.clone();
+    }
+    let index = args
+        .get(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map_or(0, |i| i.parse().expect("Invalid line number."));
+    let input = fs::read_to_string(source).expect("File not found.");
+    input
+        .lines()
+        .nth(index)
+        .expect("Line not found.")
+        .to_string()
+}
+
+// Solves a single puzzle with the algorithm given with '--algorithm', showing search progress on
+// stderr. A pattern database given with '--pdb' guides the search, which then defaults to A*.
+// A tablebase given with '--tablebase' answers without searching. '--threads' limits the threads of
+// the parallel search, '--upper-bound' and '--max-depth' the length of solutions looked for.
+// '--explain' says what each move of the solution stops against and what it is for. '--claim' only
+// checks that the puzzle can be solved in that many moves. '--prefix' gives a file of moves the
+// solution has to start with and prints the best moves to follow them. '--forbid x:y,x:y' turns
+// cells into lava no piece may enter for this search only. '--k-best N' prints the N shortest
+// solutions and '--alternative' one sharing no move with the optimal solution as well. '--trace'
+// writes each step of a breadth first search to a file as a line of JSON and '--depth-stats' lists
+// the size of each of its layers. '--color' shows the board in color with the path of the main
+// piece. Puzzles with pushable blockers are solved by 'solve_pushing', which takes none of these
+// options.
+fn solve(puzzle: &str, args: &[String]) {
+    let (mut board, state) = read_puzzle(puzzle);
+    for cell in flag(args, "--forbid")
+        .into_iter()
+        .flat_map(|cells| cells.split(','))
+    {
+        let coordinates = cell
+            .split_once(':')
+            .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+        let (x, y) = coordinates.expect("Invalid forbidden cell.");
+        board.set(x, y, BoardPiece::Lava(None));
+    }
+    let mut issues = validate(&board, &state);
+    let pushing = issues.contains(&ValidationIssue::PushableBlockers);
+    issues.retain(|issue| *issue != ValidationIssue::PushableBlockers);
+    if !issues.is_empty() {
+        for issue in issues {
+            eprintln!("Invalid puzzle: {issue}.");
+        }
+        return;
+    }
+    if pushing {
+        // Only this search moves the blockers, the options below don't apply to it.
+        match solve_pushing(&board, state) {
+            Some((_, _, moves)) => {
+                println!("Solved in {} moves:", moves.len());
+                print_moves(&moves);
+            }
+            None => println!("No solution."),
+        }
+        return;
+    }
+    if let Some(claim) = flag(args, "--claim") {
+        let claim = claim.parse().expect("Invalid claim.");
+        match verify_claim(&board, state, claim) {
+            ClaimResult::Achievable(moves) => {
+                println!("Can be solved in {claim} moves, {} here:", moves.len());
+                print_moves(&moves);
+            }
+            ClaimResult::TooShort => println!("Can't be solved in {claim} moves."),
+            ClaimResult::Unsolvable => println!("No solution."),
+        }
+        return;
+    }
+    if let Some(k) = flag(args, "--k-best") {
+        let k = k.parse().expect("Invalid solution count.");
+        let best = solve_k_best(&board, state, k);
+        if best.is_empty() {
+            println!("No solution.");
+        }
+        for (i, moves) in best.iter().enumerate() {
+            println!("Solution {} in {} moves:", i + 1, moves.len());
+            print_moves(moves);
+        }
+        return;
+    }
+    if args.iter().any(|arg| arg == "--alternative") {
+        let Some(solution) = solve_puzzle(&board, state) else {
+            println!("No solution.");
+            return;
+        };
+        println!("Solved in {} moves:", solution.moves.len());
+        print_moves(&solution.moves);
+        match disjoint_alternative(&board, state, &solution.moves) {
+            Some(moves) => {
+                println!("Alternative in {} moves:", moves.len());
+                print_moves(&moves);
+            }
+            None => println!("Every solution shares a move with it."),
+        }
+        return;
+    }
+    if let Some(path) = flag(args, "--prefix") {
+        let prefix = read_moves(path);
+        match solve_with_prefix(&board, state, &prefix, &SolveOptions::default()) {
+            Ok(SolveOutcome::Solved(solution)) => {
+                println!(
+                    "Solved in {} moves, {} after the prefix:",
+                    solution.moves.len(),
+                    solution.moves.len() - prefix.len()
+                );
+                print_moves(&solution.moves[prefix.len()..]);
+            }
+            Ok(_) => println!("No solution starts with the prefix."),
+            Err(err) => println!("Invalid prefix: {err}."),
+        }
+        return;
+    }
+    let pdb = flag(args, "--pdb").map(|path| {
+        let input = BufReader::new(File::open(path).expect("File not found."));
+        PatternDatabase::read(input, &board).expect("Failed to read pattern database.")
+    });
+    let default = if pdb.is_some() {
+        Algorithm::Astar
+    } else {
+        Algorithm::Bfs
+    };
+    let algorithm = flag(args, "--algorithm").map_or(default, |name| {
+        name.parse().unwrap_or_else(|err: String| panic!("{err}"))
+    });
+    let mut builder = Solver::builder().algorithm(algorithm);
+    if let Some(pdb) = pdb {
+        builder = builder.heuristic(pdb);
+    }
+    if let Some(path) = flag(args, "--tablebase") {
+        let input = BufReader::new(File::open(path).expect("File not found."));
+        let tablebase = Tablebase::read(input, &board).expect("Failed to read tablebase.");
+        builder = builder.tablebase(Arc::new(tablebase));
+    }
+    if let Some(threads) = threads(args) {
+        builder = builder.threads(threads);
+    }
+    if let Some(bound) = flag(args, "--upper-bound") {
+        builder = builder.upper_bound(bound.parse().expect("Invalid upper bound."));
+    }
+    if let Some(depth) = flag(args, "--max-depth") {
+        builder = builder.max_depth(depth.parse().expect("Invalid max depth."));
+    }
+    if let Some(path) = flag(args, "--trace") {
+        let out = Mutex::new(BufWriter::new(
+            File::create(path).expect("Failed to create file."),
+        ));
+        builder = builder.on_trace(move |event: &TraceEvent| {
+            writeln!(out.lock().unwrap(), "{}", event.to_json()).expect("Failed to write trace.");
+        });
+    }
+    let solver = builder
+        .on_progress(|p: &SearchProgress| {
+            eprint!(
+                "\rdepth {:>3}  frontier {:>10}  expanded {:>10}",
+                p.depth, p.frontier, p.expanded
+            );
+        })
+        .progress_interval(100_000)
+        .build();
+    let outcome = solver.solve(&board, state);
+    eprintln!();
+    if args.iter().any(|arg| arg == "--depth-stats") {
+        for (depth, layer) in outcome.stats().depths.iter().enumerate() {
+            eprintln!(
+                "depth {depth:>3}  frontier {:>10}  visited {:>10}",
+                layer.frontier, layer.visited
+            );
+        }
+    }
+    match outcome {
+        SolveOutcome::Solved(solution) => {
+            println!("Solved in {} moves:", solution.moves.len());
+            if args.iter().any(|arg| arg == "--explain") {
+                for annotation in explain(&board, state, &solution.moves).unwrap() {
+                    println!("{annotation}");
+                }
+            } else {
+                print_moves(&solution.moves);
+            }
+            if args.iter().any(|arg| arg == "--color") {
+                print_board_colored(&board, state, &solution.moves);
+            }
+        }
+        SolveOutcome::Unsolvable(stats) => {
+            println!(
+                "No solution: {}.",
+                Unsolvability::new(&board, &state, &stats)
+            )
+        }
+        SolveOutcome::LimitReached(SolveError::BudgetExceeded { lower_bound }, _) => {
+            println!("No solution within the upper bound, at least {lower_bound} moves are needed.")
+        }
+        SolveOutcome::NotWithin(max_depth, _) => println!("No solution within {max_depth} moves."),
+        SolveOutcome::LimitReached(err, _) => panic!("Unlimited search failed: {err}."),
+    }
+}
+
+// Builds the pattern database of a puzzle's board and writes it to 'out'.
+fn build_pattern_db(puzzle: &str, out: &str) {
+    let (board, _) = read_puzzle(puzzle);
     let before = Instant::now();
-    let (send, tasks) = crossbeam::channel::unbounded();
-    let mut received = 0;
-    rayon::scope(|handle| {
-        let mut submitted = 0;
-        for line in input.lines() {
-            let line = line.to_string();
-            submitted += 1;
-            let s_c = send.clone();
-            handle.spawn(move |_| {
-                let res = solve_board(line);
-                s_c.send(res).unwrap();
+    let pdb = PatternDatabase::new(&board);
+    let file = BufWriter::new(File::create(out).expect("Failed to create file."));
+    pdb.write(file).expect("Failed to write pattern database.");
+    println!(
+        "Pattern database built in {}ms.",
+        before.elapsed().as_millis()
+    );
+}
+
+// Builds the tablebase of a puzzle's board and writes it to 'out'.
+fn build_tablebase(puzzle: &str, out: &str) {
+    let (board, _) = read_puzzle(puzzle);
+    let before = Instant::now();
+    let tablebase = Tablebase::new(&board);
+    let file = BufWriter::new(File::create(out).expect("Failed to create file."));
+    tablebase.write(file).expect("Failed to write tablebase.");
+    println!("Tablebase built in {}ms.", before.elapsed().as_millis());
+}
+
+// Lists the lines of a puzzle file that hold the same puzzle.
+fn print_duplicates(path: &str) {
+    let input = BufReader::new(File::open(path).expect("File not found."));
+    let groups = duplicates(input).expect("Failed to read input.");
+    for group in &groups {
+        let lines: Vec<String> = group.iter().map(usize::to_string).collect();
+        println!("{}", lines.join(" "));
+    }
+    eprintln!("{} puzzles with duplicates.", groups.len());
+}
+
+// Plays a puzzle given as for 'solve', or with '--resume <file>' a game saved by '--save <file>'
+// or the repl's 'save', saving the game on quitting with '--save'.
+#[cfg(feature = "tui")]
+fn play(args: &[String]) {
+    let session = match flag(args, "--resume") {
+        Some(path) => {
+            let saved = fs::read_to_string(path).expect("File not found.");
+            Session::from_json(&saved).expect("Invalid saved game.")
+        }
+        None => {
+            let (board, state) = read_puzzle(&load_puzzle(args));
+            if let Some(issue) = validate(&board, &state).first() {
+                eprintln!("Invalid puzzle: {issue}.");
+                return;
+            }
+            Session::new(board, state)
+        }
+    };
+    let session = ssolver::play::play_session(session).expect("Terminal error.");
+    if let Some(path) = flag(args, "--save") {
+        fs::write(path, session.to_json()).expect("Failed to save the game.");
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn play(_args: &[String]) {
+    eprintln!("The play subcommand requires the 'tui' feature.");
+}
+
+// Moves one per line as printed by 'solve', blank lines skipped.
+fn read_moves(path: &str) -> Vec<Move> {
+    fs::read_to_string(path)
+        .expect("File not found.")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_move(line).unwrap_or_else(|| panic!("Invalid move '{line}'.")))
+        .collect()
+}
+
+// Compares two solutions of a puzzle, each read from a file of moves.
+fn compare(puzzle: &str, first: &str, second: &str) {
+    let (board, state) = read_puzzle(puzzle);
+    println!(
+        "{}",
+        diff_solutions(&board, state, &read_moves(first), &read_moves(second))
+    );
+}
+
+// Evolves puzzles with long solutions from '--seed', printing the final population best first.
+// '--generations' defaults to 50.
+fn evolve_puzzles(args: &[String]) {
+    let seed = seed(args);
+    let mut params = EvolveParams::default();
+    if let Some(generations) = flag(args, "--generations") {
+        params.generations = generations.parse().expect("Invalid generation count.");
+    }
+    println!("Evolved from seed {seed}:");
+    for puzzle in evolve(seed, &params) {
+        let unique = match puzzle.optimal_solutions {
+            Some(1) => ", unique",
+            _ => "",
+        };
+        println!(
+            "{} moves{unique}, {} blockers: {}",
+            puzzle.optimal,
+            puzzle.blockers,
+            puzzle_to_string(&puzzle.board, &puzzle.state)
+        );
+    }
+}
+
+fn encode(puzzle: &str) {
+    let (board, state) = read_puzzle(puzzle);
+    println!("Compact: {}", encode_compact(&board, &state));
+    println!("Share code: {}", encode_share_code(&board, &state));
+}
+
+// Prints the board plainly, or in color with the path of the main piece over 'moves' if 'color'.
+fn show_board(board: &Board, state: State, moves: &[Move], color: bool) {
+    if color {
+        print_board_colored(board, state, moves);
+    } else {
+        print_board(board, state);
+    }
+}
+
+// Prints the puzzle of a share code or compact string, in color if 'color'.
+fn decode(code: &str, color: bool) {
+    let decoded = decode_share_code(code)
+        .or_else(|err| decode_compact(code).ok_or(err))
+        .map(|(board, state)| (puzzle_to_string(&board, &state), board, state));
+    match decoded {
+        Ok((puzzle, board, state)) => {
+            println!("{puzzle}");
+            show_board(&board, state, &[], color);
+        }
+        Err(err) => eprintln!("Invalid code: {err}."),
+    }
+}
+
+// Explores puzzles one command per line from stdin: 'load' followed by a puzzle as for 'solve',
+// 'show', 'move <piece> <direction>' with the piece 'main', '1' or '2', 'undo', 'redo', 'reset',
+// 'hint', 'solve', 'save <file>', 'restore <file>', 'color' to turn colored boards on or off and
+// 'quit'. With colors on 'solve' also shows the path of the main piece.
+fn repl() {
+    let mut session: Option<Session> = None;
+    let mut color = false;
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Failed to write prompt.");
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.expect("Failed to read command.");
+        let words: Vec<String> = line.split_whitespace().map(String::from).collect();
+        let Some(command) = words.first() else {
+            continue;
+        };
+        if command == "quit" || command == "exit" {
+            break;
+        }
+        if command == "color" {
+            color = !color;
+            println!("Colors {}.", if color { "on" } else { "off" });
+            continue;
+        }
+        if command == "load" {
+            let source = words.get(1).filter(|source| {
+                source.starts_with("map:") || fs::metadata(source).is_ok_and(|meta| meta.is_file())
             });
+            if source.is_none() {
+                println!("Expected a map string or an existing file.");
+                continue;
+            }
+            let (board, state) = match puzzle_from_string(&load_puzzle(&words[1..])) {
+                Ok(puzzle) => puzzle,
+                Err(err) => {
+                    println!("Invalid puzzle: {err}.");
+                    continue;
+                }
+            };
+            let issues = validate(&board, &state);
+            for issue in &issues {
+                println!("Invalid puzzle: {issue}.");
+            }
+            if issues.is_empty() {
+                show_board(&board, state, &[], color);
+                session = Some(Session::new(board, state));
+            }
+            continue;
+        }
+        if let ("restore", [path]) = (command.as_str(), &words[1..]) {
+            let saved = match fs::read_to_string(path) {
+                Ok(saved) => saved,
+                Err(err) => {
+                    println!("Failed to read {path}: {err}.");
+                    continue;
+                }
+            };
+            match Session::from_json(&saved) {
+                Some(restored) => {
+                    show_board(restored.board(), restored.state(), &[], color);
+                    session = Some(restored);
+                }
+                None => println!("Not a saved game."),
+            }
+            continue;
+        }
+        let Some(session) = &mut session else {
+            println!("No puzzle loaded, use 'load'.");
+            continue;
+        };
+        let (board, state) = (session.board().clone(), session.state());
+        match (command.as_str(), &words[1..]) {
+            ("show", []) => show_board(&board, state, &[], color),
+            ("move", [piece, dir]) => {
+                let piece = match piece.as_str() {
+                    "main" => PieceType::Main,
+                    "1" => PieceType::HelperOne,
+                    "2" => PieceType::HelperTwo,
+                    _ => {
+                        println!("Unknown piece {piece}, expected main, 1 or 2.");
+                        continue;
+                    }
+                };
+                let dir = match dir.as_str() {
+                    "up" => Direction::Up,
+                    "down" => Direction::Down,
+                    "left" => Direction::Left,
+                    "right" => Direction::Right,
+                    _ => {
+                        println!("Unknown direction {dir}, expected up, down, left or right.");
+                        continue;
+                    }
+                };
+                if !session.apply((piece, dir)) {
+                    println!("That piece can't move that way.");
+                    continue;
+                }
+                show_board(&board, session.state(), &[], color);
+                if session.is_solved() {
+                    println!("Solved in {} moves.", session.history().len());
+                }
+            }
+            ("undo", []) => {
+                if session.undo().is_none() {
+                    println!("Nothing to undo.");
+                }
+                show_board(&board, session.state(), &[], color);
+            }
+            ("redo", []) => {
+                if session.redo().is_none() {
+                    println!("Nothing to redo.");
+                }
+                show_board(&board, session.state(), &[], color);
+            }
+            ("reset", []) => {
+                session.reset();
+                show_board(&board, session.state(), &[], color);
+            }
+            ("hint", []) => match hint(&board, state) {
+                Some(m) => println!("{}", format_move(&m)),
+                None => println!("No move helps, the puzzle is solved or can't be solved."),
+            },
+            ("save", [path]) => match fs::write(path, session.to_json()) {
+                Ok(()) => println!("Saved to {path}."),
+                Err(err) => println!("Failed to save to {path}: {err}."),
+            },
+            ("solve", []) => match solve_puzzle(&board, state) {
+                Some(solution) => {
+                    println!("Solved in {} more moves:", solution.moves.len());
+                    print_moves(&solution.moves);
+                    if color {
+                        print_board_colored(&board, state, &solution.moves);
+                    }
+                }
+                None => println!("No solution from here."),
+            },
+            _ => println!(
+                "Unknown command, expected load, show, move <piece> <direction>, undo, redo, \
+                 reset, hint, solve, save <file>, restore <file>, color or quit."
+            ),
+        }
+    }
+}
+
+// Steps through a game recorded as a 'Replay', printing the board after each move.
+fn replay(path: &str) {
+    let input = fs::read_to_string(path).expect("File not found.");
+    let checked = Replay::parse(&input).and_then(|replay| Ok((replay.play()?, replay)));
+    let ((board, states), replay) = match checked {
+        Ok(checked) => checked,
+        Err(err) => {
+            eprintln!("Invalid replay: {err}.");
+            return;
         }
-        while received < submitted {
-            let _next = tasks.recv().unwrap().unwrap();
-            received += 1;
+    };
+    print_board(&board, states[0]);
+    for ((time, m), state) in replay.moves.iter().zip(&states[1..]) {
+        println!(
+            "[{}:{:02}.{:03}] {}",
+            time / 60_000,
+            time / 1000 % 60,
+            time % 1000,
+            format_move(m)
+        );
+        print_board(&board, *state);
+    }
+    let optimal = solve_puzzle(&board, states[0]).map(|solution| solution.moves.len());
+    let last = states[states.len() - 1];
+    match (is_solved(&board, &last), optimal) {
+        (true, Some(optimal)) => {
+            println!(
+                "Solved in {} moves, optimal is {optimal}.",
+                replay.moves.len()
+            )
         }
+        (_, None) => println!(
+            "Not solved after {} moves, the puzzle has no solution.",
+            replay.moves.len()
+        ),
+        (false, Some(optimal)) => println!(
+            "Not solved after {} moves, {} more needed, optimal is {optimal}.",
+            replay.moves.len(),
+            solve_puzzle(&board, last).map_or(0, |solution| solution.moves.len())
+        ),
+    }
+}
+
+#[cfg(feature = "server")]
+fn serve(addr: &str) {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start runtime.");
+    runtime
+        .block_on(ssolver::server::serve(addr))
+        .expect("Server error.");
+}
+
+#[cfg(not(feature = "server"))]
+fn serve(_addr: &str) {
+    eprintln!("The serve subcommand requires the 'server' feature.");
+}
+
+// Generates puzzles from '--seed' (default 0) and times every algorithm on them. '--count',
+// '--width', '--height', '--blockers' and '--moves min-max' shape the puzzles.
+fn run_bench(args: &[String]) {
+    let params = puzzle_params(args);
+    let seed = flag(args, "--seed").map_or(0, |seed| seed.parse().expect("Invalid seed."));
+    let count = number(args, "--count").unwrap_or(20);
+
+    let before = Instant::now();
+    let puzzles = generate_many(seed, count, &params);
+    println!(
+        "Generated {} puzzles from seed {seed} in {}ms.",
+        puzzles.len(),
+        before.elapsed().as_millis()
+    );
+    for report in bench(&puzzles, &Algorithm::ALL, &SolveOptions::default()) {
+        println!("{report}");
+    }
+}
+
+// Generates '--count' (default 10) puzzles shaped as for 'bench' and prints them as JSON with their
+// optimal length and seed. Puzzle 'i' comes from seed '--seed' plus 'i', so each one can be made
+// again alone. Without '--seed' one is picked from the clock. '--unique' leaves out puzzles with
+// more than one optimal solution.
+fn generate_puzzles(args: &[String]) {
+    let params = puzzle_params(args);
+    let seed = seed(args);
+    let count = number(args, "--count").unwrap_or(10) as u64;
+    let unique = args.iter().any(|arg| arg == "--unique");
+    let puzzles: Vec<json::JsonValue> = (seed..seed.saturating_add(count))
+        .filter_map(|seed| {
+            let (board, state) = generate_seeded(seed, &params)?;
+            if unique && !has_unique_optimal(&board, state) {
+                return None;
+            }
+            let optimal = solve_puzzle(&board, state)?.moves.len();
+            Some(json::object! {
+                map: puzzle_to_string(&board, &state),
+                optimal: optimal,
+                seed: seed,
+            })
+        })
+        .collect();
+    println!("{}", json::JsonValue::from(puzzles).pretty(2));
+}
+
+// '--seed', or one from the clock without it. Output should include it so runs can be repeated.
+fn seed(args: &[String]) -> u64 {
+    flag(args, "--seed").map_or_else(
+        || {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            now.expect("Clock before 1970.").as_nanos() as u64
+        },
+        |seed| seed.parse().expect("Invalid seed."),
+    )
+}
+
+fn number(args: &[String], name: &str) -> Option<usize> {
+    flag(args, name).map(|value| {
+        value
+            .parse::<usize>()
+            .unwrap_or_else(|_| panic!("Invalid {name} value."))
+    })
+}
+
+// '--width', '--height', '--blockers' and '--moves min-max', the defaults for any left out.
+fn puzzle_params(args: &[String]) -> PuzzleParams {
+    let number = |name: &str| number(args, name);
+    let defaults = PuzzleParams::default();
+    let moves = flag(args, "--moves").map_or(defaults.moves.clone(), |moves| {
+        let (min, max) = moves.split_once('-').unwrap_or((moves, moves));
+        let parse = |n: &str| n.parse::<usize>().expect("Invalid --moves range.");
+        parse(min)..=parse(max)
     });
+    PuzzleParams {
+        width: number("--width").unwrap_or(defaults.width),
+        height: number("--height").unwrap_or(defaults.height),
+        blockers: number("--blockers").unwrap_or(defaults.blockers),
+        moves,
+        ..defaults
+    }
+}
+
+// Value given with flag 'name', if any.
+fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let at = args.iter().position(|arg| arg == name)?;
+    Some(args.get(at + 1).expect("Missing flag value."))
+}
+
+// Number of threads given with '--threads', None to use every core.
+fn threads(args: &[String]) -> Option<usize> {
+    flag(args, "--threads").map(|threads| threads.parse().expect("Invalid thread count."))
+}
+
+fn test1000(threads: Option<usize>) {
+    let input = fs::read_to_string("test_input/maps_moves.txt").expect("File not found.");
+    let lines: Vec<&str> = input.lines().collect();
+    let before = Instant::now();
+    let options = SolveOptions {
+        threads,
+        ..SolveOptions::default()
+    };
+    let outcomes = solve_batch(&lines, &options);
+    for (line, outcome) in outcomes.into_iter().enumerate() {
+        let outcome = outcome.unwrap_or_else(|err| panic!("Line {line} is invalid: {err}."));
+        if !matches!(outcome, SolveOutcome::Solved(_)) {
+            panic!("Line {line} not solved: {outcome:?}.");
+        }
+    }
 
     let ms = before.elapsed().as_micros();
     println!(
         "All {} solutions found in: {}.{}ms",
+        lines.len(),
+        ms / 1000,
+        ms % 1000
+    );
+}
+
+// Solves every puzzle in a file on 'threads' threads or all cores, optionally writing a CSV row
+// per puzzle, then prints aggregate statistics. Searches running past 'timeout' are given up on
+// and reported as failed lines. With 'unique' each puzzle is searched once, its repeats up to
+// symmetry taking its solution.
+fn batch(
+    path: &str,
+    csv: Option<&str>,
+    cache: Option<&str>,
+    threads: Option<usize>,
+    timeout: Option<Duration>,
+    unique: bool,
+) {
+    let input = BufReader::new(File::open(path).expect("File not found."));
+    let mut report = csv.map(|csv| {
+        let out = BufWriter::new(File::create(csv).expect("Failed to create CSV file."));
+        CsvReport::new(out).expect("Failed to write CSV file.")
+    });
+    let workers = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from));
+    let before = Instant::now();
+    let options = SolveOptions {
+        timeout,
+        ..SolveOptions::default()
+    };
+    let mut summary = BatchSummary::default();
+    let on_result = |result: BatchResult| {
+        summary.add(&result);
+        if let Some(report) = &mut report {
+            report.row(&result).expect("Failed to write CSV file.");
+        }
+        if let Err(err) = &result.moves {
+            eprintln!("Line {} failed: {err}.", result.line);
+        }
+    };
+    let received = match (cache, unique) {
+        (Some(cache), _) => {
+            if unique {
+                eprintln!("The --unique flag is ignored with --cache.");
+            }
+            solve_cached(input, &options, workers, cache, on_result)
+        }
+        (None, true) => solve_stream_unique(input, &options, workers, workers * 4, on_result),
+        (None, false) => solve_stream(input, &options, workers, workers * 4, on_result),
+    }
+    .expect("Failed to read input.");
+
+    let ms = before.elapsed().as_micros();
+    println!(
+        "{} puzzles done in: {}.{}ms",
         received,
         ms / 1000,
         ms % 1000
     );
+    print!("{summary}");
 }
 
-fn solve_board(line: String) -> Option<(Board, State, Vec<Move>)> {
-    let (board, state) = puzzle_from_string(&line);
-    let (_, state, moves) = solve_puzzle(&board, state)?;
-    Some((board, state, moves))
+#[cfg(feature = "sqlite")]
+fn solve_cached(
+    input: impl BufRead + Send,
+    options: &SolveOptions,
+    workers: usize,
+    path: &str,
+    on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    let cache = ResultCache::open(path).expect("Failed to open cache.");
+    solve_stream_cached(input, options, workers, workers * 4, &cache, on_result)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn solve_cached(
+    input: impl BufRead + Send,
+    options: &SolveOptions,
+    workers: usize,
+    _path: &str,
+    on_result: impl FnMut(BatchResult),
+) -> io::Result<usize> {
+    eprintln!("The --cache flag requires the 'sqlite' feature, solving without it.");
+    solve_stream(input, options, workers, workers * 4, on_result)
+}
+
+// 'analyze gods-number' followed by a puzzle as for 'solve' gives the optimal solution lengths over
+// every placement of the pieces on its board, listing '--examples' (default 5) of the hardest.
+// 'analyze reachability' counts the states reachable from the puzzle and the dead ends among them,
+// listing '--examples' of those. 'analyze distances' shows the fewest moves the main piece needs to
+// stop on each cell, '.' where it can't. 'analyze solutions' counts the solutions of at most
+// '--max-length' moves. 'analyze heatmap' prints as JSON how often each cell is passed over and
+// stopped on over every optimal solution, also drawing it to '--svg' if given. 'analyze graph'
+// prints the states searched within '--depth' (default 3) moves as a Graphviz DOT graph.
+fn analyze(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("gods-number") => {
+            let (board, _) = read_puzzle(&load_puzzle(&args[1..]));
+            let examples = flag(&args[1..], "--examples").map_or(5, |examples| {
+                examples.parse().expect("Invalid example count.")
+            });
+            print!("{}", analyze_space(&board, examples));
+        }
+        Some("reachability") => {
+            let (board, state) = read_puzzle(&load_puzzle(&args[1..]));
+            let examples = flag(&args[1..], "--examples").map_or(5, |examples| {
+                examples.parse().expect("Invalid example count.")
+            });
+            match reachability(&board, &state, examples) {
+                Some(report) => print!("{report}"),
+                None => eprintln!("Pieces are on blocked cells or on top of each other."),
+            }
+        }
+        Some("distances") => {
+            let (board, state) = read_puzzle(&load_puzzle(&args[1..]));
+            for row in distance_map(&board, &state) {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|distance| distance.map_or(".".to_string(), |d| d.to_string()))
+                    .map(|cell| format!("{cell:>3}"))
+                    .collect();
+                println!("{}", cells.concat());
+            }
+        }
+        Some("solutions") => {
+            let (board, state) = read_puzzle(&load_puzzle(&args[1..]));
+            let max_length = flag(&args[1..], "--max-length")
+                .expect("Missing --max-length.")
+                .parse()
+                .expect("Invalid max length.");
+            println!(
+                "{} solutions of at most {max_length} moves.",
+                count_solutions(&board, state, max_length)
+            );
+        }
+        Some("heatmap") => {
+            let (board, state) = read_puzzle(&load_puzzle(&args[1..]));
+            let Some(heatmap) = optimal_heatmap(&board, &state) else {
+                eprintln!("No solution found.");
+                return;
+            };
+            println!("{}", heatmap.to_json());
+            if let Some(path) = flag(&args[1..], "--svg") {
+                fs::write(path, heatmap_to_svg(&board, state, &heatmap))
+                    .expect("Failed to write the SVG.");
+            }
+        }
+        Some("graph") => {
+            let (board, state) = read_puzzle(&load_puzzle(&args[1..]));
+            let depth = flag(&args[1..], "--depth")
+                .map_or(3, |depth| depth.parse().expect("Invalid depth."));
+            print!("{}", search_graph_dot(&board, state, depth));
+        }
+        _ => eprintln!(
+            "Unknown analysis, expected gods-number, reachability, distances, \
+             solutions, heatmap or graph."
+        ),
+    }
 }
 
 fn _test_diff_size() {
@@ -56,9 +920,18 @@ fn _test_diff_size() {
     let mut i = 0;
     for item in parsed.members() {
         i += 1;
-        let (board, state) = puzzle_from_string(item["map"].as_str().unwrap());
-        let (_, _, moves) = solve_puzzle(&board, state).unwrap();
+        let (board, state) = read_puzzle(item["map"].as_str().unwrap());
         let opt = item["optimal"].as_usize().unwrap();
+        // Nothing longer than the known optimum needs searching.
+        let options = SolveOptions {
+            upper_bound: Some(opt),
+            ..SolveOptions::default()
+        };
+        let moves = solve_puzzle_with(&board, state, &options)
+            .into_result()
+            .unwrap()
+            .unwrap()
+            .moves;
         println!("Puzzle {}, sol found: {}, sol: {}", i, moves.len(), opt);
         assert_eq!(moves.len(), opt);
     }
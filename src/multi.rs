@@ -0,0 +1,211 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::solver::*;
+
+// Positions of the main piece and 'N' helpers, and the number of goals the main piece has reached
+// so far. The helpers are kept in fixed size arrays, so each helper count gets its own search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MultiState<const N: usize> {
+    pub main: Position,
+    pub helpers: [Position; N],
+    pub goals: u8,
+}
+
+impl<const N: usize> MultiState<N> {
+    pub fn new(main: Position, helpers: [Position; N]) -> Self {
+        Self {
+            main,
+            helpers,
+            goals: 0,
+        }
+    }
+}
+
+impl From<State> for MultiState<2> {
+    fn from(state: State) -> Self {
+        Self {
+            main: state.main_pos(),
+            helpers: [state.helper_one_pos(), state.helper_two_pos()],
+            goals: state.goals_reached(),
+        }
+    }
+}
+
+// Piece 0 is the main piece, piece 'i' the helper at 'helpers[i - 1]'.
+pub type PieceMove = (usize, Direction);
+
+struct Node<const N: usize> {
+    state: MultiState<N>,
+    m: Option<PieceMove>,
+    prev: usize,
+}
+
+fn moves<const N: usize>(nodes: &[Node<N>], mut index: usize) -> Vec<PieceMove> {
+    let mut moves = Vec::new();
+    while let Some(m) = nodes[index].m {
+        moves.push(m);
+        index = nodes[index].prev;
+    }
+    moves.reverse();
+    moves
+}
+
+// Slides piece 'piece' like 'slide', every other piece blocking its way and lava fatal to it
+// making the move illegal. Mirrors and lava keyed to a helper treat the first helper as HelperOne
+// and every later one as HelperTwo.
+fn slide_piece<const N: usize>(
+    board: &Board,
+    state: &MultiState<N>,
+    piece: usize,
+    dir: Direction,
+) -> Option<Position> {
+    // The other pieces, the moving one swapped out for the main piece if it is a helper.
+    let mut blocks = state.helpers;
+    let (kind, start) = match piece {
+        0 => (PieceType::Main, state.main),
+        1 => (PieceType::HelperOne, state.helpers[0]),
+        _ => (PieceType::HelperTwo, state.helpers[piece - 1]),
+    };
+    if piece > 0 {
+        blocks[piece - 1] = state.main;
+    }
+    // Every piece stacked on the moving one, leaving the blocking to 'blocks'.
+    let alone = State::new(start, start, start);
+    let stops = |pos: &Position| match board.movement() {
+        Movement::Slide => false,
+        Movement::Step => true,
+        Movement::Sticky => {
+            let (x, y) = (pos_to_x(pos), pos_to_y(pos));
+            [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ]
+            .iter()
+            .filter_map(|dir| board.step(x, y, dir))
+            .any(|(x, y)| blocks.contains(&xy_to_pos(x, y)))
+        }
+    };
+
+    let enter = |(entered, pos, dir): (Position, Position, Direction)| {
+        (!board.at(&entered).is_fatal_to(&kind)).then_some((pos, dir))
+    };
+    let (mut pos, mut dir) = enter(advance(board, &alone, &blocks, &kind, &start, dir)?)?;
+    // Every cell can be left in at most four directions before the slide repeats itself.
+    let mut steps_left = board.width() * board.height() * 4;
+    while !stops(&pos) {
+        let Some(next) = advance(board, &alone, &blocks, &kind, &pos, dir) else {
+            break;
+        };
+        (pos, dir) = enter(next)?;
+        steps_left = steps_left.checked_sub(1)?;
+    }
+    Some(pos)
+}
+
+fn move_piece<const N: usize>(
+    board: &Board,
+    state: &MultiState<N>,
+    piece: usize,
+    dir: Direction,
+) -> Option<MultiState<N>> {
+    let pos = slide_piece(board, state, piece, dir)?;
+    let mut next = *state;
+    if piece > 0 {
+        next.helpers[piece - 1] = pos;
+        return Some(next);
+    }
+    next.main = pos;
+    if let BoardPiece::Goal(index) = board.at(&pos) {
+        if *index == next.goals {
+            next.goals += 1;
+        }
+    }
+    Some(next)
+}
+
+// Breadth first search for a puzzle with any number of helpers, giving the final state and an
+// optimal solution. Pieces are tried main piece first, then the helpers in order, each in the
+// directions of the default 'TieBreak'. The helper goals apply to the first two helpers. Puzzles
+// with two helpers are handed to the faster 'solve_puzzle', which finds the same solution. None
+// if there is no solution, or the states don't fit a 'StateLayout', such as 15 helpers on 16x16.
+pub fn solve_puzzle_n<const N: usize>(
+    board: &Board,
+    state: MultiState<N>,
+) -> Option<(MultiState<N>, Vec<PieceMove>)> {
+    if N == 2 {
+        let start = State::new(state.main, state.helpers[0], state.helpers[1])
+            .with_goals_reached(state.goals);
+        let solution = solve_puzzle(board, start)?;
+        let end = solution.final_state;
+        let mut helpers = [0; N];
+        helpers.copy_from_slice(&[end.helper_one_pos(), end.helper_two_pos()]);
+        let moves = solution
+            .moves
+            .iter()
+            .map(|(piece, dir)| {
+                let index = match piece {
+                    PieceType::Main => 0,
+                    PieceType::HelperOne => 1,
+                    PieceType::HelperTwo => 2,
+                };
+                (index, *dir)
+            })
+            .collect();
+        let end = MultiState {
+            main: end.main_pos(),
+            helpers,
+            goals: end.goals_reached(),
+        };
+        return Some((end, moves));
+    }
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    let is_won = |state: &MultiState<N>| {
+        state.goals == win.goals
+            && *board.at(&state.main) == BoardPiece::Start
+            && [win.helper_one, win.helper_two]
+                .iter()
+                .zip(&state.helpers)
+                .all(|(goal, pos)| goal.is_none_or(|goal| goal == *pos))
+    };
+    // Main piece and helpers each take a slot, so states are kept as the packed bits.
+    let layout = StateLayout::new(board, N + 1)?;
+    let pack = |state: &MultiState<N>| {
+        let pieces = std::iter::once(state.main).chain(state.helpers);
+        layout.pack(pieces, state.goals)
+    };
+    let mut visited = HashSet::from([pack(&state)]);
+    let mut nodes = vec![Node {
+        state,
+        m: None,
+        prev: 0,
+    }];
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(index) = queue.pop_front() {
+        let state = nodes[index].state;
+        if is_won(&state) {
+            return Some((state, moves(&nodes, index)));
+        }
+        for piece in 0..=N {
+            for dir in order.directions {
+                let Some(next) = move_piece(board, &state, piece, dir) else {
+                    continue;
+                };
+                if !visited.insert(pack(&next)) {
+                    continue;
+                }
+                nodes.push(Node {
+                    state: next,
+                    m: Some((piece, dir)),
+                    prev: index,
+                });
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,68 @@
+use crate::solver::{Board, Move, PieceType, State};
+use crate::weighted::{solve_weighted, uniform_cost, MoveCosts};
+
+// Weight that makes one unit of the primary objective outweigh any realistic number of moves,
+// turning a lexicographic objective into a single additive cost.
+const PRIMARY: u32 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    // Fewest moves in total, same as 'solve_puzzle'.
+    MinMoves,
+    // Fewest main piece moves among the shortest solutions.
+    MinMainMovesAmongShortest,
+    // Fewest main piece moves regardless of length, shorter solutions preferred on ties.
+    MinMainMoves,
+    // Fewest distinct pieces moved (e.g. only the main piece), shorter solutions preferred on ties.
+    MinPiecesMoved,
+}
+
+impl Objective {
+    // Per move costs for objectives that are plain sums over the moves.
+    fn costs(&self) -> Option<MoveCosts> {
+        match self {
+            Objective::MinMoves => Some(MoveCosts::uniform(1)),
+            Objective::MinMainMovesAmongShortest => {
+                Some(MoveCosts::per_piece(PRIMARY + 1, PRIMARY))
+            }
+            Objective::MinMainMoves => Some(MoveCosts::per_piece(PRIMARY, 1)),
+            Objective::MinPiecesMoved => None,
+        }
+    }
+}
+
+fn piece_bit(piece: &PieceType) -> u8 {
+    match piece {
+        PieceType::Main => 0b001,
+        PieceType::HelperOne => 0b010,
+        PieceType::HelperTwo => 0b100,
+    }
+}
+
+// Searches states paired with the set of pieces moved so far, paying extra for each new piece.
+fn solve_min_pieces(board: &Board, state: State) -> Option<(State, Vec<Move>)> {
+    let ((state, _), moves, _) = uniform_cost(
+        board,
+        (state, 0u8),
+        |(state, _)| *state,
+        |(_, moved), m, next| {
+            let bit = piece_bit(&m.0);
+            let cost = if moved & bit == 0 { PRIMARY + 1 } else { 1 };
+            ((next, moved | bit), cost)
+        },
+    )?;
+    Some((state, moves))
+}
+
+// Finds the best solution under 'objective', giving the final state and the moves.
+pub fn solve_with_objective(
+    board: &Board,
+    state: State,
+    objective: Objective,
+) -> Option<(State, Vec<Move>)> {
+    let Some(costs) = objective.costs() else {
+        return solve_min_pieces(board, state);
+    };
+    let (state, moves, _) = solve_weighted(board, state, &costs)?;
+    Some((state, moves))
+}
@@ -0,0 +1,244 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::solver::*;
+
+// Layers are expanded this many nodes at a time, with the limits checked in between.
+const CHUNK: usize = 16 * 1024;
+
+// Locks the hashed visited set is split over, a power of two.
+const SHARDS: usize = 64;
+
+// Pools built so far by number of threads, kept so every search doesn't start its own threads.
+#[cfg(feature = "parallel")]
+static POOLS: Mutex<Vec<(usize, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
+
+struct Node {
+    state: State,
+    m: Option<Move>,
+    prev: usize,
+}
+
+// Runs 'work' on a pool of 'threads' threads, or on the global rayon pool using every core if None.
+#[cfg(feature = "parallel")]
+pub(crate) fn in_pool<T: Send>(threads: Option<usize>, work: impl FnOnce() -> T + Send) -> T {
+    match threads {
+        Some(threads) => {
+            let pool = {
+                let mut pools = POOLS.lock().unwrap();
+                match pools.iter().find(|(size, _)| *size == threads) {
+                    Some((_, pool)) => pool.clone(),
+                    None => {
+                        let pool = ThreadPoolBuilder::new()
+                            .num_threads(threads)
+                            .build()
+                            .expect("Failed to start thread pool.");
+                        pools.push((threads, Arc::new(pool)));
+                        pools[pools.len() - 1].1.clone()
+                    }
+                }
+            };
+            pool.install(work)
+        }
+        None => work(),
+    }
+}
+
+// Without rayon everything runs on the calling thread.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn in_pool<T>(_threads: Option<usize>, work: impl FnOnce() -> T) -> T {
+    work()
+}
+
+// 'Visited' shared by the threads expanding a layer. Dense bits are set with an atomic or, hashed
+// states are spread over 'SHARDS' locks by hash so threads seldom wait on each other.
+enum SharedVisited {
+    Dense {
+        seen: Vec<AtomicU64>,
+        height: usize,
+        area: usize,
+        progress: usize,
+    },
+    Hashed(Vec<Mutex<ZobristSet>>),
+}
+
+impl SharedVisited {
+    fn new(visited: Visited) -> Self {
+        match visited {
+            Visited::Dense {
+                seen,
+                height,
+                area,
+                progress,
+            } => SharedVisited::Dense {
+                seen: seen.into_iter().map(AtomicU64::new).collect(),
+                height,
+                area,
+                progress,
+            },
+            Visited::Hashed(_) => {
+                SharedVisited::Hashed((0..SHARDS).map(|_| Mutex::new(ZobristSet::new())).collect())
+            }
+        }
+    }
+
+    // Marks 'state' as visited, gives false if it already was. Of threads inserting the same
+    // state at once exactly one gets true.
+    fn insert(&self, state: &State) -> bool {
+        match self {
+            SharedVisited::Dense {
+                seen,
+                height,
+                area,
+                progress,
+            } => {
+                let index = Visited::dense_index(state, *height, *area, *progress);
+                let bit = 1 << (index % 64);
+                seen[index / 64].fetch_or(bit, Ordering::Relaxed) & bit == 0
+            }
+            // The top bits pick the shard, the set probes from the bottom ones.
+            SharedVisited::Hashed(shards) => {
+                let shard = (ZobristSet::hash(state) >> (64 - SHARDS.trailing_zeros())) as usize;
+                shards[shard].lock().unwrap().insert(state)
+            }
+        }
+    }
+}
+
+fn moves(nodes: &[Node], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(m) = nodes[index].m {
+        moves.push(m);
+        index = nodes[index].prev;
+    }
+    moves.reverse();
+    moves
+}
+
+fn parallel_bfs(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    let win = WinCondition::new(board);
+    let visited = Visited::new(board, win.goals);
+    stats.visited = Some(visited.kind());
+    let visited = SharedVisited::new(visited);
+    if win.is_met(board, &state) {
+        return Ok(Some((state, Vec::new())));
+    }
+    let mut nodes = vec![Node {
+        state,
+        m: None,
+        prev: 0,
+    }];
+    // Nodes 'depth' moves from the start, in the order breadth first search would reach them.
+    let mut layer: Range<usize> = 0..1;
+    let mut depth = 0;
+
+    while !layer.is_empty() {
+        if let Some(on_progress) = &options.on_progress {
+            on_progress(&SearchProgress {
+                depth,
+                frontier: layer.len(),
+                expanded: stats.nodes_expanded,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            depth,
+            frontier = layer.len(),
+            expanded = stats.nodes_expanded,
+            pruned = stats.duplicates_pruned,
+            "depth reached"
+        );
+        stats.depths.push(DepthStats {
+            frontier: layer.len(),
+            visited: nodes.len(),
+        });
+
+        if options.upper_bound.is_some_and(|bound| depth >= bound) {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: depth + 1,
+            });
+        }
+
+        for start in layer.clone().step_by(CHUNK) {
+            let end = (start + CHUNK).min(layer.end);
+            let queued = nodes.len() - start;
+            // The whole layer has been checked, so no solution is this short.
+            let over_budget = options
+                .max_nodes
+                .is_some_and(|max| stats.nodes_expanded >= max)
+                || options.max_queue.is_some_and(|max| queued >= max);
+            if over_budget {
+                return Err(SolveError::BudgetExceeded {
+                    lower_bound: depth + 1,
+                });
+            }
+            check_limits(options, deadline, stats, depth, queued)?;
+
+            // Moves are tried and the new states claimed in parallel, then added in the order of
+            // the nodes reaching them. A state reached from several nodes goes to whichever
+            // claimed it first, so the solution can change between runs but not its length.
+            #[cfg(feature = "parallel")]
+            let chunk = (start..end).into_par_iter();
+            #[cfg(not(feature = "parallel"))]
+            let chunk = start..end;
+            let expanded: Vec<_> = chunk
+                .map(|index| {
+                    let next_states = neighbourhood(board, &nodes[index].state, &options.tie_break);
+                    let generated = next_states.len();
+                    let new: Vec<_> = next_states
+                        .into_iter()
+                        .filter(|(_, state)| visited.insert(state))
+                        .collect();
+                    (generated, new)
+                })
+                .collect();
+            stats.nodes_expanded += end - start;
+            for (index, (generated, new)) in (start..end).zip(expanded) {
+                stats.nodes_generated += generated;
+                stats.duplicates_pruned += generated - new.len();
+                for (m, state) in new {
+                    nodes.push(Node {
+                        state,
+                        m: Some(m),
+                        prev: index,
+                    });
+                    // Checked as they are added, the rest of the layer is never expanded.
+                    if win.is_met(board, &state) {
+                        return Ok(Some((state, moves(&nodes, nodes.len() - 1))));
+                        // Solution found, yay!
+                    }
+                }
+            }
+            stats.max_queue_len = stats.max_queue_len.max(nodes.len() - end);
+        }
+        layer = layer.end..nodes.len();
+        depth += 1;
+    }
+
+    Ok(None) // Exhausted search, no solution found.
+}
+
+// Breadth first search expanding each layer on 'options.threads' threads, for single puzzles too
+// hard to wait for on one core. Needs the 'parallel' feature to use more than the calling thread.
+// Finds solutions as short as 'solve_puzzle_with', though with several threads not always the same
+// one. The limits are only checked between chunks of a layer and progress is reported per layer.
+pub fn solve_parallel(board: &Board, state: State, options: &SolveOptions) -> SolveOutcome {
+    in_pool(options.threads, || {
+        run_search(board, state, options, parallel_bfs)
+    })
+}
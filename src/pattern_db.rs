@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use crate::canonical::board_hash;
+use crate::heuristic::{relaxed_stops, route, Heuristic, UNSOLVABLE};
+use crate::solver::*;
+
+const MAGIC: &[u8; 4] = b"SSPD";
+const VERSION: u8 = 1;
+// Stored for states that can't be solved, distances past it are cut down to one less.
+const UNREACHABLE: u8 = u8::MAX;
+// Entries per number of goals reached, indexed by main and then helper position.
+const LAYER: usize = 256 * 256;
+
+// Exact solution lengths of the puzzle reduced to the main piece and one helper, for each of the
+// two helpers. The other helper is taken to be wherever suits the two best, so it can stop them on
+// any cell they pass but its own moves are free. That makes every entry a lower bound on the moves
+// left in the full puzzle, and the larger of the two is the estimate. It is never below
+// 'RicochetLowerBound', which leaves out both helpers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDatabase {
+    board: u64,
+    goals: u8,
+    // HelperOne then HelperTwo, indexed by 'index'.
+    distances: [Vec<u8>; 2],
+}
+
+fn index(goals: u8, main: Position, helper: Position) -> usize {
+    goals as usize * LAYER + main as usize * 256 + helper as usize
+}
+
+// Distances for the puzzle reduced to the main piece and 'helper'.
+fn build(board: &Board, helper: &PieceType) -> Vec<u8> {
+    let goals = goal_count(board);
+    let win = WinCondition::new(board);
+    let helper_goal = match helper {
+        PieceType::HelperOne => win.helper_one,
+        _ => win.helper_two,
+    };
+    let free: Vec<Position> = board
+        .cells()
+        .filter(|(_, _, cell)| {
+            !matches!(
+                cell,
+                BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+            )
+        })
+        .map(|(x, y, _)| xy_to_pos(x, y))
+        .collect();
+    // The reduced state with the left out helper on 'moving', out of the way.
+    let reduced = |main: Position, tracked: Position, moving: Position| match helper {
+        PieceType::HelperOne => State::new(main, tracked, moving),
+        _ => State::new(main, moving, tracked),
+    };
+
+    // States left out of the reduced puzzle, such as ones with pieces on top of each other, are
+    // estimated at 0.
+    let mut distances = vec![0; (goals as usize + 1) * LAYER];
+    let mut reverse = vec![Vec::new(); distances.len()];
+    for reached in 0..=goals {
+        for &main in &free {
+            for &tracked in free.iter().filter(|pos| **pos != main) {
+                let from = index(reached, main, tracked);
+                distances[from] = UNREACHABLE;
+                let state = reduced(main, tracked, main);
+                for stop in relaxed_stops(board, &state, &PieceType::Main, main) {
+                    let counted = *board.at(&stop) == BoardPiece::Goal(reached);
+                    reverse[index(reached + counted as u8, stop, tracked)].push(from as u32);
+                }
+                let state = reduced(main, tracked, tracked);
+                for stop in relaxed_stops(board, &state, helper, tracked) {
+                    reverse[index(reached, main, stop)].push(from as u32);
+                }
+            }
+        }
+    }
+
+    let mut queue = VecDeque::new();
+    if let Some(Some(start)) = route(board).last() {
+        let ends = match helper_goal {
+            Some(goal) => vec![goal],
+            None => free.clone(),
+        };
+        for end in ends.into_iter().filter(|end| end != start) {
+            let end = index(goals, *start, end);
+            distances[end] = 0;
+            queue.push_back(end);
+        }
+    }
+    while let Some(to) = queue.pop_front() {
+        let distance = (distances[to] + 1).min(UNREACHABLE - 1);
+        for &from in &reverse[to] {
+            if distances[from as usize] == UNREACHABLE {
+                distances[from as usize] = distance;
+                queue.push_back(from as usize);
+            }
+        }
+    }
+    distances
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl PatternDatabase {
+    // Builds the database for 'board', which takes a few seconds on larger boards, so it is meant
+    // to be built once with 'write' and loaded with 'read' after.
+    pub fn new(board: &Board) -> Self {
+        Self {
+            board: board_hash(board),
+            goals: goal_count(board),
+            distances: [
+                build(board, &PieceType::HelperOne),
+                build(board, &PieceType::HelperTwo),
+            ],
+        }
+    }
+
+    // Whether the database was built for 'board'. Boards that are rotated or reflected don't match.
+    pub fn fits(&self, board: &Board) -> bool {
+        self.board == board_hash(board)
+    }
+
+    // Writes the magic bytes 'SSPD' and a version byte, then the board hash (little endian), the
+    // number of goals and both distance tables.
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&self.board.to_le_bytes())?;
+        out.write_all(&[self.goals])?;
+        for distances in &self.distances {
+            out.write_all(distances)?;
+        }
+        Ok(())
+    }
+
+    // Reads a database written by 'write', failing if it was built for another board.
+    pub fn read(mut input: impl Read, board: &Board) -> io::Result<Self> {
+        let mut header = [0; 14];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid("not a pattern database"));
+        }
+        let mut database = Self {
+            board: u64::from_le_bytes(header[5..13].try_into().unwrap()),
+            goals: header[13],
+            distances: [Vec::new(), Vec::new()],
+        };
+        if !database.fits(board) {
+            return Err(invalid("pattern database is for another board"));
+        }
+        for distances in &mut database.distances {
+            *distances = vec![0; (database.goals as usize + 1) * LAYER];
+            input.read_exact(distances)?;
+        }
+        Ok(database)
+    }
+}
+
+impl Heuristic for PatternDatabase {
+    fn estimate(&self, _board: &Board, state: &State) -> u32 {
+        if state.goals_reached() > self.goals {
+            return UNSOLVABLE;
+        }
+        let main = state.main_pos();
+        let [one, two] = &self.distances;
+        let estimates = [
+            one[index(state.goals_reached(), main, state.helper_one_pos())],
+            two[index(state.goals_reached(), main, state.helper_two_pos())],
+        ];
+        match estimates.into_iter().max().unwrap() {
+            UNREACHABLE => UNSOLVABLE,
+            estimate => estimate as u32,
+        }
+    }
+}
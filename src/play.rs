@@ -0,0 +1,177 @@
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::session::Session;
+use crate::solver::*;
+use crate::tools::arrow;
+
+struct Game {
+    session: Session,
+    selected: PieceType,
+    optimal: Option<usize>,
+    // Fewest moves left from the current state.
+    remaining: Option<usize>,
+}
+
+impl Game {
+    fn new(mut session: Session) -> Self {
+        Self {
+            optimal: solve_puzzle(session.board(), session.start())
+                .map(|solution| solution.moves.len()),
+            remaining: session.optimal_remaining(),
+            session,
+            selected: PieceType::Main,
+        }
+    }
+
+    fn slide(&mut self, dir: Direction) {
+        self.session.apply((self.selected, dir));
+    }
+
+    // Two characters per cell, the second one showing a wall on the east edge. Walls on the south
+    // edge are shown by underlining the cell.
+    fn cell(&self, x: usize, y: usize) -> Span<'static> {
+        let pos = xy_to_pos(x, y);
+        let (board, state) = (self.session.board(), self.session.state());
+        let selected_pos = state.pos(&self.selected);
+        let piece = board.get(x, y);
+        let (glyph, mut style) = if pos == state.main_pos() {
+            ("M", Style::new().fg(Color::Red).bold())
+        } else if pos == state.helper_one_pos() {
+            ("1", Style::new().fg(Color::Blue).bold())
+        } else if pos == state.helper_two_pos() {
+            ("2", Style::new().fg(Color::Blue).bold())
+        } else {
+            match piece {
+                BoardPiece::Blocker => ("#", Style::new().fg(Color::DarkGray)),
+                BoardPiece::Pushable => ("%", Style::new().fg(Color::Gray)),
+                BoardPiece::Void => (" ", Style::new()),
+                BoardPiece::Lava(_) => ("!", Style::new().fg(Color::LightRed).bold()),
+                BoardPiece::Goal(_) => ("o", Style::new().fg(Color::Green).bold()),
+                BoardPiece::HelperGoal(_) => ("x", Style::new().fg(Color::Cyan).bold()),
+                BoardPiece::Mirror(Slant::Slash, _) => ("/", Style::new().fg(Color::Yellow)),
+                BoardPiece::Mirror(Slant::Backslash, _) => ("\\", Style::new().fg(Color::Yellow)),
+                BoardPiece::OneWay(dir) => (arrow(dir), Style::new().fg(Color::Yellow)),
+                BoardPiece::Portal(_) => ("@", Style::new().fg(Color::Magenta).bold()),
+                BoardPiece::Start => ("+", Style::new().fg(Color::Yellow).bold()),
+                _ => (".", Style::new()),
+            }
+        };
+        let edge = if board.has_wall(x, y, &Direction::Right) {
+            "|"
+        } else if *piece == BoardPiece::Blocker {
+            "#"
+        } else {
+            " "
+        };
+        if board.has_wall(x, y, &Direction::Down) {
+            style = style.underlined();
+        }
+        if pos == selected_pos {
+            style = style.reversed();
+        }
+        Span::styled(format!("{glyph}{edge}"), style)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [board_area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(4)]).areas(frame.area());
+
+        let board = self.session.board();
+        let lines: Vec<Line> = (0..board.height())
+            .map(|y| {
+                Line::from(
+                    (0..board.width())
+                        .map(|x| self.cell(x, y))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Puzzle ")),
+            board_area,
+        );
+
+        let moves = |moves: Option<usize>| match moves {
+            Some(n) => n.to_string(),
+            None => "unsolvable".to_string(),
+        };
+        let mut status = vec![Line::from(format!(
+            "Moves: {}   Left: {}   Optimal: {}   Selected: {:?}",
+            self.session.history().len(),
+            moves(self.remaining),
+            moves(self.optimal),
+            self.selected
+        ))];
+        if self.session.is_solved() {
+            status.push(Line::from("Solved!".green().bold()));
+        }
+        status.push(Line::from(
+            "m/1/2/tab: select piece  arrows: slide  u: undo  y: redo  r: reset  q: quit",
+        ));
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('m') => self.selected = PieceType::Main,
+            KeyCode::Char('1') => self.selected = PieceType::HelperOne,
+            KeyCode::Char('2') => self.selected = PieceType::HelperTwo,
+            KeyCode::Tab => {
+                self.selected = match self.selected {
+                    PieceType::Main => PieceType::HelperOne,
+                    PieceType::HelperOne => PieceType::HelperTwo,
+                    PieceType::HelperTwo => PieceType::Main,
+                }
+            }
+            KeyCode::Up => self.slide(Direction::Up),
+            KeyCode::Down => self.slide(Direction::Down),
+            KeyCode::Left => self.slide(Direction::Left),
+            KeyCode::Right => self.slide(Direction::Right),
+            KeyCode::Char('u') => {
+                self.session.undo();
+            }
+            KeyCode::Char('y') => {
+                self.session.redo();
+            }
+            KeyCode::Char('r') => self.session.reset(),
+            _ => {}
+        }
+        self.remaining = self.session.optimal_remaining();
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, game: &mut Game) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| game.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                code => game.handle_key(code),
+            }
+        }
+    }
+}
+
+// Interactive terminal game for a single puzzle.
+pub fn play(board: Board, state: State) -> io::Result<()> {
+    play_session(Session::new(board, state)).map(|_| ())
+}
+
+// Plays on from 'session', giving it back as it was left when the game is quit.
+pub fn play_session(session: Session) -> io::Result<Session> {
+    let mut game = Game::new(session);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut game);
+    ratatui::restore();
+    result.map(|_| game.session)
+}
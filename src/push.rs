@@ -0,0 +1,116 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::solver::*;
+
+// Positions of the pushable blockers, kept sorted since they are interchangeable.
+pub type Blocks = Vec<Position>;
+
+struct Node {
+    state: State,
+    blocks: Blocks,
+    m: Option<Move>,
+    prev: usize,
+}
+
+fn moves(nodes: &[Node], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(m) = nodes[index].m {
+        moves.push(m);
+        index = nodes[index].prev;
+    }
+    moves.reverse();
+    moves
+}
+
+// Moves 'piece', with the main piece pushing the blocker it runs into one cell further if that
+// cell is free. The main piece stops in front of the blocker either way.
+fn push_move(
+    board: &Board,
+    state: &State,
+    blocks: &Blocks,
+    piece: &PieceType,
+    dir: &Direction,
+) -> Option<(State, Blocks)> {
+    let start = state.pos(piece);
+    let (pos, last_dir) =
+        slide(board, state, blocks, piece, &start, dir, |_| {}).unwrap_or((start, *dir));
+    let mut next_blocks = blocks.clone();
+    if *piece == PieceType::Main {
+        let pushed = try_move(&pos, &last_dir, board, state, &[])
+            .and_then(|ahead| blocks.iter().position(|block| *block == ahead));
+        if let Some(index) = pushed {
+            let others: Blocks = blocks
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, block)| *block)
+                .collect();
+            if let Some(dest) = try_move(&blocks[index], &last_dir, board, state, &others) {
+                next_blocks[index] = dest;
+                next_blocks.sort_unstable();
+            }
+        }
+    }
+    if pos == start && next_blocks == *blocks {
+        return None;
+    }
+    Some((place(board, state, piece, pos), next_blocks))
+}
+
+// Breadth first search on boards with pushable blockers, giving the final state, where the
+// blockers ended up and the moves.
+pub fn solve_pushing(board: &Board, state: State) -> Option<(State, Blocks, Vec<Move>)> {
+    // The blockers move with the search, so the board only keeps their starting cells empty.
+    let mut board = board.clone();
+    let mut blocks = Blocks::new();
+    for (x, y, piece) in board.cells() {
+        if *piece == BoardPiece::Pushable {
+            blocks.push(xy_to_pos(x, y));
+        }
+    }
+    for block in &blocks {
+        board.set(pos_to_x(block), pos_to_y(block), BoardPiece::Empty);
+    }
+    blocks.sort_unstable();
+
+    let order = TieBreak::default();
+    let win = WinCondition::new(&board);
+    let mut visited = HashSet::new();
+    let mut nodes = Vec::new();
+    let mut queue = VecDeque::new();
+    visited.insert((state, blocks.clone()));
+    nodes.push(Node {
+        state,
+        blocks,
+        m: None,
+        prev: 0,
+    });
+    queue.push_back(0);
+
+    while let Some(index) = queue.pop_front() {
+        let (state, blocks) = (nodes[index].state, nodes[index].blocks.clone());
+        if win.is_met(&board, &state) {
+            return Some((state, blocks, moves(&nodes, index)));
+        }
+        for piece in order.pieces {
+            for dir in order.directions {
+                let Some((next, next_blocks)) = push_move(&board, &state, &blocks, &piece, &dir)
+                else {
+                    continue;
+                };
+                if !visited.insert((next, next_blocks.clone())) {
+                    continue;
+                }
+                nodes.push(Node {
+                    state: next,
+                    blocks: next_blocks,
+                    m: Some((piece, dir)),
+                    prev: index,
+                });
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+    }
+
+    None
+}
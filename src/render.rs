@@ -0,0 +1,4 @@
+pub mod dot;
+#[cfg(feature = "gif")]
+pub mod gif;
+pub mod svg;
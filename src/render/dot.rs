@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::solver::*;
+use crate::tools::format_move;
+
+fn node(state: &State) -> String {
+    format!("s{:08x}", state.to_bits())
+}
+
+fn label(state: &State) -> String {
+    let cell = |pos: Position| format!("{},{}", pos_to_x(&pos), pos_to_y(&pos));
+    format!(
+        "M {}\\n1 {}\\n2 {}",
+        cell(state.main_pos()),
+        cell(state.helper_one_pos()),
+        cell(state.helper_two_pos())
+    )
+}
+
+// Searches the puzzle breadth first to 'max_depth' moves and writes what was explored as a
+// Graphviz DOT graph, a node for each state and an edge for each move. Moves back to a state
+// already found, which the search prunes, are dashed. Solved states are not expanded and drawn as
+// double circles, and the first optimal solution within 'max_depth', if any, is drawn in red.
+pub fn search_graph_dot(board: &Board, state: State, max_depth: usize) -> String {
+    let options = SolveOptions {
+        max_depth: Some(max_depth),
+        ..SolveOptions::default()
+    };
+    // States along the solution and the moves made from them.
+    let (mut path, mut solution) = (HashSet::new(), HashSet::new());
+    if let SolveOutcome::Solved(found) = solve_puzzle_with(board, state, &options) {
+        path.insert(state);
+        let mut current = state;
+        for (m, next) in playback(board, state, &found.moves) {
+            solution.insert((current, m));
+            path.insert(next);
+            current = next;
+        }
+    }
+
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    let mut dot = String::from("digraph search {\n    node [shape=circle, fontsize=10];\n");
+    let mut seen = HashSet::from([state]);
+    let mut layer = vec![state];
+    for depth in 0..=max_depth {
+        let mut next_layer = Vec::new();
+        for state in &layer {
+            let solved = win.is_met(board, state);
+            let shape = if solved { ", shape=doublecircle" } else { "" };
+            let color = if path.contains(state) {
+                ", color=red"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "    {} [label=\"{}\"{shape}{color}];",
+                node(state),
+                label(state)
+            )
+            .unwrap();
+            if solved || depth == max_depth {
+                continue;
+            }
+            for (m, reached) in neighbourhood(board, state, &order) {
+                let mut style = Vec::new();
+                if !seen.insert(reached) {
+                    style.push("style=dashed");
+                } else {
+                    next_layer.push(reached);
+                }
+                if solution.contains(&(*state, m)) {
+                    style.push("color=red, penwidth=2");
+                }
+                writeln!(
+                    dot,
+                    "    {} -> {} [label=\"{}\"{}];",
+                    node(state),
+                    node(&reached),
+                    format_move(&m),
+                    style
+                        .iter()
+                        .map(|style| format!(", {style}"))
+                        .collect::<String>()
+                )
+                .unwrap();
+            }
+        }
+        layer = next_layer;
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -0,0 +1,151 @@
+use std::borrow::Cow;
+
+use gif::{Encoder, EncodingError, Frame, Repeat};
+
+use crate::solver::*;
+
+const CELL: usize = 24;
+const STEP_DELAY: u16 = 8;
+const MOVE_DELAY: u16 = 40;
+
+const WHITE: u8 = 0;
+const GRID: u8 = 1;
+const BLOCKER: u8 = 2;
+const GOAL: u8 = 3;
+const START: u8 = 4;
+const MAIN: u8 = 5;
+const HELPER: u8 = 6;
+const PORTAL: u8 = 7;
+const LAVA: u8 = 8;
+
+#[rustfmt::skip]
+const PALETTE: [u8; 27] = [
+    0xff, 0xff, 0xff,
+    0xcc, 0xcc, 0xcc,
+    0x33, 0x33, 0x33,
+    0x2c, 0xa0, 0x2c,
+    0xe6, 0xb8, 0x00,
+    0xd6, 0x27, 0x28,
+    0x1f, 0x77, 0xb4,
+    0x94, 0x67, 0xbd,
+    0xff, 0x7f, 0x0e,
+];
+
+type Cell = (usize, usize);
+
+fn cell(pos: &Position) -> Cell {
+    (pos_to_x(pos), pos_to_y(pos))
+}
+
+// Rasterizes the board with pieces drawn at 'pieces' (main, helper one, helper two).
+fn draw(board: &Board, pieces: &[Cell; 3]) -> Vec<u8> {
+    let width = board.width() * CELL;
+    let mut pixels = vec![WHITE; width * board.height() * CELL];
+    let radius = (CELL / 3) as isize;
+    for (py, row) in pixels.chunks_mut(width).enumerate() {
+        let (y, in_y) = (py / CELL, py % CELL);
+        for (px, pixel) in row.iter_mut().enumerate() {
+            let (x, in_x) = (px / CELL, px % CELL);
+            let (dx, dy) = (
+                in_x as isize - CELL as isize / 2,
+                in_y as isize - CELL as isize / 2,
+            );
+            let dist = dx * dx + dy * dy;
+            let border = in_x < 3 || in_y < 3 || in_x >= CELL - 3 || in_y >= CELL - 3;
+            let ring = dist <= radius * radius && dist >= (radius - 3) * (radius - 3);
+            let slash = (in_x + in_y).abs_diff(CELL - 1) <= 1;
+            let backslash = in_x.abs_diff(in_y) <= 1;
+            let chevron = |dir: &Direction| {
+                let (along, across) = match dir {
+                    Direction::Up => (-dy, dx),
+                    Direction::Down => (dy, dx),
+                    Direction::Left => (-dx, dy),
+                    Direction::Right => (dx, dy),
+                };
+                across.abs() <= CELL as isize / 4
+                    && (along + across.abs() - CELL as isize / 8).abs() <= 1
+            };
+            *pixel = match *board.get(x, y) {
+                BoardPiece::Blocker => BLOCKER,
+                // Hatched, so holes are told apart from blockers.
+                BoardPiece::Void if (in_x + in_y).is_multiple_of(4) => GRID,
+                BoardPiece::Void => WHITE,
+                BoardPiece::Pushable if border => BLOCKER,
+                BoardPiece::Pushable => GRID,
+                BoardPiece::Lava(_) => LAVA,
+                _ if in_x == 0 || in_y == 0 => GRID,
+                BoardPiece::Goal(_) if ring => GOAL,
+                BoardPiece::HelperGoal(_) if ring => HELPER,
+                BoardPiece::Start if border => START,
+                BoardPiece::Portal(_) if dist <= radius * radius => PORTAL,
+                BoardPiece::Mirror(Slant::Slash, _) if slash => BLOCKER,
+                BoardPiece::Mirror(Slant::Backslash, _) if backslash => BLOCKER,
+                BoardPiece::OneWay(dir) if chevron(&dir) => START,
+                _ => WHITE,
+            };
+            let on_wall = (in_y < 2 && board.has_wall(x, y, &Direction::Up))
+                || (in_y >= CELL - 2 && board.has_wall(x, y, &Direction::Down))
+                || (in_x < 2 && board.has_wall(x, y, &Direction::Left))
+                || (in_x >= CELL - 2 && board.has_wall(x, y, &Direction::Right));
+            if on_wall {
+                *pixel = BLOCKER;
+            }
+            if let Some(i) = pieces.iter().position(|p| *p == (x, y)) {
+                if dist <= (radius - 1) * (radius - 1) {
+                    *pixel = if i == 0 { MAIN } else { HELPER };
+                }
+            }
+        }
+    }
+    pixels
+}
+
+// Encodes an animated GIF of 'moves' being played from 'state', sliding pieces one cell per frame.
+pub fn to_gif(board: &Board, state: State, moves: &[Move]) -> Result<Vec<u8>, EncodingError> {
+    let (width, height) = (
+        (board.width() * CELL) as u16,
+        (board.height() * CELL) as u16,
+    );
+    let mut out = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut out, width, height, &PALETTE)?;
+        encoder.set_repeat(Repeat::Infinite)?;
+        let mut write = |pieces: &[Cell; 3], delay: u16| {
+            let frame = Frame {
+                width,
+                height,
+                delay,
+                buffer: Cow::Owned(draw(board, pieces)),
+                ..Frame::default()
+            };
+            encoder.write_frame(&frame)
+        };
+
+        let mut pieces = [
+            cell(&state.main_pos()),
+            cell(&state.helper_one_pos()),
+            cell(&state.helper_two_pos()),
+        ];
+        write(&pieces, MOVE_DELAY)?;
+        let mut current = state;
+        for (m, next) in playback(board, state, moves) {
+            let index = match m.0 {
+                PieceType::Main => 0,
+                PieceType::HelperOne => 1,
+                PieceType::HelperTwo => 2,
+            };
+            let path = slide_path(board, &current, &m.0, &m.1);
+            for (step, pos) in path.iter().enumerate().skip(1) {
+                pieces[index] = cell(pos);
+                let delay = if step + 1 == path.len() {
+                    MOVE_DELAY
+                } else {
+                    STEP_DELAY
+                };
+                write(&pieces, delay)?;
+            }
+            current = next;
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,248 @@
+use std::fmt::Write;
+
+use crate::analysis::Heatmap;
+use crate::solver::*;
+
+const CELL: usize = 40;
+
+fn center(pos: &Position) -> (usize, usize) {
+    (
+        pos_to_x(pos) * CELL + CELL / 2,
+        pos_to_y(pos) * CELL + CELL / 2,
+    )
+}
+
+fn piece_color(piece: &PieceType) -> &'static str {
+    match piece {
+        PieceType::Main => "#d62728",
+        PieceType::HelperOne | PieceType::HelperTwo => "#1f77b4",
+    }
+}
+
+// Draws the board with its pieces as an SVG document, overlaying arrows for 'solution' if given.
+pub fn to_svg(board: &Board, state: State, solution: Option<&[Move]>) -> String {
+    let (width, height) = (board.width() * CELL, board.height() * CELL);
+    let goals = goal_count(board);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    svg.push_str(concat!(
+        r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" markerWidth="6" markerHeight="6" orient="auto-start-reverse">"#,
+        r#"<path d="M 0 0 L 10 5 L 0 10 z" fill="context-stroke"/></marker></defs>"#,
+        "\n"
+    ));
+
+    for (x, y, piece) in board.cells() {
+        // Voids are left out, showing through to whatever the SVG is placed on.
+        if *piece == BoardPiece::Void {
+            continue;
+        }
+        let (px, py) = (x * CELL, y * CELL);
+        let fill = match piece {
+            BoardPiece::Blocker => "#333333",
+            BoardPiece::Pushable => "#999999",
+            BoardPiece::Lava(_) => "#ff7f0e",
+            _ => "white",
+        };
+        writeln!(
+            svg,
+            r##"<rect x="{px}" y="{py}" width="{CELL}" height="{CELL}" fill="{fill}" stroke="#cccccc"/>"##
+        )
+        .unwrap();
+        let (cx, cy) = (px + CELL / 2, py + CELL / 2);
+        match piece {
+            BoardPiece::Goal(index) => {
+                writeln!(
+                    svg,
+                    r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="#2ca02c" stroke-width="3"/>"##,
+                    CELL / 3
+                )
+                .unwrap();
+                // Number goals when their visiting order matters.
+                if goals > 1 {
+                    writeln!(
+                        svg,
+                        r##"<text x="{cx}" y="{cy}" font-size="{}" fill="#2ca02c" text-anchor="middle" dominant-baseline="central">{}</text>"##,
+                        CELL / 3,
+                        index + 1
+                    )
+                    .unwrap();
+                }
+            }
+            BoardPiece::HelperGoal(_) => writeln!(
+                svg,
+                r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="#1f77b4" stroke-width="3" stroke-dasharray="4 3"/>"##,
+                CELL / 3
+            )
+            .unwrap(),
+            BoardPiece::Mirror(slant, key) => {
+                let (ay, by) = match slant {
+                    Slant::Slash => (py + CELL, py),
+                    Slant::Backslash => (py, py + CELL),
+                };
+                let color = key.as_ref().map_or("#333333", piece_color);
+                writeln!(
+                    svg,
+                    r#"<line x1="{px}" y1="{ay}" x2="{}" y2="{by}" stroke="{color}" stroke-width="4"/>"#,
+                    px + CELL
+                )
+                .unwrap();
+            }
+            BoardPiece::OneWay(dir) => {
+                // Chevron pointing in the allowed direction.
+                let (r, t) = (CELL as isize / 4, CELL as isize / 8);
+                let (dx, dy) = match dir {
+                    Direction::Up => (0, -1),
+                    Direction::Down => (0, 1),
+                    Direction::Left => (-1, 0),
+                    Direction::Right => (1, 0),
+                };
+                let (cx, cy) = (cx as isize, cy as isize);
+                writeln!(
+                    svg,
+                    r##"<polyline points="{},{} {},{} {},{}" fill="none" stroke="#e6b800" stroke-width="3"/>"##,
+                    cx - dx * t + dy * r,
+                    cy - dy * t + dx * r,
+                    cx + dx * t,
+                    cy + dy * t,
+                    cx - dx * t - dy * r,
+                    cy - dy * t - dx * r
+                )
+                .unwrap();
+            }
+            BoardPiece::Portal(id) => writeln!(
+                svg,
+                r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="#e0c8f0" stroke="#9467bd" stroke-width="2"/><text x="{cx}" y="{cy}" font-size="{}" fill="#9467bd" text-anchor="middle" dominant-baseline="central">{}</text>"##,
+                CELL * 2 / 5,
+                CELL / 3,
+                id + 1
+            )
+            .unwrap(),
+            BoardPiece::Start => writeln!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#e6b800" stroke-width="3"/>"##,
+                px + CELL / 6,
+                py + CELL / 6,
+                CELL * 2 / 3,
+                CELL * 2 / 3
+            )
+            .unwrap(),
+            _ => {}
+        }
+    }
+
+    for (x, y, _) in board.cells() {
+        if board.walls(x, y) != 0 {
+            let (x0, y0, x1, y1) = (x * CELL, y * CELL, (x + 1) * CELL, (y + 1) * CELL);
+            for (side, (ax, ay, bx, by)) in [
+                (Direction::Up, (x0, y0, x1, y0)),
+                (Direction::Down, (x0, y1, x1, y1)),
+                (Direction::Left, (x0, y0, x0, y1)),
+                (Direction::Right, (x1, y0, x1, y1)),
+            ] {
+                if board.has_wall(x, y, &side) {
+                    writeln!(
+                        svg,
+                        r##"<line x1="{ax}" y1="{ay}" x2="{bx}" y2="{by}" stroke="#333333" stroke-width="5" stroke-linecap="square"/>"##
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    for (piece, pos) in [
+        (PieceType::Main, state.main_pos()),
+        (PieceType::HelperOne, state.helper_one_pos()),
+        (PieceType::HelperTwo, state.helper_two_pos()),
+    ] {
+        let (cx, cy) = center(&pos);
+        writeln!(
+            svg,
+            r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{}"/>"#,
+            CELL / 4,
+            piece_color(&piece)
+        )
+        .unwrap();
+    }
+
+    if let Some(moves) = solution {
+        let mut current = state;
+        for (m, next) in playback(board, state, moves) {
+            let path = slide_path(board, &current, &m.0, &m.1);
+            // Break the line where the piece wraps around the board edge.
+            let segments: Vec<&[Position]> = path
+                .chunk_by(|a, b| {
+                    pos_to_x(a).abs_diff(pos_to_x(b)) + pos_to_y(a).abs_diff(pos_to_y(b)) == 1
+                })
+                .collect();
+            for (i, segment) in segments.iter().enumerate() {
+                let points: Vec<String> = segment
+                    .iter()
+                    .map(|pos| {
+                        let (x, y) = center(pos);
+                        format!("{x},{y}")
+                    })
+                    .collect();
+                let marker = if i + 1 == segments.len() {
+                    r#" marker-end="url(#arrow)""#
+                } else {
+                    ""
+                };
+                writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2" stroke-opacity="0.7"{marker}/>"#,
+                    points.join(" "),
+                    piece_color(&m.0)
+                )
+                .unwrap();
+            }
+            current = next;
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Draws the board as 'to_svg' does, shading each cell by how often pieces pass over it in
+// 'heatmap', relative to the busiest cell, and writing how often they stop on it in its corner.
+pub fn heatmap_to_svg(board: &Board, state: State, heatmap: &Heatmap) -> String {
+    let mut svg = to_svg(board, state, None);
+    svg.truncate(svg.len() - "</svg>\n".len());
+    let busiest = heatmap
+        .traversed
+        .iter()
+        .flatten()
+        .max()
+        .map_or(1, |most| *most.max(&1));
+    for (y, row) in heatmap.traversed.iter().enumerate() {
+        for (x, traversed) in row.iter().enumerate() {
+            if *traversed == 0 {
+                continue;
+            }
+            let (px, py) = (x * CELL, y * CELL);
+            writeln!(
+                svg,
+                r##"<rect x="{px}" y="{py}" width="{CELL}" height="{CELL}" fill="#ff9900" fill-opacity="{:.2}"/>"##,
+                *traversed as f64 / busiest as f64 * 0.7
+            )
+            .unwrap();
+            let stopped = heatmap.stopped[y][x];
+            if stopped > 0 {
+                writeln!(
+                    svg,
+                    r##"<text x="{}" y="{}" font-size="10" fill="#000000">{stopped}</text>"##,
+                    px + 3,
+                    py + CELL - 4
+                )
+                .unwrap();
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
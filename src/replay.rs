@@ -0,0 +1,102 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+use crate::tools::{format_move, parse_move, puzzle_from_string, ParseError};
+use crate::validate::{validate, ValidationIssue};
+
+// A recorded game: the puzzle as read by 'puzzle_from_string' on the first line, then one move per
+// line as milliseconds since the game started followed by the move as written by 'format_move',
+// like '1500 Helper1 Left'. Blank lines and lines starting with '#' are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub puzzle: String,
+    pub moves: Vec<(u64, Move)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    MissingPuzzle,
+    // Line, counting from 1, that isn't a timestamp followed by a move.
+    Malformed(usize),
+    // Line, counting from 1, timestamped before the move ahead of it.
+    OutOfOrder(usize),
+    UnreadablePuzzle(ParseError),
+    InvalidPuzzle(ValidationIssue),
+    // Move, counting from 1, that can't be made or comes after the puzzle is solved.
+    IllegalMove(usize, Move),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::MissingPuzzle => write!(f, "replay has no puzzle"),
+            ReplayError::Malformed(line) => write!(f, "line {line} is not a timestamped move"),
+            ReplayError::OutOfOrder(line) => {
+                write!(f, "line {line} is timestamped before the move ahead of it")
+            }
+            ReplayError::UnreadablePuzzle(err) => write!(f, "unreadable puzzle: {err}"),
+            ReplayError::InvalidPuzzle(issue) => write!(f, "invalid puzzle: {issue}"),
+            ReplayError::IllegalMove(index, m) => {
+                write!(f, "move {index}, {}, is illegal", format_move(m))
+            }
+        }
+    }
+}
+
+impl Replay {
+    pub fn parse(input: &str) -> Result<Self, ReplayError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        let (_, puzzle) = lines.next().ok_or(ReplayError::MissingPuzzle)?;
+        let mut moves: Vec<(u64, Move)> = Vec::new();
+        for (line, text) in lines {
+            let (time, m) = text
+                .split_once(' ')
+                .and_then(|(time, m)| Some((time.parse().ok()?, parse_move(m.trim())?)))
+                .ok_or(ReplayError::Malformed(line))?;
+            if moves.last().is_some_and(|(last, _)| time < *last) {
+                return Err(ReplayError::OutOfOrder(line));
+            }
+            moves.push((time, m));
+        }
+        Ok(Self {
+            puzzle: puzzle.into(),
+            moves,
+        })
+    }
+
+    // Plays the moves from the start of the puzzle, giving the board with the starting state
+    // followed by the state after each move.
+    pub fn play(&self) -> Result<(Board, Vec<State>), ReplayError> {
+        let (board, state) =
+            puzzle_from_string(&self.puzzle).map_err(ReplayError::UnreadablePuzzle)?;
+        if let Some(issue) = validate(&board, &state).first() {
+            return Err(ReplayError::InvalidPuzzle(*issue));
+        }
+        let mut states = vec![state];
+        for (i, (_, m)) in self.moves.iter().enumerate() {
+            let current = states[states.len() - 1];
+            let next = apply_move(&board, &current, m)
+                .filter(|_| !is_solved(&board, &current))
+                .ok_or(ReplayError::IllegalMove(i + 1, *m))?;
+            states.push(next);
+        }
+        Ok((board, states))
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.puzzle)?;
+        for (time, m) in &self.moves {
+            writeln!(f, "{time} {}", format_move(m))?;
+        }
+        Ok(())
+    }
+}
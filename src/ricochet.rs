@@ -0,0 +1,214 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+
+const QUADRANT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Circle,
+    Triangle,
+    Square,
+    Hexagon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub color: Color,
+    pub symbol: Symbol,
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    // A quadrant has this many cells instead of 64.
+    CellCount(usize),
+    InvalidCell(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::CellCount(count) => write!(f, "quadrant has {count} cells, not 64"),
+            ImportError::InvalidCell(cell) => write!(f, "'{cell}' is not a quadrant cell"),
+        }
+    }
+}
+
+// A quadrant of a Ricochet Robots board, as drawn in the top left of the board with the center
+// in its bottom right corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quadrant {
+    // Walls of each cell as a combination of 'wall_bit's, row by row.
+    walls: [[u8; QUADRANT]; QUADRANT],
+    targets: Vec<(Color, Symbol, usize, usize)>,
+}
+
+fn parse_cell(cell: &str) -> Option<(u8, Option<(Color, Symbol)>)> {
+    if cell == "X" {
+        return Some((0, None));
+    }
+    let walls_end = cell
+        .find(|c| !matches!(c, 'N' | 'E' | 'S' | 'W'))
+        .unwrap_or(cell.len());
+    let mut walls = 0;
+    for side in cell[..walls_end].chars() {
+        walls |= wall_bit(&match side {
+            'N' => Direction::Up,
+            'E' => Direction::Right,
+            'S' => Direction::Down,
+            _ => Direction::Left,
+        });
+    }
+    let target = match &cell[walls_end..] {
+        "" => None,
+        target => {
+            let mut letters = target.chars();
+            let color = match letters.next()? {
+                'R' => Color::Red,
+                'G' => Color::Green,
+                'B' => Color::Blue,
+                'Y' => Color::Yellow,
+                _ => return None,
+            };
+            let symbol = match letters.next()? {
+                'C' => Symbol::Circle,
+                'T' => Symbol::Triangle,
+                'Q' => Symbol::Square,
+                'H' => Symbol::Hexagon,
+                _ => return None,
+            };
+            if letters.next().is_some() {
+                return None;
+            }
+            Some((color, symbol))
+        }
+    };
+    Some((walls, target))
+}
+
+// Reads a quadrant in the format common to Ricochet Robots solvers: 64 comma separated cells row
+// by row, each one 'X' for a plain cell or the sides with a wall out of 'N', 'E', 'S' and 'W',
+// optionally followed by a target as a color out of 'R', 'G', 'B' and 'Y' and a symbol out of
+// 'C'ircle, 'T'riangle, 'Q' for square and 'H'exagon. 'NWGT' is a green triangle with walls on
+// its top and left sides. Whitespace is skipped, so quadrants can be split over lines.
+pub fn parse_quadrant(input: &str) -> Result<Quadrant, ImportError> {
+    let cells: Vec<String> = input
+        .split(',')
+        .map(|cell| cell.chars().filter(|c| !c.is_whitespace()).collect())
+        .filter(|cell: &String| !cell.is_empty())
+        .collect();
+    if cells.len() != QUADRANT * QUADRANT {
+        return Err(ImportError::CellCount(cells.len()));
+    }
+    let mut quadrant = Quadrant {
+        walls: [[0; QUADRANT]; QUADRANT],
+        targets: Vec::new(),
+    };
+    for (i, cell) in cells.iter().enumerate() {
+        let (x, y) = (i % QUADRANT, i / QUADRANT);
+        let (walls, target) =
+            parse_cell(cell).ok_or_else(|| ImportError::InvalidCell(cell.to_string()))?;
+        quadrant.walls[y][x] = walls;
+        if let Some((color, symbol)) = target {
+            quadrant.targets.push((color, symbol, x, y));
+        }
+    }
+    Ok(quadrant)
+}
+
+// A 16x16 board put together from four quadrants, with the targets on it.
+#[derive(Clone)]
+pub struct RicochetBoard {
+    pub board: Board,
+    pub targets: Vec<Target>,
+}
+
+// Puts 'quadrants' on a 16x16 board top left, top right, bottom right and bottom left, each one
+// turned clockwise so its corner with the center is in the middle of the board.
+pub fn import_board(quadrants: &[Quadrant; 4]) -> RicochetBoard {
+    let size = QUADRANT * 2;
+    let mut board = Board::new(size, size);
+    let mut targets = Vec::new();
+    for (turns, quadrant) in quadrants.iter().enumerate() {
+        let (offset_x, offset_y) =
+            [(0, 0), (QUADRANT, 0), (QUADRANT, QUADRANT), (0, QUADRANT)][turns];
+        // Position of x,y in the quadrant once turned, and the side a wall ends up on.
+        let place = |(mut x, mut y): (usize, usize)| {
+            for _ in 0..turns {
+                (x, y) = (QUADRANT - 1 - y, x);
+            }
+            (offset_x + x, offset_y + y)
+        };
+        let turn = |mut side: Direction| {
+            for _ in 0..turns {
+                side = match side {
+                    Direction::Up => Direction::Right,
+                    Direction::Right => Direction::Down,
+                    Direction::Down => Direction::Left,
+                    Direction::Left => Direction::Up,
+                };
+            }
+            side
+        };
+        for (y, row) in quadrant.walls.iter().enumerate() {
+            for (x, walls) in row.iter().enumerate() {
+                let (x, y) = place((x, y));
+                for side in [
+                    Direction::Up,
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                ] {
+                    if walls & wall_bit(&side) != 0 {
+                        board.add_wall(x, y, turn(side));
+                    }
+                }
+            }
+        }
+        for (color, symbol, x, y) in &quadrant.targets {
+            let (x, y) = place((*x, *y));
+            targets.push(Target {
+                color: *color,
+                symbol: *symbol,
+                pos: xy_to_pos(x, y),
+            });
+        }
+    }
+    RicochetBoard { board, targets }
+}
+
+impl RicochetBoard {
+    // Puzzle of moving the main piece onto 'target', the target cell becoming the start cell
+    // with no goals on the board, so the puzzle is solved once the main piece stops there. The
+    // main piece starts off the start cell, so such puzzles don't pass 'validate' or survive
+    // 'puzzle_to_string' but can be solved as they are. The board has room for two more robots
+    // as helpers, any others can be given as 'fixed' cells that are blocked for the whole round.
+    pub fn round(
+        &self,
+        target: &Target,
+        main: Position,
+        helpers: [Position; 2],
+        fixed: &[Position],
+    ) -> (Board, State) {
+        let mut board = self.board.clone();
+        let at = |pos: &Position| (pos_to_x(pos), pos_to_y(pos));
+        let (x, y) = at(&target.pos);
+        board.set(x, y, BoardPiece::Start);
+        for pos in fixed {
+            let (x, y) = at(pos);
+            board.set(x, y, BoardPiece::Blocker);
+        }
+        (board, State::new(main, helpers[0], helpers[1]))
+    }
+}
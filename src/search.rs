@@ -0,0 +1,304 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::astar::{solve_astar, solve_astar_with};
+use crate::bidirectional::solve_bidirectional;
+use crate::heuristic::Heuristic;
+use crate::ida::{solve_ida_star, solve_ida_star_with};
+use crate::parallel::solve_parallel;
+use crate::solver::*;
+use crate::tablebase::Tablebase;
+
+// A way of solving puzzles, so algorithms can be picked at runtime and compared on equal terms.
+pub trait Search {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome;
+}
+
+// Breadth first search, see 'solve_puzzle_with'.
+#[derive(Debug, Clone, Default)]
+pub struct Bfs(pub SolveOptions);
+
+impl Search for Bfs {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        solve_puzzle_with(board, state, &self.0)
+    }
+}
+
+// See 'solve_astar'.
+#[derive(Debug, Clone, Default)]
+pub struct Astar(pub SolveOptions);
+
+impl Search for Astar {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        solve_astar(board, state, &self.0)
+    }
+}
+
+// See 'solve_ida_star'.
+#[derive(Debug, Clone, Default)]
+pub struct IdaStar(pub SolveOptions);
+
+impl Search for IdaStar {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        solve_ida_star(board, state, &self.0)
+    }
+}
+
+// See 'solve_bidirectional'.
+#[derive(Debug, Clone, Default)]
+pub struct Bidirectional(pub SolveOptions);
+
+impl Search for Bidirectional {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        solve_bidirectional(board, state, &self.0)
+    }
+}
+
+// See 'solve_parallel'.
+#[derive(Debug, Clone, Default)]
+pub struct ParallelBfs(pub SolveOptions);
+
+impl Search for ParallelBfs {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        solve_parallel(board, state, &self.0)
+    }
+}
+
+// Search used to solve a puzzle, every one of them giving optimal solutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    // Breadth first, the first optimal solution in 'TieBreak' order.
+    #[default]
+    Bfs,
+    // A*, usually expanding far fewer states.
+    Astar,
+    // Iterative deepening A*, same solutions as breadth first.
+    IdaStar,
+    // Breadth first from both the start and the solved states. Still slower than 'Bfs', so left
+    // out of 'Algorithm::ALL'.
+    Bidirectional,
+    // Breadth first on every core, solutions as short as 'Bfs'.
+    ParallelBfs,
+}
+
+impl Algorithm {
+    // Algorithms worth picking between, the ones 'bench' compares.
+    pub const ALL: [Algorithm; 4] = [
+        Algorithm::Bfs,
+        Algorithm::Astar,
+        Algorithm::IdaStar,
+        Algorithm::ParallelBfs,
+    ];
+
+    // Algorithms that can be picked by name but don't pay off yet.
+    pub const EXPERIMENTAL: [Algorithm; 1] = [Algorithm::Bidirectional];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Bfs => "bfs",
+            Algorithm::Astar => "astar",
+            Algorithm::IdaStar => "ida",
+            Algorithm::Bidirectional => "bidirectional",
+            Algorithm::ParallelBfs => "parallel",
+        }
+    }
+
+    pub fn with_options(&self, options: SolveOptions) -> Box<dyn Search + Send + Sync> {
+        match self {
+            Algorithm::Bfs => Box::new(Bfs(options)),
+            Algorithm::Astar => Box::new(Astar(options)),
+            Algorithm::IdaStar => Box::new(IdaStar(options)),
+            Algorithm::Bidirectional => Box::new(Bidirectional(options)),
+            Algorithm::ParallelBfs => Box::new(ParallelBfs(options)),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .chain(Algorithm::EXPERIMENTAL)
+            .find(|algorithm| algorithm.name() == name)
+            .ok_or_else(|| format!("Unknown algorithm {name}."))
+    }
+}
+
+// A configured search, built with 'Solver::builder' and reusable for any number of puzzles.
+#[derive(Clone, Default)]
+pub struct Solver {
+    algorithm: Algorithm,
+    options: SolveOptions,
+    // Guides A* and IDA* instead of their default.
+    heuristic: Option<Arc<dyn Heuristic + Send + Sync>>,
+    // Answers puzzles on its board without searching.
+    tablebase: Option<Arc<Tablebase>>,
+}
+
+impl fmt::Debug for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Solver")
+            .field("algorithm", &self.algorithm)
+            .field("options", &self.options)
+            .field("heuristic", &self.heuristic.is_some())
+            .field("tablebase", &self.tablebase.is_some())
+            .finish()
+    }
+}
+
+impl Solver {
+    pub fn builder() -> SolverBuilder {
+        SolverBuilder::default()
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn options(&self) -> &SolveOptions {
+        &self.options
+    }
+
+    pub fn solve(&self, board: &Board, state: State) -> SolveOutcome {
+        let options = &self.options;
+        let looked_up = self
+            .tablebase
+            .as_ref()
+            .and_then(|tablebase| tablebase.solve(board, state, &options.tie_break));
+        if let Some(outcome) = looked_up {
+            // Limited like a search, where the smaller of the two limits is the one reached.
+            let upper_bound = options.upper_bound.unwrap_or(usize::MAX);
+            let max_depth = options.max_depth.unwrap_or(usize::MAX);
+            return match outcome {
+                SolveOutcome::Solved(solution)
+                    if solution.moves.len() > max_depth && max_depth <= upper_bound =>
+                {
+                    SolveOutcome::NotWithin(max_depth, solution.stats)
+                }
+                SolveOutcome::Solved(solution) if solution.moves.len() > upper_bound => {
+                    let lower_bound = solution.moves.len();
+                    SolveOutcome::LimitReached(
+                        SolveError::BudgetExceeded { lower_bound },
+                        solution.stats,
+                    )
+                }
+                outcome => outcome,
+            };
+        }
+        match (self.algorithm, &self.heuristic) {
+            (Algorithm::Bfs, _) => solve_puzzle_with(board, state, options),
+            (Algorithm::Astar, None) => solve_astar(board, state, options),
+            (Algorithm::Astar, Some(h)) => solve_astar_with(board, state, options, h.as_ref()),
+            (Algorithm::IdaStar, None) => solve_ida_star(board, state, options),
+            (Algorithm::IdaStar, Some(h)) => solve_ida_star_with(board, state, options, h.as_ref()),
+            (Algorithm::Bidirectional, _) => solve_bidirectional(board, state, options),
+            (Algorithm::ParallelBfs, _) => solve_parallel(board, state, options),
+        }
+    }
+}
+
+impl Search for Solver {
+    fn search(&self, board: &Board, state: State) -> SolveOutcome {
+        self.solve(board, state)
+    }
+}
+
+// Options left out keep their defaults, an unlimited breadth first search.
+#[derive(Debug, Clone, Default)]
+pub struct SolverBuilder {
+    solver: Solver,
+}
+
+impl SolverBuilder {
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.solver.algorithm = algorithm;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.solver.options.timeout = Some(timeout);
+        self
+    }
+
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.solver.options.cancel = Some(cancel);
+        self
+    }
+
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.solver.options.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn max_queue(mut self, max_queue: usize) -> Self {
+        self.solver.options.max_queue = Some(max_queue);
+        self
+    }
+
+    pub fn upper_bound(mut self, upper_bound: usize) -> Self {
+        self.solver.options.upper_bound = Some(upper_bound);
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.solver.options.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(&SearchProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.solver.options.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+
+    pub fn progress_interval(mut self, interval: usize) -> Self {
+        self.solver.options.progress_interval = Some(interval);
+        self
+    }
+
+    // Only called by 'Algorithm::Bfs', see 'SolveOptions::on_trace'.
+    pub fn on_trace(mut self, on_trace: impl Fn(&TraceEvent) + Send + Sync + 'static) -> Self {
+        self.solver.options.on_trace = Some(Arc::new(on_trace));
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.solver.options.tie_break = tie_break;
+        self
+    }
+
+    // Threads for 'Algorithm::ParallelBfs', all cores by default.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.solver.options.threads = Some(threads);
+        self
+    }
+
+    // Only used by A* and IDA*, which otherwise build a 'RicochetLowerBound' for every puzzle.
+    pub fn heuristic(mut self, heuristic: impl Heuristic + Send + Sync + 'static) -> Self {
+        self.solver.heuristic = Some(Arc::new(heuristic));
+        self
+    }
+
+    // Puzzles on the board 'tablebase' was built for are looked up in it whatever the algorithm,
+    // others are searched as usual.
+    pub fn tablebase(mut self, tablebase: Arc<Tablebase>) -> Self {
+        self.solver.tablebase = Some(tablebase);
+        self
+    }
+
+    pub fn build(self) -> Solver {
+        self.solver
+    }
+}
@@ -0,0 +1,176 @@
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::Router;
+use json::{object, JsonValue};
+
+use crate::solver::{
+    solve_puzzle_with, Board, CancellationToken, Move, SolveError, SolveOptions, State,
+};
+use crate::tools::{format_move, puzzle_from_string};
+use crate::validate::validate;
+
+// Longest a single search may run before the server gives up on it.
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn error(status: StatusCode, message: &str) -> (StatusCode, String) {
+    (status, object! { error: message }.dump())
+}
+
+// Accepts either a raw puzzle string or a JSON object with the puzzle in its "map" field.
+fn puzzle_from_body(body: &str) -> Option<String> {
+    let body = body.trim();
+    if !body.starts_with('{') {
+        return Some(body.to_string());
+    }
+    json::parse(body).ok()?["map"].as_str().map(str::to_string)
+}
+
+// Parses and validates a puzzle, giving the reason it can't be solved as the error.
+fn read_puzzle(puzzle: &str) -> Result<(Board, State), String> {
+    let (board, state) =
+        puzzle_from_string(puzzle).map_err(|err| format!("invalid puzzle: {err}"))?;
+    match validate(&board, &state).first() {
+        Some(issue) => Err(format!("invalid puzzle: {issue}")),
+        None => Ok((board, state)),
+    }
+}
+
+// Cancels the search when dropped, such as when the client goes away before it finishes.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+fn solution_json(moves: Option<Vec<Move>>) -> JsonValue {
+    match moves {
+        Some(moves) => object! {
+            solvable: true,
+            length: moves.len(),
+            moves: moves.iter().map(format_move).collect::<Vec<_>>(),
+        },
+        None => object! { solvable: false },
+    }
+}
+
+async fn solve(body: String) -> (StatusCode, String) {
+    let Some(puzzle) = puzzle_from_body(&body) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "expected a puzzle string or {\"map\": ...}",
+        );
+    };
+    let (board, state) = match read_puzzle(&puzzle) {
+        Ok(puzzle) => puzzle,
+        Err(message) => return error(StatusCode::BAD_REQUEST, &message),
+    };
+    let cancel = CancelOnDrop(CancellationToken::new());
+    let options = SolveOptions {
+        timeout: Some(SOLVE_TIMEOUT),
+        cancel: Some(cancel.0.clone()),
+        ..SolveOptions::default()
+    };
+    let solved = tokio::task::spawn_blocking(move || {
+        solve_puzzle_with(&board, state, &options)
+            .into_result()
+            .map(|solution| solution.map(|solution| solution.moves))
+    })
+    .await;
+    match solved {
+        Ok(Ok(moves)) => (StatusCode::OK, solution_json(moves).dump()),
+        Ok(Err(SolveError::Timeout)) => error(StatusCode::SERVICE_UNAVAILABLE, "search timed out"),
+        Ok(Err(err)) => error(StatusCode::INTERNAL_SERVER_ERROR, &err.to_string()),
+        Err(_) => error(StatusCode::INTERNAL_SERVER_ERROR, "solver failed"),
+    }
+}
+
+async fn solve_ws(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(stream_solve)
+}
+
+// Reads the puzzle from the first message, then streams progress events and finally the solution.
+async fn stream_solve(mut socket: WebSocket) {
+    let Some(Ok(Message::Text(body))) = socket.recv().await else {
+        return;
+    };
+    let Some(puzzle) = puzzle_from_body(&body) else {
+        let event = object! { event: "error", error: "expected a puzzle string or {\"map\": ...}" };
+        let _ = socket.send(Message::Text(event.dump().into())).await;
+        return;
+    };
+    let (board, state) = match read_puzzle(&puzzle) {
+        Ok(puzzle) => puzzle,
+        Err(message) => {
+            let event = object! { event: "error", error: message };
+            let _ = socket.send(Message::Text(event.dump().into())).await;
+            return;
+        }
+    };
+
+    let cancel = CancellationToken::new();
+    let (send, mut progress) = tokio::sync::mpsc::unbounded_channel();
+    let options = SolveOptions {
+        timeout: Some(SOLVE_TIMEOUT),
+        cancel: Some(cancel.clone()),
+        on_progress: Some(Arc::new(move |p| {
+            let _ = send.send(*p);
+        })),
+        ..SolveOptions::default()
+    };
+    let solver = tokio::task::spawn_blocking(move || {
+        solve_puzzle_with(&board, state, &options)
+            .into_result()
+            .map(|solution| solution.map(|solution| solution.moves))
+    });
+
+    // The channel closes once the solver finishes and drops its sender.
+    while let Some(p) = progress.recv().await {
+        let event = object! {
+            event: "progress",
+            depth: p.depth,
+            frontier: p.frontier,
+            expanded: p.expanded,
+        };
+        if socket
+            .send(Message::Text(event.dump().into()))
+            .await
+            .is_err()
+        {
+            // Client went away, stop burning a blocking thread on its puzzle.
+            cancel.cancel();
+            return;
+        }
+    }
+
+    let event = match solver.await {
+        Ok(Ok(moves)) => {
+            let mut event = solution_json(moves);
+            event["event"] = "solution".into();
+            event
+        }
+        Ok(Err(err)) => object! { event: "error", error: err.to_string() },
+        Err(_) => object! { event: "error", error: "solver failed" },
+    };
+    let _ = socket.send(Message::Text(event.dump().into())).await;
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/solve", post(solve))
+        .route("/solve/ws", get(solve_ws))
+        .route("/healthz", get(|| async { "ok" }))
+}
+
+// Serves the solver over HTTP on 'addr' until the process is stopped.
+pub async fn serve(addr: &str) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router()).await
+}
@@ -0,0 +1,166 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::solver::*;
+#[cfg(feature = "json")]
+use crate::tools::{format_move, parse_move, puzzle_from_string, puzzle_to_string};
+#[cfg(feature = "json")]
+use crate::validate::validate;
+
+// A puzzle being played: the board, the moves made so far and the states they led to. Moves can
+// be undone and redone until a new move is made.
+#[derive(Clone)]
+pub struct Session {
+    board: Board,
+    // The starting state followed by the state after each move of 'history'.
+    states: Vec<State>,
+    history: Vec<Move>,
+    // Moves undone, the most recently undone last.
+    undone: Vec<Move>,
+    // Fewest moves left from states met before, None for unsolvable ones. Filled in along each
+    // optimal solution found, so following it or going back to a state costs no search.
+    remaining: BTreeMap<State, Option<usize>>,
+}
+
+impl Session {
+    pub fn new(board: Board, state: State) -> Self {
+        Self {
+            board,
+            states: vec![state],
+            history: Vec::new(),
+            undone: Vec::new(),
+            remaining: BTreeMap::new(),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn start(&self) -> State {
+        self.states[0]
+    }
+
+    pub fn state(&self) -> State {
+        *self.states.last().unwrap()
+    }
+
+    // Moves made so far, undone ones left out.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    // Makes move 'm', giving false and leaving the session as it was if it is illegal. The moves
+    // undone can't be redone anymore.
+    pub fn apply(&mut self, m: Move) -> bool {
+        let Some(next) = apply_move(&self.board, &self.state(), &m) else {
+            return false;
+        };
+        self.states.push(next);
+        self.history.push(m);
+        self.undone.clear();
+        true
+    }
+
+    // Takes back the last move, None if there are none.
+    pub fn undo(&mut self) -> Option<Move> {
+        let m = self.history.pop()?;
+        self.states.pop();
+        self.undone.push(m);
+        Some(m)
+    }
+
+    // Makes the last move undone again, None if there is none.
+    pub fn redo(&mut self) -> Option<Move> {
+        let m = self.undone.pop()?;
+        let next = apply_move(&self.board, &self.state(), &m).expect("Undone move is legal.");
+        self.states.push(next);
+        self.history.push(m);
+        Some(m)
+    }
+
+    // Undoes every move, they can all be redone.
+    pub fn reset(&mut self) {
+        while self.undo().is_some() {}
+    }
+
+    pub fn is_solved(&self) -> bool {
+        is_solved(&self.board, &self.state())
+    }
+
+    // Fewest moves left to solve the puzzle from the current state, None if it can't be solved.
+    // Searches only for states off every solution found so far: no state after an unsolvable one
+    // can be solved, and the rest of an optimal solution is optimal from each of its states.
+    pub fn optimal_remaining(&mut self) -> Option<usize> {
+        let state = self.state();
+        if let Some(remaining) = self.remaining.get(&state) {
+            return *remaining;
+        }
+        let previous = self.states.len().checked_sub(2).map(|i| self.states[i]);
+        if previous.is_some_and(|previous| self.remaining.get(&previous) == Some(&None)) {
+            self.remaining.insert(state, None);
+            return None;
+        }
+        let Some(solution) = solve_puzzle(&self.board, state) else {
+            self.remaining.insert(state, None);
+            return None;
+        };
+        let mut current = state;
+        for (i, m) in solution.moves.iter().enumerate() {
+            self.remaining
+                .insert(current, Some(solution.moves.len() - i));
+            current = apply_move(&self.board, &current, m).expect("Solution move is legal.");
+        }
+        self.remaining.insert(current, Some(0));
+        Some(solution.moves.len())
+    }
+}
+
+// Saved as a JSON object with the puzzle as written by 'puzzle_to_string', the moves made and
+// undone in the order they were made and the current state, which loading checks against the one
+// the moves lead to. Like 'puzzle_to_string', the start must have the main piece on the start cell
+// and no goals reached.
+#[cfg(feature = "json")]
+impl Session {
+    pub fn to_json(&self) -> String {
+        let moves = |moves: &[Move]| moves.iter().map(format_move).collect::<Vec<_>>();
+        let mut undone = self.undone.clone();
+        undone.reverse();
+        json::object! {
+            puzzle: puzzle_to_string(&self.board, &self.start()),
+            history: moves(&self.history),
+            undone: moves(&undone),
+            state: self.state().to_bits(),
+        }
+        .dump()
+    }
+
+    // Restores a session saved by 'to_json', None if it or its puzzle is malformed, the puzzle
+    // fails 'validate' or its moves are illegal.
+    pub fn from_json(input: &str) -> Option<Self> {
+        let saved = json::parse(input).ok()?;
+        let (board, state) = puzzle_from_string(saved["puzzle"].as_str()?).ok()?;
+        if !validate(&board, &state).is_empty() {
+            return None;
+        }
+        let moves = |value: &json::JsonValue| -> Option<Vec<Move>> {
+            if !value.is_array() {
+                return None;
+            }
+            value.members().map(|m| parse_move(m.as_str()?)).collect()
+        };
+        let mut session = Self::new(board, state);
+        let history = moves(&saved["history"])?;
+        let undone = moves(&saved["undone"])?;
+        for m in history.iter().chain(&undone) {
+            if !session.apply(*m) {
+                return None;
+            }
+        }
+        for _ in &undone {
+            session.undo();
+        }
+        (session.state().to_bits() == saved["state"].as_u32()?).then_some(session)
+    }
+}
@@ -0,0 +1,112 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::compact::{from_bytes, to_bytes};
+use crate::solver::*;
+
+// Crockford's base32, digits and capitals without I, L, O and U, so codes can't be misread as
+// other characters or spell words.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const GROUP: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareCodeError {
+    InvalidCharacter(char),
+    // The code was mistyped or misheard somewhere.
+    ChecksumMismatch,
+    // The checksum holds but the code isn't a puzzle, e.g. from a newer version.
+    NotAPuzzle,
+}
+
+impl fmt::Display for ShareCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareCodeError::InvalidCharacter(c) => write!(f, "'{c}' can't be in a share code"),
+            ShareCodeError::ChecksumMismatch => write!(f, "share code has a typo"),
+            ShareCodeError::NotAPuzzle => write!(f, "share code is not a puzzle"),
+        }
+    }
+}
+
+// CRC-16/CCITT-FALSE, which catches any run of up to three wrong characters.
+fn checksum(bytes: &[u8]) -> u16 {
+    let mut crc = 0xffff_u16;
+    for byte in bytes {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+// Value of a share code character, reading the letters that look like digits as those digits
+// and ignoring case.
+fn value(c: char) -> Option<u8> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        c => c,
+    };
+    ALPHABET
+        .iter()
+        .position(|a| char::from(*a) == c)
+        .map(|i| i as u8)
+}
+
+// Writes a puzzle as a code meant to be read aloud or typed in: the bits of 'encode_compact' and a
+// 16 bit checksum in base32, in groups of four characters joined by dashes. A plain 8x8 puzzle
+// takes about ten groups.
+pub fn encode_share_code(board: &Board, state: &State) -> String {
+    let mut bytes = to_bytes(board, state);
+    bytes.extend_from_slice(&checksum(&bytes).to_be_bytes());
+    let mut chars = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0_u32, 0);
+    for byte in bytes {
+        buffer = buffer << 8 | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            chars.push(ALPHABET[(buffer >> bits & 31) as usize]);
+        }
+    }
+    if bits > 0 {
+        chars.push(ALPHABET[(buffer << (5 - bits) & 31) as usize]);
+    }
+    let groups: Vec<&str> = chars
+        .chunks(GROUP)
+        .map(|group| core::str::from_utf8(group).unwrap())
+        .collect();
+    groups.join("-")
+}
+
+// Reads a code written by 'encode_share_code'. Dashes and whitespace are skipped, case doesn't
+// matter and O, I and L are read as 0, 1 and 1.
+pub fn decode_share_code(code: &str) -> Result<(Board, State), ShareCodeError> {
+    let mut bytes = Vec::new();
+    let (mut buffer, mut bits) = (0_u32, 0);
+    for c in code.chars().filter(|c| *c != '-' && !c.is_whitespace()) {
+        let value = value(c).ok_or(ShareCodeError::InvalidCharacter(c))?;
+        buffer = buffer << 5 | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    // Padding is always zeros, so a typo in the last character is caught even if it only
+    // changes those bits.
+    if bytes.len() < 2 || buffer & ((1 << bits) - 1) != 0 {
+        return Err(ShareCodeError::ChecksumMismatch);
+    }
+    let (puzzle, sum) = bytes.split_at(bytes.len() - 2);
+    if checksum(puzzle).to_be_bytes() != sum {
+        return Err(ShareCodeError::ChecksumMismatch);
+    }
+    from_bytes(puzzle).ok_or(ShareCodeError::NotAPuzzle)
+}
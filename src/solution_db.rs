@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::canonical::{fingerprint_with_symmetry, Symmetry};
+use crate::solver::*;
+
+const MAGIC: &[u8; 4] = b"SSDB";
+const VERSION: u8 = 1;
+// Magic bytes, version and record count.
+const HEADER: usize = 9;
+// Fingerprint and record offset.
+const INDEX_ENTRY: usize = 12;
+// Stored as the length of puzzles that can't be solved.
+const UNSOLVABLE: u16 = u16::MAX;
+
+// What is known about a stored puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    // An optimal solution.
+    Solved(Vec<Move>),
+    Unsolvable,
+}
+
+impl Record {
+    pub fn optimal_length(&self) -> Option<usize> {
+        match self {
+            Record::Solved(moves) => Some(moves.len()),
+            Record::Unsolvable => None,
+        }
+    }
+
+    // The solution, None if the puzzle can't be solved.
+    pub fn into_moves(self) -> Option<Vec<Move>> {
+        match self {
+            Record::Solved(moves) => Some(moves),
+            Record::Unsolvable => None,
+        }
+    }
+}
+
+// Solved puzzles keyed by 'fingerprint', so a puzzle is found again in any rotation or reflection
+// and with the helpers swapped. Solutions are kept for the canonical form and mapped to the
+// orientation asked for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolutionDatabase {
+    records: BTreeMap<u64, Record>,
+}
+
+pub(crate) fn encode_move(m: &Move) -> u8 {
+    let piece = match m.0 {
+        PieceType::Main => 0,
+        PieceType::HelperOne => 1,
+        PieceType::HelperTwo => 2,
+    };
+    let dir = match m.1 {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    };
+    piece << 2 | dir
+}
+
+pub(crate) fn decode_move(byte: u8) -> io::Result<Move> {
+    let piece = match byte >> 2 {
+        0 => PieceType::Main,
+        1 => PieceType::HelperOne,
+        2 => PieceType::HelperTwo,
+        _ => return Err(invalid("unknown piece in a stored move")),
+    };
+    let dir = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ][byte as usize & 3];
+    Ok((piece, dir))
+}
+
+// Takes a record stored for the canonical form back to the puzzle 'symmetry' was found for.
+pub(crate) fn unmap(record: &Record, symmetry: &Symmetry) -> Record {
+    match record {
+        Record::Solved(moves) => {
+            Record::Solved(moves.iter().map(|m| symmetry.unmap_move(m)).collect())
+        }
+        Record::Unsolvable => Record::Unsolvable,
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_header(input: &mut impl Read) -> io::Result<usize> {
+    let mut header = [0; HEADER];
+    input.read_exact(&mut header)?;
+    if &header[..4] != MAGIC || header[4] != VERSION {
+        return Err(invalid("not a solution database"));
+    }
+    Ok(u32::from_le_bytes(header[5..].try_into().unwrap()) as usize)
+}
+
+// The buffer grows with the bytes actually read, so a corrupt count fails on the short index
+// rather than allocating whatever it asks for.
+fn read_index(input: &mut impl Read, count: usize) -> io::Result<Vec<(u64, u32)>> {
+    let length = count
+        .checked_mul(INDEX_ENTRY)
+        .ok_or_else(|| invalid("index too large"))?;
+    let mut index = Vec::new();
+    input.take(length as u64).read_to_end(&mut index)?;
+    if index.len() != length {
+        return Err(invalid("index cut short"));
+    }
+    Ok(index
+        .chunks_exact(INDEX_ENTRY)
+        .map(|entry| {
+            (
+                u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                u32::from_le_bytes(entry[8..].try_into().unwrap()),
+            )
+        })
+        .collect())
+}
+
+// Reads the record at the current position of 'input'.
+fn read_record(input: &mut impl Read) -> io::Result<Record> {
+    let mut length = [0; 2];
+    input.read_exact(&mut length)?;
+    match u16::from_le_bytes(length) {
+        UNSOLVABLE => Ok(Record::Unsolvable),
+        length => {
+            let mut moves = vec![0; length as usize];
+            input.read_exact(&mut moves)?;
+            let moves = moves
+                .into_iter()
+                .map(decode_move)
+                .collect::<io::Result<_>>()?;
+            Ok(Record::Solved(moves))
+        }
+    }
+}
+
+impl SolutionDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    // Stores an optimal solution for the puzzle, or None if it can't be solved, replacing what
+    // was stored for it before.
+    pub fn insert(&mut self, board: &Board, state: &State, moves: Option<&[Move]>) {
+        let (fingerprint, symmetry) = fingerprint_with_symmetry(board, state);
+        let record = match moves {
+            Some(moves) => Record::Solved(moves.iter().map(|m| symmetry.map_move(m)).collect()),
+            None => Record::Unsolvable,
+        };
+        self.records.insert(fingerprint, record);
+    }
+
+    // What is stored for the puzzle, with the moves of a solution fitting the puzzle as given.
+    pub fn get(&self, board: &Board, state: &State) -> Option<Record> {
+        let (fingerprint, symmetry) = fingerprint_with_symmetry(board, state);
+        Some(unmap(self.records.get(&fingerprint)?, &symmetry))
+    }
+
+    // Writes, with numbers in little endian:
+    // - the magic bytes 'SSDB', a version byte and the number of records as a u32
+    // - the index, a u64 fingerprint and the u32 offset of its record from the end of the index
+    //   for each record, in ascending order of fingerprint
+    // - the records, a u16 solution length (u16::MAX if unsolvable) followed by a byte per move,
+    //   the piece (0 main, 1 HelperOne, 2 HelperTwo) shifted left by 2 and the direction (0 up,
+    //   1 down, 2 left, 3 right) in the low bits
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let count =
+            u32::try_from(self.records.len()).map_err(|_| invalid("too many records to write"))?;
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&count.to_le_bytes())?;
+        let mut offset = 0u32;
+        for (fingerprint, record) in &self.records {
+            out.write_all(&fingerprint.to_le_bytes())?;
+            out.write_all(&offset.to_le_bytes())?;
+            let size = 2 + record.optimal_length().unwrap_or(0);
+            offset = u32::try_from(size)
+                .ok()
+                .and_then(|size| offset.checked_add(size))
+                .ok_or_else(|| invalid("records too large to write"))?;
+        }
+        for record in self.records.values() {
+            match record {
+                Record::Solved(moves) => {
+                    let length = u16::try_from(moves.len())
+                        .ok()
+                        .filter(|length| *length != UNSOLVABLE)
+                        .ok_or_else(|| invalid("solution too long to write"))?;
+                    out.write_all(&length.to_le_bytes())?;
+                    out.write_all(&moves.iter().map(encode_move).collect::<Vec<_>>())?;
+                }
+                Record::Unsolvable => out.write_all(&UNSOLVABLE.to_le_bytes())?,
+            }
+        }
+        Ok(())
+    }
+
+    // Reads a whole database written by 'write'.
+    pub fn read(mut input: impl Read) -> io::Result<Self> {
+        let count = read_header(&mut input)?;
+        let index = read_index(&mut input, count)?;
+        let mut records = BTreeMap::new();
+        // Records follow in index order, so they are read straight through.
+        for (fingerprint, _) in index {
+            records.insert(fingerprint, read_record(&mut input)?);
+        }
+        Ok(Self { records })
+    }
+
+    // Looks up a single puzzle in a database written by 'write' without loading the rest,
+    // reading only the header, the index and the one record.
+    pub fn lookup(
+        mut input: impl Read + Seek,
+        board: &Board,
+        state: &State,
+    ) -> io::Result<Option<Record>> {
+        let count = read_header(&mut input)?;
+        let index = read_index(&mut input, count)?;
+        let (fingerprint, symmetry) = fingerprint_with_symmetry(board, state);
+        let Ok(found) = index.binary_search_by_key(&fingerprint, |(fingerprint, _)| *fingerprint)
+        else {
+            return Ok(None);
+        };
+        let records = (HEADER + count * INDEX_ENTRY) as u64;
+        input.seek(SeekFrom::Start(records + index[found].1 as u64))?;
+        Ok(Some(unmap(&read_record(&mut input)?, &symmetry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_counts_are_invalid_data() {
+        let mut board = Board::new(4, 4);
+        board.set(0, 0, BoardPiece::Start);
+        board.set(3, 3, BoardPiece::Goal(0));
+        let state = State::new(xy_to_pos(0, 0), xy_to_pos(3, 0), xy_to_pos(0, 3));
+        let mut db = SolutionDatabase::new();
+        db.insert(&board, &state, None);
+        let mut bytes = Vec::new();
+        db.write(&mut bytes).unwrap();
+        bytes[5..HEADER].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = SolutionDatabase::read(bytes.as_slice()).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = SolutionDatabase::lookup(io::Cursor::new(&bytes), &board, &state);
+        assert_eq!(error.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::analysis::StateSpace;
+use crate::solver::*;
+
+// Distance of states no solved state can be reached from, and index of states not reached.
+const UNREACHABLE: u32 = u32::MAX;
+
+// Every solution of a puzzle, shortest first, see 'solutions'.
+pub struct Solutions {
+    // Moves out of state 'i' are 'edges[starts[i]..starts[i + 1]]', state 0 being the start.
+    starts: Vec<u32>,
+    edges: Vec<(Move, u32)>,
+    // Fewest moves from each state to a solved one, UNREACHABLE if it can't be solved.
+    distances: Vec<u32>,
+    // States that can still be solved, no gap between solution lengths is longer than this.
+    solvable: usize,
+    // Length of the solutions being searched for.
+    length: usize,
+    last_found: usize,
+    // Depth first search over paths of 'length' moves, each state with the next of its moves to try
+    // and the moves taken to the deepest one.
+    stack: Vec<(u32, u32)>,
+    moves: Vec<Move>,
+    done: bool,
+}
+
+impl Solutions {
+    fn new(board: &Board, state: State) -> Self {
+        let order = TieBreak::default();
+        let win = WinCondition::new(board);
+        let space = StateSpace::new(board);
+        // Index in 'states' of every slot of 'space' reached, UNREACHABLE for the others.
+        let mut index = vec![UNREACHABLE; space.slots()];
+        let slot = |state: &State| space.index(state).expect("Piece off the board.");
+        index[slot(&state)] = 0;
+        let mut states = vec![state];
+        let (mut starts, mut edges) = (vec![0], Vec::new());
+        // Solutions end on the first solved state, so the search doesn't go past one.
+        let mut solved = Vec::new();
+        let mut next = 0;
+        while next < states.len() {
+            let state = states[next];
+            if win.is_met(board, &state) {
+                solved.push(next);
+            } else {
+                for (m, reached) in neighbourhood(board, &state, &order) {
+                    let known = &mut index[slot(&reached)];
+                    if *known == UNREACHABLE {
+                        *known = states.len() as u32;
+                        states.push(reached);
+                    }
+                    edges.push((m, *known));
+                }
+            }
+            starts.push(edges.len() as u32);
+            next += 1;
+        }
+
+        let mut predecessors = vec![Vec::new(); states.len()];
+        for from in 0..states.len() {
+            for (_, to) in &edges[starts[from] as usize..starts[from + 1] as usize] {
+                predecessors[*to as usize].push(from as u32);
+            }
+        }
+        let mut distances = vec![UNREACHABLE; states.len()];
+        let mut queue = VecDeque::new();
+        for index in solved {
+            distances[index] = 0;
+            queue.push_back(index);
+        }
+        while let Some(to) = queue.pop_front() {
+            for &from in &predecessors[to] {
+                if distances[from as usize] == UNREACHABLE {
+                    distances[from as usize] = distances[to] + 1;
+                    queue.push_back(from as usize);
+                }
+            }
+        }
+
+        Self {
+            starts,
+            edges,
+            solvable: distances.iter().filter(|d| **d != UNREACHABLE).count(),
+            // Raised to the shortest length before the first search.
+            length: (distances[0] as usize).saturating_sub(1),
+            last_found: distances[0] as usize,
+            done: distances[0] == UNREACHABLE,
+            distances,
+            stack: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<Move>;
+
+    fn next(&mut self) -> Option<Vec<Move>> {
+        if self.done {
+            return None;
+        }
+        if self.distances[0] == 0 {
+            // Already solved, the only solution is doing nothing.
+            self.done = true;
+            return Some(Vec::new());
+        }
+        loop {
+            let Some((state, edge)) = self.stack.last_mut() else {
+                // Every solution of 'length' moves has been given, start over one move longer.
+                self.length += 1;
+                // A longer solution would go around a cycle a shorter one could have, and no
+                // cycle is longer than 'solvable' moves.
+                if self.length > self.last_found + self.solvable {
+                    self.done = true;
+                    return None;
+                }
+                self.stack.push((0, self.starts[0]));
+                continue;
+            };
+            let state = *state as usize;
+            if *edge == self.starts[state + 1] {
+                self.stack.pop();
+                self.moves.pop();
+                continue;
+            }
+            let (m, next) = self.edges[*edge as usize];
+            *edge += 1;
+            let left = self.length - self.stack.len();
+            let distance = self.distances[next as usize];
+            if distance as usize > left || (distance == 0 && left > 0) {
+                continue;
+            }
+            if left == 0 {
+                self.last_found = self.length;
+                let mut solution = self.moves.clone();
+                solution.push(m);
+                return Some(solution);
+            }
+            self.stack.push((next, self.starts[next as usize]));
+            self.moves.push(m);
+        }
+    }
+}
+
+// Every solution of the puzzle, shortest first and in default 'TieBreak' order among those as
+// long. Solutions end as soon as the puzzle is solved and may go around in circles, so puzzles
+// with any solution usually have infinitely many. They are found lazily, taking a few first ones
+// only costs a search of the states reachable from 'state'.
+pub fn solutions(board: &Board, state: State) -> Solutions {
+    Solutions::new(board, state)
+}
+
+// The 'k' shortest solutions as 'solutions' gives them, which may run over several lengths once
+// the optimal ones are used up. Fewer if the puzzle has no more.
+pub fn solve_k_best(board: &Board, state: State, k: usize) -> Vec<Vec<Move>> {
+    solutions(board, state).take(k).collect()
+}
+
+// Number of solutions taking at most 'max_length' moves, every distinct sequence of moves counted
+// including ones going around in circles. Counts paths a layer at a time rather than listing them,
+// so it takes a breadth first search 'max_length' deep however many there are. Saturates at
+// 'u64::MAX'.
+pub fn count_solutions(board: &Board, state: State, max_length: usize) -> u64 {
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    let mut layer = HashMap::from([(state, 1_u64)]);
+    let mut count = 0_u64;
+    for depth in 0..=max_length {
+        let mut next: HashMap<State, u64> = HashMap::new();
+        for (state, paths) in layer {
+            // Solutions end on the first solved state.
+            if win.is_met(board, &state) {
+                count = count.saturating_add(paths);
+                continue;
+            }
+            if depth == max_length {
+                continue;
+            }
+            for (_, reached) in neighbourhood(board, &state, &order) {
+                let entry = next.entry(reached).or_default();
+                *entry = entry.saturating_add(paths);
+            }
+        }
+        layer = next;
+    }
+    count
+}
+
+// Optimal solutions, counted up to 2 and 0 if there are none. Breadth first, counting the
+// shortest paths to each state of a layer, which is about as fast as finding the optimal length.
+// Stops as soon as a second solution turns up.
+pub(crate) fn count_optimal(board: &Board, state: State) -> usize {
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    if win.is_met(board, &state) {
+        return 1;
+    }
+    let mut seen = HashSet::from([state]);
+    let mut layer = HashMap::from([(state, 1)]);
+    while !layer.is_empty() {
+        let mut next: HashMap<State, usize> = HashMap::new();
+        let mut solved = 0;
+        for (state, paths) in &layer {
+            for (_, reached) in neighbourhood(board, state, &order) {
+                if seen.contains(&reached) {
+                    continue;
+                }
+                let count = next.entry(reached).or_default();
+                *count = (*count + paths).min(2);
+                if win.is_met(board, &reached) {
+                    solved += paths;
+                    if solved >= 2 {
+                        return 2;
+                    }
+                }
+            }
+        }
+        if solved > 0 {
+            return solved;
+        }
+        seen.extend(next.keys());
+        layer = next;
+    }
+    0
+}
+
+// Whether the puzzle has exactly one optimal solution, so it has a single intended answer.
+pub fn has_unique_optimal(board: &Board, state: State) -> bool {
+    count_optimal(board, state) == 1
+}
+
+// Shortest solution that never makes one of the 'forbidden' moves from the state paired with it,
+// None if there is none.
+pub fn solve_avoiding(
+    board: &Board,
+    state: State,
+    forbidden: &HashSet<(State, Move)>,
+) -> Option<Vec<Move>> {
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    // The move each state was first reached with and the state it was made from.
+    let mut parents: HashMap<State, Option<(State, Move)>> = HashMap::from([(state, None)]);
+    let mut queue = VecDeque::from([state]);
+    while let Some(current) = queue.pop_front() {
+        if win.is_met(board, &current) {
+            let mut moves = Vec::new();
+            let mut at = current;
+            while let Some((from, m)) = parents[&at] {
+                moves.push(m);
+                at = from;
+            }
+            moves.reverse();
+            return Some(moves);
+        }
+        for (m, reached) in neighbourhood(board, &current, &order) {
+            if forbidden.contains(&(current, m)) || parents.contains_key(&reached) {
+                continue;
+            }
+            parents.insert(reached, Some((current, m)));
+            queue.push_back(reached);
+        }
+    }
+    None
+}
+
+// Shortest solution sharing no move with 'solution', a move being the same if it is made from
+// the same state, to show an alternate line next to it. It may be longer. None if there is none.
+pub fn disjoint_alternative(board: &Board, state: State, solution: &[Move]) -> Option<Vec<Move>> {
+    let mut forbidden = HashSet::new();
+    let mut current = state;
+    for (m, next) in playback(board, state, solution) {
+        forbidden.insert((current, m));
+        current = next;
+    }
+    solve_avoiding(board, state, &forbidden)
+}
@@ -1,10 +1,19 @@
-use std::collections::VecDeque;
-use std::rc::Rc;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::bitboard::{Bitboard, BitboardCache};
 
 use Direction::*;
 use PieceType::*;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Direction {
     Up,
     Down,
@@ -12,46 +21,780 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PieceType {
     HelperOne,
     HelperTwo,
     Main,
 }
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slant {
+    // '/', turns Right into Up and Down into Left.
+    Slash,
+    // '\', turns Right into Down and Up into Left.
+    Backslash,
+}
+
+impl Slant {
+    pub fn deflect(&self, dir: &Direction) -> Direction {
+        match (self, dir) {
+            (Slant::Slash, Up) => Right,
+            (Slant::Slash, Down) => Left,
+            (Slant::Slash, Left) => Down,
+            (Slant::Slash, Right) => Up,
+            (Slant::Backslash, Up) => Left,
+            (Slant::Backslash, Down) => Right,
+            (Slant::Backslash, Left) => Up,
+            (Slant::Backslash, Right) => Down,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum BoardPiece {
     Start,
-    Goal,
+    // Goals are visited by the main piece in order of their index, starting at 0.
+    Goal(u8),
+    // Cell the given helper has to stand on when the main piece finishes.
+    HelperGoal(PieceType),
+    // Diagonal that turns sliding pieces 90 degrees, except for the piece it is keyed to.
+    Mirror(Slant, Option<PieceType>),
+    // Arrow cell that can only be entered while moving in the given direction.
+    OneWay(Direction),
+    // Portal with its index in 'Board::portals', a piece entering it comes out of its twin.
+    Portal(u8),
     Blocker,
+    // Blocker the main piece can push, see 'push::solve_pushing'. Other searches treat it as a
+    // plain blocker.
+    Pushable,
+    // Hole in the board, not part of it and never entered.
+    Void,
+    // Cell that destroys the piece it is keyed to, or any piece if None. Moves that would take
+    // such a piece over or onto it can't be made, so it is never stopped on or passed through.
+    Lava(Option<PieceType>),
     Empty,
     BoardHelper,
     BoardMain,
 }
 
-type Position = u8;
+impl BoardPiece {
+    // Whether 'piece' can't enter the cell without being destroyed.
+    pub fn is_fatal_to(&self, piece: &PieceType) -> bool {
+        matches!(self, BoardPiece::Lava(key) if key.is_none_or(|key| key == *piece))
+    }
+}
+
+// How far a piece travels in a single move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Movement {
+    // Slide until something is in the way, as in Ricochet Robots.
+    #[default]
+    Slide,
+    // Move exactly one cell, as in classic sliding block puzzles.
+    Step,
+    // Slide until next to another piece, as in Lunar Lockout.
+    Sticky,
+}
+
+// Cell packed as x << 4 | y, so boards are at most 'MAX_SIDE' cells wide and high.
+pub type Position = u8;
+
+// Largest board side a 'Position' can hold.
+pub const MAX_SIDE: usize = 16;
+
 pub type Move = (PieceType, Direction);
 
-pub type State = (Position, Position, Position, u8);
-pub type Board = Vec<Vec<BoardPiece>>;
+// Positions of the three pieces and the number of goals the main piece has reached so far, packed
+// into a u32 with the main piece in the top byte, then helper one, helper two and the goals reached
+// in the low byte. Compared and hashed as a single number, states still order by main piece first.
+// 'StateLayout' packs states tighter, with as many bits as their board needs.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct State(u32);
 
-struct Node {
-    m: Option<Move>,
-    state: State,
-    prev: Option<Rc<Node>>,
+// Where 'piece' is in the bits of a state.
+const fn shift(piece: &PieceType) -> u32 {
+    match piece {
+        Main => 24,
+        HelperOne => 16,
+        HelperTwo => 8,
+    }
+}
+
+impl State {
+    pub fn new(main: Position, helper_one: Position, helper_two: Position) -> Self {
+        Self((main as u32) << 24 | (helper_one as u32) << 16 | (helper_two as u32) << 8)
+    }
+
+    // The state packed as described on 'State', for storing states compactly.
+    pub fn to_bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn main_pos(&self) -> Position {
+        self.pos(&Main)
+    }
+
+    pub fn helper_one_pos(&self) -> Position {
+        self.pos(&HelperOne)
+    }
+
+    pub fn helper_two_pos(&self) -> Position {
+        self.pos(&HelperTwo)
+    }
+
+    // Position of 'piece'.
+    pub fn pos(&self, piece: &PieceType) -> Position {
+        (self.0 >> shift(piece)) as Position
+    }
+
+    // Number of goals the main piece has stopped on in order.
+    pub fn goals_reached(&self) -> u8 {
+        self.0 as u8
+    }
+
+    pub fn with_pos(&self, piece: &PieceType, pos: Position) -> Self {
+        let shift = shift(piece);
+        Self(self.0 & !(0xFF << shift) | (pos as u32) << shift)
+    }
+
+    pub fn with_goals_reached(&self, goals: u8) -> Self {
+        Self(self.0 & !0xFF | goals as u32)
+    }
+
+    // Whether any piece stands on 'pos'.
+    pub fn is_occupied(&self, pos: Position) -> bool {
+        pos == self.main_pos() || pos == self.helper_one_pos() || pos == self.helper_two_pos()
+    }
+}
+
+// Bits of a packed state for one board: a slot for each piece, main piece first, holding its y and
+// then its x in as few bits as the board's height and width need, and the goals reached in the
+// lowest bits. The number of slots is free, so states with any number of helpers pack the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateLayout {
+    x_bits: u32,
+    y_bits: u32,
+    goal_bits: u32,
+    pieces: u32,
+}
+
+// Bits needed to tell 'values' values apart.
+const fn bits_for(values: usize) -> u32 {
+    usize::BITS - values.saturating_sub(1).leading_zeros()
+}
+
+impl StateLayout {
+    // Layout for states of 'board' with 'pieces' pieces, None if they take more than 128 bits.
+    pub fn new(board: &Board, pieces: usize) -> Option<Self> {
+        let layout = Self {
+            x_bits: bits_for(board.width()),
+            y_bits: bits_for(board.height()),
+            goal_bits: bits_for(goal_count(board) as usize + 1),
+            pieces: u32::try_from(pieces).ok()?,
+        };
+        let bits = layout
+            .pieces
+            .checked_mul(layout.x_bits + layout.y_bits)?
+            .checked_add(layout.goal_bits)?;
+        (bits <= u128::BITS).then_some(layout)
+    }
+
+    // Bits a packed state takes, the rest of the u128 is always zero. States of up to 64 bits fit
+    // in a u64.
+    pub fn bits(&self) -> u32 {
+        self.pieces * (self.x_bits + self.y_bits) + self.goal_bits
+    }
+
+    // Packs the positions of the pieces in slot order and the goals reached. 'positions' must
+    // give one position for every slot.
+    pub fn pack(&self, positions: impl IntoIterator<Item = Position>, goals: u8) -> u128 {
+        positions.into_iter().fold(0, |bits, pos| {
+            let bits = bits << self.x_bits | pos_to_x(&pos) as u128;
+            bits << self.y_bits | pos_to_y(&pos) as u128
+        }) << self.goal_bits
+            | goals as u128
+    }
+
+    // Fills 'positions' with the pieces packed in 'bits' and gives the goals reached.
+    pub fn unpack(&self, mut bits: u128, positions: &mut [Position]) -> u8 {
+        debug_assert_eq!(positions.len(), self.pieces as usize);
+        let mask = |bits: u32| (1_u128 << bits) - 1;
+        let goals = (bits & mask(self.goal_bits)) as u8;
+        bits >>= self.goal_bits;
+        for pos in positions.iter_mut().rev() {
+            let y = (bits & mask(self.y_bits)) as usize;
+            bits >>= self.y_bits;
+            *pos = xy_to_pos((bits & mask(self.x_bits)) as usize, y);
+            bits >>= self.x_bits;
+        }
+        goals
+    }
+
+    // Packs a three piece state, the layout must have three slots.
+    pub fn pack_state(&self, state: &State) -> u128 {
+        let positions = [
+            state.main_pos(),
+            state.helper_one_pos(),
+            state.helper_two_pos(),
+        ];
+        self.pack(positions, state.goals_reached())
+    }
+
+    pub fn unpack_state(&self, bits: u128) -> State {
+        let mut positions = [0; 3];
+        let goals = self.unpack(bits, &mut positions);
+        State::new(positions[0], positions[1], positions[2]).with_goals_reached(goals)
+    }
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("State")
+            .field("main", &self.main_pos())
+            .field("helper_one", &self.helper_one_pos())
+            .field("helper_two", &self.helper_two_pos())
+            .field("goals", &self.goals_reached())
+            .finish()
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Board {
+    cells: Vec<Vec<BoardPiece>>,
+    // Thin walls on the edges of each cell as a combination of 'wall_bit's. Every wall is stored
+    // on both cells it separates.
+    walls: Vec<Vec<u8>>,
+    // Pieces sliding off an edge come back in from the opposite one.
+    toroidal: bool,
+    // Linked portal cells, indexed by the id stored in 'BoardPiece::Portal'.
+    portals: Vec<(Position, Position)>,
+    movement: Movement,
+    bitboard: BitboardCache,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![BoardPiece::Empty; width]; height],
+            walls: vec![vec![0; width]; height],
+            toroidal: false,
+            portals: Vec::new(),
+            movement: Movement::Slide,
+            bitboard: BitboardCache::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> &BoardPiece {
+        &self.cells[y][x]
+    }
+
+    // Piece at the cell 'pos'.
+    pub fn at(&self, pos: &Position) -> &BoardPiece {
+        &self.cells[pos_to_y(pos)][pos_to_x(pos)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, piece: BoardPiece) {
+        self.cells[y][x] = piece;
+        self.bitboard.reset();
+    }
+
+    // Every cell with its coordinates, in reading order.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &BoardPiece)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| line.iter().enumerate().map(move |(x, piece)| (x, y, piece)))
+    }
+
+    // Walls around cell x,y as a combination of 'wall_bit's.
+    pub fn walls(&self, x: usize, y: usize) -> u8 {
+        self.walls[y][x]
+    }
+
+    pub fn is_toroidal(&self) -> bool {
+        self.toroidal
+    }
+
+    pub fn set_toroidal(&mut self, toroidal: bool) {
+        self.toroidal = toroidal;
+        self.bitboard.reset();
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    pub fn set_movement(&mut self, movement: Movement) {
+        self.movement = movement;
+        self.bitboard.reset();
+    }
+
+    // Linked portal pairs, indexed by the id stored in 'BoardPiece::Portal'.
+    pub fn portals(&self) -> &[(Position, Position)] {
+        &self.portals
+    }
+
+    // Adds a wall on the 'side' edge of cell x,y.
+    pub fn add_wall(&mut self, x: usize, y: usize, side: Direction) {
+        self.walls[y][x] |= wall_bit(&side);
+        let opposite = match side {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+        };
+        if let Some((x, y)) = self.step(x, y, &side) {
+            self.walls[y][x] |= wall_bit(&opposite);
+        }
+        self.bitboard.reset();
+    }
+
+    // Links the cells at 'a' and 'b' as a pair of portals.
+    pub fn add_portal(&mut self, a: Position, b: Position) {
+        let id = self.portals.len() as u8;
+        for pos in [a, b] {
+            self.cells[pos_to_y(&pos)][pos_to_x(&pos)] = BoardPiece::Portal(id);
+        }
+        self.portals.push((a, b));
+        self.bitboard.reset();
+    }
+
+    // The other end of the portal at 'pos', if there is one.
+    pub fn twin(&self, pos: &Position) -> Option<Position> {
+        match self.cells[pos_to_y(pos)][pos_to_x(pos)] {
+            BoardPiece::Portal(id) => {
+                let (a, b) = self.portals[id as usize];
+                Some(if a == *pos { b } else { a })
+            }
+            _ => None,
+        }
+    }
+
+    // Stopping cells of every row and column, None unless every slide runs in a straight line.
+    pub(crate) fn bitboard(&self) -> Option<&Bitboard> {
+        self.bitboard.get(self)
+    }
+
+    pub fn has_wall(&self, x: usize, y: usize, side: &Direction) -> bool {
+        self.walls[y][x] & wall_bit(side) != 0
+    }
+
+    // Cell next to x,y in direction 'dir', wrapping around the edges on toroidal boards.
+    pub(crate) fn step(&self, x: usize, y: usize, dir: &Direction) -> Option<(usize, usize)> {
+        let (width, height) = (self.width(), self.height());
+        match dir {
+            Up if y > 0 => Some((x, y - 1)),
+            Down if y + 1 < height => Some((x, y + 1)),
+            Left if x > 0 => Some((x - 1, y)),
+            Right if x + 1 < width => Some((x + 1, y)),
+            Up if self.toroidal => Some((x, height - 1)),
+            Down if self.toroidal => Some((x, 0)),
+            Left if self.toroidal => Some((width - 1, y)),
+            Right if self.toroidal => Some((0, y)),
+            _ => None,
+        }
+    }
+}
+
+pub fn wall_bit(side: &Direction) -> u8 {
+    match side {
+        Up => 0b0001,
+        Down => 0b0010,
+        Left => 0b0100,
+        Right => 0b1000,
+    }
+}
+
+// Snapshot of a running search, reported each time it reaches a new depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchProgress {
+    pub depth: usize,
+    pub frontier: usize,
+    pub expanded: usize,
+}
+
+// Step of a breadth first search, reported to 'SolveOptions::on_trace' so the search can be
+// replayed elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    // 'state', 'depth' moves from the start, is about to have its moves tried.
+    Expand { state: State, depth: usize },
+    // Making 'm' in 'from' reached 'state' for the first time.
+    Generate { from: State, m: Move, state: State },
+    // Making 'm' in 'from' reached 'state' again, which is left out.
+    PruneDuplicate { from: State, m: Move, state: State },
+    // 'state', 'depth' moves from the start, is solved and ends the search.
+    Goal { state: State, depth: usize },
+}
+
+// One JSON object per event, named by "event" and with states packed as by 'State::to_bits'.
+#[cfg(feature = "json")]
+impl TraceEvent {
+    pub fn to_json(&self) -> String {
+        match self {
+            TraceEvent::Expand { state, depth } => json::object! {
+                event: "expand",
+                state: state.to_bits(),
+                depth: *depth,
+            },
+            TraceEvent::Generate { from, m, state } => json::object! {
+                event: "generate",
+                from: from.to_bits(),
+                move: crate::tools::format_move(m),
+                state: state.to_bits(),
+            },
+            TraceEvent::PruneDuplicate { from, m, state } => json::object! {
+                event: "prune-duplicate",
+                from: from.to_bits(),
+                move: crate::tools::format_move(m),
+                state: state.to_bits(),
+            },
+            TraceEvent::Goal { state, depth } => json::object! {
+                event: "goal",
+                state: state.to_bits(),
+                depth: *depth,
+            },
+        }
+        .dump()
+    }
+}
+
+// Shared flag that stops every search it was handed to once cancelled, from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Order in which moves are tried. Of all optimal solutions the search returns the one that comes
+// first when comparing move by move, pieces ranked by 'pieces' and then directions by 'directions'.
+// The default prefers Main, then HelperOne, then HelperTwo, and Left, Right, Up, Down. Every piece
+// and direction has to be listed exactly once, anything left out is never moved.
+#[derive(Debug, Clone, Copy)]
+pub struct TieBreak {
+    pub pieces: [PieceType; 3],
+    pub directions: [Direction; 4],
+}
+
+impl Default for TieBreak {
+    fn default() -> Self {
+        Self {
+            pieces: [Main, HelperOne, HelperTwo],
+            directions: [Left, Right, Up, Down],
+        }
+    }
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&SearchProgress) + Send + Sync>;
+
+pub type TraceCallback = Arc<dyn Fn(&TraceEvent) + Send + Sync>;
+
+// Limits applied to a search, the default searches until the state space is exhausted.
+#[derive(Clone, Default)]
+pub struct SolveOptions {
+    pub timeout: Option<Duration>,
+    pub cancel: Option<CancellationToken>,
+    // Maximum number of states expanded before giving up.
+    pub max_nodes: Option<usize>,
+    // Maximum number of states waiting in the queue before giving up.
+    pub max_queue: Option<usize>,
+    // Most moves a solution may take, such as a known optimal length. Longer paths are cut off
+    // and the search ends with 'BudgetExceeded' if no solution fits.
+    pub upper_bound: Option<usize>,
+    // Most moves worth looking for, such as a difficulty budget. Like 'upper_bound', but the
+    // search ends with 'SolveOutcome::NotWithin' if no solution fits.
+    pub max_depth: Option<usize>,
+    // Called whenever the search reaches a new depth and every 'progress_interval' expansions.
+    pub on_progress: Option<ProgressCallback>,
+    pub progress_interval: Option<usize>,
+    // Called on every step of the search, only by the breadth first 'solve_puzzle_with'.
+    pub on_trace: Option<TraceCallback>,
+    pub tie_break: TieBreak,
+    // Size of the thread pool searches and batches that run in parallel use, all cores if None.
+    pub threads: Option<usize>,
+}
+
+impl fmt::Debug for SolveOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolveOptions")
+            .field("timeout", &self.timeout)
+            .field("cancel", &self.cancel)
+            .field("max_nodes", &self.max_nodes)
+            .field("max_queue", &self.max_queue)
+            .field("upper_bound", &self.upper_bound)
+            .field("max_depth", &self.max_depth)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("progress_interval", &self.progress_interval)
+            .field("on_trace", &self.on_trace.is_some())
+            .field("tie_break", &self.tie_break)
+            .field("threads", &self.threads)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveError {
+    Timeout,
+    // Holds how far the search got before it was stopped.
+    Cancelled(SearchProgress),
+    // No solution is shorter than 'lower_bound' moves.
+    BudgetExceeded { lower_bound: usize },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Timeout => write!(f, "search timed out"),
+            SolveError::Cancelled(progress) => write!(
+                f,
+                "search cancelled at depth {} after {} expansions",
+                progress.depth, progress.expanded
+            ),
+            SolveError::BudgetExceeded { lower_bound } => write!(
+                f,
+                "search budget exceeded, solution needs at least {lower_bound} moves"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for SolveError {}
+
+// Counters collected over a single search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes_expanded: usize,
+    pub nodes_generated: usize,
+    pub max_queue_len: usize,
+    pub duplicates_pruned: usize,
+    pub duration: Duration,
+    // How the states reached were kept track of, None for searches keeping a map of their own.
+    pub visited: Option<VisitedSet>,
+    // Size of each layer of a breadth first search, indexed by depth. Only the layers that were
+    // fully generated are listed, and searches that don't go a layer at a time leave it empty.
+    pub depths: Vec<DepthStats>,
+}
+
+// A layer of a breadth first search, see 'SearchStats::depths'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthStats {
+    // States first reached at this depth.
+    pub frontier: usize,
+    // States reached at this depth or less.
+    pub visited: usize,
+}
+
+// Set of reached states a breadth first search picked for the board, see 'SearchStats::visited'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitedSet {
+    // A bit for every placement of the pieces on the board with every number of goals reached.
+    Dense,
+    // Only the states reached, hashed. Used when the dense set would take too much memory.
+    Hashed,
+}
+
+// Limits are only checked every this many expansions to keep clock reads out of the hot loop.
+const LIMIT_CHECK_INTERVAL: usize = 1024;
+
+// Bytes the dense visited set may take, larger boards and puzzles with more goals hash the states
+// they reach instead.
+const DENSE_VISITED_LIMIT: usize = 64 << 20;
+
+// States a search has reached.
+pub(crate) enum Visited {
+    // A bit for every state, indexed by the cells of the pieces and the goals reached.
+    Dense {
+        seen: Vec<u64>,
+        height: usize,
+        area: usize,
+        progress: usize,
+    },
+    Hashed(ZobristSet),
+}
+
+impl Visited {
+    // Picks the dense set if it fits in DENSE_VISITED_LIMIT, which takes an eighth of a byte for
+    // every placement of the pieces on the board for every number of goals reached.
+    pub(crate) fn new(board: &Board, goals: u8) -> Self {
+        let (height, area) = (board.height(), board.width() * board.height());
+        let progress = goals as usize + 1;
+        let states = area
+            .checked_pow(3)
+            .and_then(|placements| placements.checked_mul(progress))
+            .filter(|states| states.div_ceil(8) <= DENSE_VISITED_LIMIT);
+        match states {
+            Some(states) => Visited::Dense {
+                seen: vec![0; states.div_ceil(64)],
+                height,
+                area,
+                progress,
+            },
+            None => Visited::Hashed(ZobristSet::new()),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> VisitedSet {
+        match self {
+            Visited::Dense { .. } => VisitedSet::Dense,
+            Visited::Hashed(_) => VisitedSet::Hashed,
+        }
+    }
+
+    // Marks 'state' as visited, gives false if it already was.
+    pub(crate) fn insert(&mut self, state: &State) -> bool {
+        match self {
+            Visited::Dense {
+                seen,
+                height,
+                area,
+                progress,
+            } => {
+                let index = Self::dense_index(state, *height, *area, *progress);
+                let (word, bit) = (index / 64, 1 << (index % 64));
+                let new = seen[word] & bit == 0;
+                seen[word] |= bit;
+                new
+            }
+            Visited::Hashed(set) => set.insert(state),
+        }
+    }
+
+    // Bit of 'state' in the dense set.
+    pub(crate) fn dense_index(state: &State, height: usize, area: usize, progress: usize) -> usize {
+        let cell = |pos: Position| pos_to_x(&pos) * height + pos_to_y(&pos);
+        let pieces = (cell(state.main_pos()) * area + cell(state.helper_one_pos())) * area
+            + cell(state.helper_two_pos());
+        pieces * progress + state.goals_reached() as usize
+    }
+}
+
+// Random keys for each piece on each position, main piece first, and for each number of goals
+// reached. A state hashes to the keys of its pieces and goals xored together.
+const ZOBRIST_KEYS: [[u64; 256]; 4] = zobrist_keys();
+
+// Fixed keys from splitmix64, so hashes are the same on every run.
+const fn zobrist_keys() -> [[u64; 256]; 4] {
+    let mut keys = [[0; 256]; 4];
+    let mut seed: u64 = 0;
+    let mut i = 0;
+    while i < 4 * 256 {
+        seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i / 256][i % 256] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+// Set of states by Zobrist hash with open addressing, taking memory for the states reached only.
+pub(crate) struct ZobristSet {
+    // Power of two long, probed linearly from the hash.
+    slots: Vec<Option<State>>,
+    len: usize,
+}
+
+impl ZobristSet {
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: vec![None; 1 << 10],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn hash(state: &State) -> u64 {
+        ZOBRIST_KEYS[0][state.main_pos() as usize]
+            ^ ZOBRIST_KEYS[1][state.helper_one_pos() as usize]
+            ^ ZOBRIST_KEYS[2][state.helper_two_pos() as usize]
+            ^ ZOBRIST_KEYS[3][state.goals_reached() as usize]
+    }
+
+    // Adds 'state', gives false if it was already in the set.
+    pub(crate) fn insert(&mut self, state: &State) -> bool {
+        // Kept at most three quarters full so probes stay short.
+        if (self.len + 1) * 4 > self.slots.len() * 3 {
+            let grown = vec![None; self.slots.len() * 2];
+            let slots = core::mem::replace(&mut self.slots, grown);
+            self.len = 0;
+            for state in slots.into_iter().flatten() {
+                self.insert(&state);
+            }
+        }
+        let mask = self.slots.len() - 1;
+        let mut slot = Self::hash(state) as usize & mask;
+        loop {
+            match &self.slots[slot] {
+                Some(seen) if seen == state => return false,
+                Some(_) => slot = (slot + 1) & mask,
+                None => {
+                    self.slots[slot] = Some(*state);
+                    self.len += 1;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+// Every state a breadth first search reached in the order it was reached, with the node it was
+// reached from and the move that got there. Kept in flat arrays rather than a vector of nodes,
+// ten bytes a state instead of sixteen.
+struct Tree {
+    states: Vec<State>,
+    parents: Vec<u32>,
+    moves: Vec<Option<Move>>,
 }
 
-impl Node {
-    fn new(m: Option<Move>, state: State, prev: Option<Rc<Self>>) -> Self {
-        Self { m, state, prev }
+impl Tree {
+    fn new(start: State) -> Self {
+        Self {
+            states: vec![start],
+            parents: vec![0],
+            moves: vec![None],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.states.len()
     }
 
-    fn moves(&self) -> Vec<Move> {
+    fn push(&mut self, state: State, parent: usize, m: Move) {
+        self.states.push(state);
+        self.parents.push(parent as u32);
+        self.moves.push(Some(m));
+    }
+
+    // Moves from the start to node 'index'.
+    fn path(&self, mut index: usize) -> Vec<Move> {
         let mut moves = Vec::new();
-        let mut current = self;
-        while let Some(prev) = &current.prev {
-            moves.push(current.m.unwrap());
-            current = prev.as_ref();
+        while let Some(m) = self.moves[index] {
+            moves.push(m);
+            index = self.parents[index] as usize;
         }
         moves.reverse();
         moves
@@ -70,94 +813,301 @@ pub fn xy_to_pos(x: usize, y: usize) -> Position {
     ((x << 4) + y) as u8
 }
 
-// Steps from 'pos' in direction 'dir' and return Some(pos) if valid.
-fn try_move(pos: &Position, dir: &Direction, board: &Board, state: &State) -> Option<Position> {
-    let mut x = pos_to_x(pos);
-    let mut y = pos_to_y(pos);
+// Steps from 'pos' in direction 'dir' and return Some(pos) if valid, 'blocks' holding pushable
+// blockers.
+#[cfg(feature = "std")]
+pub(crate) fn try_move(
+    pos: &Position,
+    dir: &Direction,
+    board: &Board,
+    state: &State,
+    blocks: &[Position],
+) -> Option<Position> {
+    advance(board, state, blocks, &Main, pos, *dir)
+        .map(|(entered, _, _)| entered)
+        .filter(|entered| !board.at(entered).is_fatal_to(&Main))
+}
 
-    let out_of_bounds = (y == 0 && *dir == Up)
-        || (x == 0 && *dir == Left)
-        || (y + 1 >= board.len() && *dir == Down)
-        || (x + 1 >= board[0].len() && *dir == Right);
+fn occupied(state: &State, blocks: &[Position], pos: Position) -> bool {
+    state.is_occupied(pos) || blocks.contains(&pos)
+}
 
-    if out_of_bounds {
+// Steps 'piece' from 'pos' in direction 'dir' if valid, giving the cell entered along with where
+// it continues from and in which direction. Only mirrors and portals change those, a portal
+// sending the piece on from its twin unless that is occupied.
+pub(crate) fn advance(
+    board: &Board,
+    state: &State,
+    blocks: &[Position],
+    piece: &PieceType,
+    pos: &Position,
+    dir: Direction,
+) -> Option<(Position, Position, Direction)> {
+    let (x, y) = (pos_to_x(pos), pos_to_y(pos));
+
+    if board.has_wall(x, y, &dir) {
         return None;
     }
 
-    match dir {
-        Up => y = y - 1,
-        Down => y = y + 1,
-        Left => x = x - 1,
-        Right => x = x + 1,
-    };
-
-    match board[y as usize][x as usize] {
-        BoardPiece::Blocker => return None,
-        _ => {}
-    };
-
+    let (x, y) = board.step(x, y, &dir)?;
     let pos = xy_to_pos(x, y);
 
     // Check collison with other pieces
-    if pos == state.0 || pos == state.1 || pos == state.2 {
+    if occupied(state, blocks, pos) {
         return None;
     }
 
-    Some(pos)
+    match board.cells[y][x] {
+        BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void => None,
+        BoardPiece::OneWay(allowed) if allowed != dir => None,
+        BoardPiece::Mirror(slant, key) if key != Some(*piece) => {
+            Some((pos, pos, slant.deflect(&dir)))
+        }
+        BoardPiece::Portal(_) => {
+            let twin = board
+                .twin(&pos)
+                .filter(|twin| !occupied(state, blocks, *twin))
+                .unwrap_or(pos);
+            Some((pos, twin, dir))
+        }
+        _ => Some((pos, pos, dir)),
+    }
+}
+
+// Whether 'pos' is orthogonally next to a piece other than 'piece'.
+fn touches_piece(board: &Board, state: &State, piece: &PieceType, pos: &Position) -> bool {
+    let own = state.pos(piece);
+    let (x, y) = (pos_to_x(pos), pos_to_y(pos));
+    [Up, Down, Left, Right]
+        .iter()
+        .filter_map(|dir| board.step(x, y, dir))
+        .map(|(x, y)| xy_to_pos(x, y))
+        .any(|next| next != own && state.is_occupied(next))
+}
+
+// Slides 'piece' from 'pos', calling 'visit' for every cell entered, and returns where it stops
+// and the direction it was last moving in if the move is legal. A piece entering a portal continues
+// from its twin unless that is occupied. Portals can send a piece around in circles forever, such a
+// move is not legal, and so is one entering lava fatal to the piece.
+pub(crate) fn slide(
+    board: &Board,
+    state: &State,
+    blocks: &[Position],
+    piece: &PieceType,
+    pos: &Position,
+    dir: &Direction,
+    mut visit: impl FnMut(Position),
+) -> Option<(Position, Direction)> {
+    let stops = |pos: &Position| match board.movement {
+        Movement::Slide => false,
+        Movement::Step => true,
+        Movement::Sticky => touches_piece(board, state, piece, pos),
+    };
+    let mut enter = |(entered, pos, dir): (Position, Position, Direction)| {
+        if board.at(&entered).is_fatal_to(piece) {
+            return None;
+        }
+        visit(entered);
+        if pos != entered {
+            visit(pos);
+        }
+        Some((pos, dir))
+    };
+    let (mut new_pos, mut dir) = enter(advance(board, state, blocks, piece, pos, *dir)?)?;
+    // Every cell can be left in at most four directions before the slide repeats itself.
+    let mut steps_left = board.width() * board.height() * 4;
+
+    while !stops(&new_pos) {
+        let Some(next) = advance(board, state, blocks, piece, &new_pos, dir) else {
+            break;
+        };
+        (new_pos, dir) = enter(next)?;
+        steps_left = steps_left.checked_sub(1)?;
+    }
+
+    Some((new_pos, dir))
 }
 
 // Returns next position in direction 'dir' if move is legal.
 fn next_position(
     board: &Board,
     state: &State,
+    piece: &PieceType,
     pos: &Position,
     dir: &Direction,
 ) -> Option<Position> {
-    let mut new_pos = try_move(pos, dir, board, state)?;
-
-    while let Some(pos) = try_move(&new_pos, &dir, board, state) {
-        new_pos = pos;
+    if let Some(bitboard) = board.bitboard() {
+        return bitboard.slide(
+            pos,
+            dir,
+            [
+                state.main_pos(),
+                state.helper_one_pos(),
+                state.helper_two_pos(),
+            ],
+        );
     }
+    slide(board, state, &[], piece, pos, dir, |_| {}).map(|(pos, _)| pos)
+}
 
-    Some(new_pos)
+// Every cell 'piece' passes over when moved in direction 'dir', including where it started and
+// where it stops. Empty if the move is illegal.
+pub fn slide_path(
+    board: &Board,
+    state: &State,
+    piece: &PieceType,
+    dir: &Direction,
+) -> Vec<Position> {
+    let start = state.pos(piece);
+    let mut path = vec![start];
+    if slide(board, state, &[], piece, &start, dir, |pos| path.push(pos)).is_none() {
+        path.clear();
+    }
+    path
 }
 
 // Gives new state with 'piece' moved in direction 'dir'.
 fn move_piece(board: &Board, state: &State, piece: &PieceType, dir: &Direction) -> Option<State> {
-    let start_pos = match piece {
-        Main => state.0,
-        HelperOne => state.1,
-        HelperTwo => state.2,
-    };
+    let start_pos = state.pos(piece);
+
+    let pos = next_position(board, state, piece, &start_pos, dir)?;
 
-    let pos = next_position(board, state, &start_pos, dir)?;
+    Some(place(board, state, piece, pos))
+}
 
-    Some(match piece {
-        Main => {
-            let goal_found =
-                if state.3 == 1 || board[pos_to_y(&pos)][pos_to_x(&pos)] == BoardPiece::Goal {
-                    1
-                } else {
-                    0
-                };
-            (pos, state.1, state.2, goal_found)
+// 'state' with 'piece' stopped at 'pos', counting the goal if the main piece reached the next one.
+pub(crate) fn place(board: &Board, state: &State, piece: &PieceType, pos: Position) -> State {
+    let state = state.with_pos(piece, pos);
+    match (piece, board.at(&pos)) {
+        (Main, BoardPiece::Goal(index)) if *index == state.goals_reached() => {
+            state.with_goals_reached(state.goals_reached() + 1)
         }
-        HelperOne => (state.0, pos, state.2, state.3),
-        HelperTwo => (state.0, state.1, pos, state.3),
-    })
+        _ => state,
+    }
+}
+
+// Applies move 'm' to 'state', gives None if the move is illegal.
+pub fn apply_move(board: &Board, state: &State, m: &Move) -> Option<State> {
+    move_piece(board, state, &m.0, &m.1)
+}
+
+// Whether a piece can never stand on or pass over a cell of this kind.
+pub(crate) fn is_blocked(cell: &BoardPiece) -> bool {
+    matches!(
+        cell,
+        BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+    )
+}
+
+// Cells a piece now on 'target' could have left moving in direction 'dir', in board order. On
+// 'straight' boards, without mirrors and portals, it came from the line behind 'target' up to the
+// first cell it could not have passed. Otherwise it could have come from any free cell.
+fn sources(
+    board: &Board,
+    state: &State,
+    straight: bool,
+    target: Position,
+    dir: &Direction,
+) -> Vec<Position> {
+    if !straight {
+        return board
+            .cells()
+            .filter(|(_, _, cell)| !is_blocked(cell))
+            .map(|(x, y, _)| xy_to_pos(x, y))
+            .filter(|pos| !state.is_occupied(*pos))
+            .collect();
+    }
+    let back = match dir {
+        Up => Down,
+        Down => Up,
+        Left => Right,
+        Right => Left,
+    };
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (pos_to_x(&target), pos_to_y(&target));
+    // Wrapping around a toroidal board ends back on 'target'.
+    while let Some((next_x, next_y)) = board
+        .step(x, y, &back)
+        .filter(|_| !board.has_wall(x, y, &back))
+    {
+        (x, y) = (next_x, next_y);
+        let pos = xy_to_pos(x, y);
+        if is_blocked(board.get(x, y)) || state.is_occupied(pos) {
+            break;
+        }
+        cells.push(pos);
+    }
+    cells.sort_unstable_by_key(|pos| (pos_to_y(pos), pos_to_x(pos)));
+    cells
 }
 
-// Gives a vector of all neighbouring states together with corresponding move.
-fn neighbourhood(board: &Board, state: &State) -> heapless::Vec<(Move, State), 12> {
+// Moves that end in 'state', with the state each starts from, in 'order'. Every cell a move could
+// have started from is tried with 'apply_move', so walls, one-way cells and lava are accounted
+// for. With 'goals' a main piece on the last goal reached may also have counted it with this
+// move, otherwise the goals reached are left alone, which is only exact on boards without goals.
+pub(crate) fn predecessors(
+    board: &Board,
+    state: &State,
+    order: &TieBreak,
+    goals: bool,
+) -> Vec<(Move, State)> {
+    let mut found = Vec::new();
+    let reached = state.goals_reached();
+    let straight = !board
+        .cells()
+        .any(|(_, _, cell)| matches!(cell, BoardPiece::Mirror(..) | BoardPiece::Portal(_)));
+    for piece in order.pieces {
+        let target = state.pos(&piece);
+        // Stopping on the last goal reached may or may not have been what counted it.
+        let counted = goals
+            && piece == Main
+            && reached > 0
+            && *board.at(&target) == BoardPiece::Goal(reached - 1);
+        for dir in order.directions {
+            // A straight slide only ends on 'target' if the piece can't go on from there. Wrapping
+            // around, it may instead have stopped short of the cell it left.
+            let slides = straight && board.movement() == Movement::Slide && !board.is_toroidal();
+            if slides && advance(board, state, &[], &piece, &target, dir).is_some() {
+                continue;
+            }
+            let m = (piece, dir);
+            for pos in sources(board, state, straight, target, &dir) {
+                let before = state.with_pos(&piece, pos);
+                let starts = [
+                    Some(before),
+                    counted.then(|| before.with_goals_reached(reached - 1)),
+                ];
+                for prev in starts.into_iter().flatten() {
+                    if apply_move(board, &prev, &m) == Some(*state) {
+                        found.push((m, prev));
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+// Moves of a state with the states they lead to, at most one for each piece and direction.
+#[cfg(feature = "heapless")]
+pub(crate) type Neighbours = heapless::Vec<(Move, State), 12>;
+#[cfg(not(feature = "heapless"))]
+pub(crate) type Neighbours = Vec<(Move, State)>;
+
+// Gives a vector of all neighbouring states together with corresponding move, in 'order'.
+pub(crate) fn neighbourhood(board: &Board, state: &State, order: &TieBreak) -> Neighbours {
     // Use heapless to skip alloc for a known max size container
     // Heapless instantiation is usually faster than filling an array with a default value.
-    let mut states = heapless::Vec::new();
+    let mut states = Neighbours::new();
 
-    for piece in [Main, HelperOne, HelperTwo] {
-        for direction in [Left, Right, Up, Down] {
+    for piece in order.pieces {
+        for direction in order.directions {
             if let Some(state) = move_piece(board, state, &piece, &direction) {
-                let move_ = (piece.clone(), direction.clone());
+                let move_ = (piece, direction);
+                #[cfg(feature = "heapless")]
                 states.push((move_, state)).expect("Undersized vec");
+                #[cfg(not(feature = "heapless"))]
+                states.push((move_, state));
             }
         }
     }
@@ -165,32 +1115,475 @@ fn neighbourhood(board: &Board, state: &State) -> heapless::Vec<(Move, State), 1
     states
 }
 
+// Number of goals the main piece has to visit before returning to the start.
+pub fn goal_count(board: &Board) -> u8 {
+    board
+        .cells
+        .iter()
+        .flatten()
+        .filter(|piece| matches!(piece, BoardPiece::Goal(_)))
+        .count() as u8
+}
+
+// Main piece has visited every goal and is back on the start cell, with every helper that has a
+// goal standing on it.
+pub fn is_solved(board: &Board, state: &State) -> bool {
+    WinCondition::new(board).is_met(board, state)
+}
+
+// Everything needed to check for a win without scanning the board, computed once per search.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WinCondition {
+    pub(crate) goals: u8,
+    pub(crate) helper_one: Option<Position>,
+    pub(crate) helper_two: Option<Position>,
+}
+
+impl WinCondition {
+    pub(crate) fn new(board: &Board) -> Self {
+        let mut win = Self {
+            goals: goal_count(board),
+            helper_one: None,
+            helper_two: None,
+        };
+        for (y, line) in board.cells.iter().enumerate() {
+            for (x, piece) in line.iter().enumerate() {
+                match piece {
+                    BoardPiece::HelperGoal(HelperOne) => win.helper_one = Some(xy_to_pos(x, y)),
+                    BoardPiece::HelperGoal(HelperTwo) => win.helper_two = Some(xy_to_pos(x, y)),
+                    _ => {}
+                }
+            }
+        }
+        win
+    }
+
+    pub(crate) fn is_met(&self, board: &Board, state: &State) -> bool {
+        state.goals_reached() == self.goals
+            && *board.at(&state.main_pos()) == BoardPiece::Start
+            && self
+                .helper_one
+                .is_none_or(|pos| pos == state.helper_one_pos())
+            && self
+                .helper_two
+                .is_none_or(|pos| pos == state.helper_two_pos())
+    }
+}
+
+// Moves of an optimal solution, the state they end in and what the search took to find them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub moves: Vec<Move>,
+    pub final_state: State,
+    pub stats: SearchStats,
+}
+
+// How a limited search ended, each with the statistics of the search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Solved(Solution),
+    // The whole state space was searched without finding a solution.
+    Unsolvable(SearchStats),
+    // A limit in 'SolveOptions' stopped the search first.
+    LimitReached(SolveError, SearchStats),
+    // No solution takes at most 'SolveOptions::max_depth' moves, which is held.
+    NotWithin(usize, SearchStats),
+}
+
+impl SolveOutcome {
+    pub fn stats(&self) -> &SearchStats {
+        match self {
+            SolveOutcome::Solved(solution) => &solution.stats,
+            SolveOutcome::Unsolvable(stats)
+            | SolveOutcome::LimitReached(_, stats)
+            | SolveOutcome::NotWithin(_, stats) => stats,
+        }
+    }
+
+    pub fn solution(self) -> Option<Solution> {
+        match self {
+            SolveOutcome::Solved(solution) => Some(solution),
+            _ => None,
+        }
+    }
+
+    // The solution if found, None if there is none and the error if the search gave up. Not
+    // finding one within 'max_depth' is 'BudgetExceeded', as for 'upper_bound'.
+    pub fn into_result(self) -> Result<Option<Solution>, SolveError> {
+        match self {
+            SolveOutcome::Solved(solution) => Ok(Some(solution)),
+            SolveOutcome::Unsolvable(_) => Ok(None),
+            SolveOutcome::LimitReached(err, _) => Err(err),
+            SolveOutcome::NotWithin(max_depth, _) => Err(SolveError::BudgetExceeded {
+                lower_bound: max_depth + 1,
+            }),
+        }
+    }
+}
+
 // Breadth first search for first (and thus optimal) solution.
-pub fn solve_puzzle(board: &Board, state: State) -> Option<(&Board, State, Vec<Move>)> {
-    let mut visited = vec![[[[false; 2]; 160]; 160]; 160];
-    let mut queue = VecDeque::new();
+pub fn solve_puzzle(board: &Board, state: State) -> Option<Solution> {
+    solve_puzzle_with(board, state, &SolveOptions::default())
+        .into_result()
+        .expect("Unlimited search failed.")
+}
 
-    queue.push_back(Node::new(None, state, None));
+// Same as 'solve_puzzle' but gives up once a limit in 'options' is reached.
+pub fn solve_puzzle_with(board: &Board, state: State, options: &SolveOptions) -> SolveOutcome {
+    run_search(board, state, options, bfs)
+}
 
-    while let Some(node) = queue.pop_front() {
-        let state = node.state;
-        let sol_found =
-            state.3 == 1 && board[pos_to_y(&state.0)][pos_to_x(&state.0)] == BoardPiece::Start;
+// Final state and moves of a solution, None if there is none.
+pub(crate) type SearchResult = Result<Option<(State, Vec<Move>)>, SolveError>;
 
-        if sol_found {
-            return Some((board, state, node.moves())); // Solution found, yay!
+// Runs 'search', timing it and collecting its statistics into the outcome. 'max_depth' is passed
+// on to the search as an 'upper_bound'.
+pub(crate) fn run_search(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    search: impl FnOnce(&Board, State, &SolveOptions, &mut SearchStats) -> SearchResult,
+) -> SolveOutcome {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("solve").entered();
+    let mut stats = SearchStats::default();
+    #[cfg(feature = "std")]
+    let start = Instant::now();
+    let bounded;
+    let options = match options.max_depth {
+        Some(max_depth) if options.upper_bound.is_none_or(|bound| bound > max_depth) => {
+            bounded = SolveOptions {
+                upper_bound: Some(max_depth),
+                ..options.clone()
+            };
+            &bounded
         }
+        _ => options,
+    };
+    let result = search(board, state, options, &mut stats);
+    let max_depth = options.max_depth.unwrap_or(usize::MAX);
+    #[cfg(feature = "std")]
+    {
+        stats.duration = start.elapsed();
+    }
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        expanded = stats.nodes_expanded,
+        generated = stats.nodes_generated,
+        pruned = stats.duplicates_pruned,
+        max_queue = stats.max_queue_len,
+        visited = ?stats.visited,
+        duration_us = stats.duration.as_micros() as u64,
+        solved = matches!(result, Ok(Some(_))),
+        "search finished"
+    );
+    match result {
+        Ok(Some((final_state, moves))) => SolveOutcome::Solved(Solution {
+            moves,
+            final_state,
+            stats,
+        }),
+        Ok(None) => SolveOutcome::Unsolvable(stats),
+        // Whatever stopped the search, no solution is that short.
+        Err(SolveError::BudgetExceeded { lower_bound }) if lower_bound > max_depth => {
+            SolveOutcome::NotWithin(max_depth, stats)
+        }
+        Err(err) => SolveOutcome::LimitReached(err, stats),
+    }
+}
 
-        let rc_node = Rc::new(node);
-        for (move_, state) in neighbourhood(board, &rc_node.state) {
-            if visited[state.0 as usize][state.1 as usize][state.2 as usize][state.3 as usize] {
-                continue;
+// When a search has to give up by. Without std there is no clock, so searches never time out and
+// their duration is left at zero.
+#[cfg(feature = "std")]
+pub(crate) type Deadline = Option<Instant>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Deadline = Option<core::convert::Infallible>;
+
+#[cfg(feature = "std")]
+pub(crate) fn deadline(options: &SolveOptions) -> Deadline {
+    options.timeout.map(|timeout| Instant::now() + timeout)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn deadline(_options: &SolveOptions) -> Deadline {
+    None
+}
+
+#[cfg(feature = "std")]
+fn expired(deadline: Deadline) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+#[cfg(not(feature = "std"))]
+fn expired(_deadline: Deadline) -> bool {
+    false
+}
+
+// Checks the timeout and cancellation, but only every LIMIT_CHECK_INTERVAL expansions. A cancelled
+// search reports 'depth' and 'frontier' as how far it got.
+pub(crate) fn check_interrupted(
+    options: &SolveOptions,
+    deadline: Deadline,
+    stats: &SearchStats,
+    depth: usize,
+    frontier: usize,
+) -> Result<(), SolveError> {
+    if !stats.nodes_expanded.is_multiple_of(LIMIT_CHECK_INTERVAL) {
+        return Ok(());
+    }
+    check_limits(options, deadline, stats, depth, frontier)
+}
+
+// Same as 'check_interrupted', checked on every call.
+pub(crate) fn check_limits(
+    options: &SolveOptions,
+    deadline: Deadline,
+    stats: &SearchStats,
+    depth: usize,
+    frontier: usize,
+) -> Result<(), SolveError> {
+    if expired(deadline) {
+        return Err(SolveError::Timeout);
+    }
+    if options.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+        return Err(SolveError::Cancelled(SearchProgress {
+            depth,
+            frontier,
+            expanded: stats.nodes_expanded,
+        }));
+    }
+    Ok(())
+}
+
+fn bfs(
+    board: &Board,
+    state: State,
+    options: &SolveOptions,
+    stats: &mut SearchStats,
+) -> SearchResult {
+    let deadline = deadline(options);
+    let win = WinCondition::new(board);
+    let mut visited = Visited::new(board, win.goals);
+    stats.visited = Some(visited.kind());
+    let trace = |event: TraceEvent| {
+        if let Some(on_trace) = &options.on_trace {
+            on_trace(&event);
+        }
+    };
+    if win.is_met(board, &state) {
+        trace(TraceEvent::Goal { state, depth: 0 });
+        return Ok(Some((state, Vec::new())));
+    }
+    let mut tree = Tree::new(state);
+    // Nodes 'depth' moves from the start, expanded in order. Their children are added to the end of
+    // the tree and make up the next layer.
+    let mut layer: Range<usize> = 0..1;
+    let mut depth = 0;
+
+    while !layer.is_empty() {
+        if let Some(on_progress) = &options.on_progress {
+            on_progress(&SearchProgress {
+                depth,
+                frontier: layer.len(),
+                expanded: stats.nodes_expanded,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            depth,
+            frontier = layer.len(),
+            expanded = stats.nodes_expanded,
+            pruned = stats.duplicates_pruned,
+            "depth reached"
+        );
+        stats.depths.push(DepthStats {
+            frontier: layer.len(),
+            visited: tree.len(),
+        });
+
+        // Nodes are checked as they are added, so the whole layer has been and no solution is
+        // this short.
+        if options.upper_bound.is_some_and(|bound| depth >= bound) {
+            return Err(SolveError::BudgetExceeded {
+                lower_bound: depth + 1,
+            });
+        }
+
+        for index in layer.clone() {
+            let queued = tree.len() - index - 1;
+            if let Some(on_progress) = &options.on_progress {
+                let interval_reached = options
+                    .progress_interval
+                    .is_some_and(|n| n > 0 && stats.nodes_expanded.is_multiple_of(n));
+                if interval_reached && index != layer.start {
+                    on_progress(&SearchProgress {
+                        depth,
+                        frontier: queued + 1,
+                        expanded: stats.nodes_expanded,
+                    });
+                }
             }
 
-            queue.push_back(Node::new(Some(move_), state, Some(Rc::clone(&rc_node))));
-            visited[state.0 as usize][state.1 as usize][state.2 as usize][state.3 as usize] = true;
+            let over_budget = options
+                .max_nodes
+                .is_some_and(|max| stats.nodes_expanded >= max)
+                || options.max_queue.is_some_and(|max| queued >= max);
+            if over_budget {
+                return Err(SolveError::BudgetExceeded {
+                    lower_bound: depth + 1,
+                });
+            }
+
+            stats.nodes_expanded += 1;
+            check_interrupted(options, deadline, stats, depth, queued)?;
+
+            let from = tree.states[index];
+            trace(TraceEvent::Expand { state: from, depth });
+            for (m, next) in neighbourhood(board, &from, &options.tie_break) {
+                stats.nodes_generated += 1;
+                if !visited.insert(&next) {
+                    stats.duplicates_pruned += 1;
+                    trace(TraceEvent::PruneDuplicate {
+                        from,
+                        m,
+                        state: next,
+                    });
+                    continue;
+                }
+                trace(TraceEvent::Generate {
+                    from,
+                    m,
+                    state: next,
+                });
+                tree.push(next, index, m);
+                // Checking here rather than when the node is expanded saves expanding the rest of
+                // the layer before it.
+                if win.is_met(board, &next) {
+                    trace(TraceEvent::Goal {
+                        state: next,
+                        depth: depth + 1,
+                    });
+                    return Ok(Some((next, tree.path(tree.len() - 1)))); // Solution found, yay!
+                }
+            }
+            stats.max_queue_len = stats.max_queue_len.max(tree.len() - index - 1);
+        }
+        layer = layer.end..tree.len();
+        depth += 1;
+    }
+
+    Ok(None) // Exhausted search, no solution found.
+}
+
+// First move of an optimal solution from the current position, None if solved or unsolvable.
+pub fn hint(board: &Board, state: State) -> Option<Move> {
+    solve_puzzle(board, state)?.moves.first().copied()
+}
+
+// Why moves can't start a solution, see 'solve_with_prefix'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    // Move of the prefix, counting from 0, that can't be made.
+    IllegalMove(usize),
+    // The puzzle is solved after this many moves of the prefix, before its end.
+    SolvedAfter(usize),
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::IllegalMove(i) => write!(f, "move {} can't be made", i + 1),
+            PrefixError::SolvedAfter(i) => write!(f, "puzzle is already solved after move {i}"),
         }
     }
+}
+
+// Shortest solution starting with the moves of 'prefix', such as the ones a player has made so
+// far. The solution holds the prefix followed by the optimal continuation, the statistics are of
+// the search for the continuation alone and limits in 'options' apply to it.
+pub fn solve_with_prefix(
+    board: &Board,
+    state: State,
+    prefix: &[Move],
+    options: &SolveOptions,
+) -> Result<SolveOutcome, PrefixError> {
+    let mut current = state;
+    for (i, m) in prefix.iter().enumerate() {
+        if is_solved(board, &current) {
+            return Err(PrefixError::SolvedAfter(i));
+        }
+        current = apply_move(board, &current, m).ok_or(PrefixError::IllegalMove(i))?;
+    }
+    let outcome = solve_puzzle_with(board, current, options);
+    Ok(match outcome {
+        SolveOutcome::Solved(mut solution) => {
+            solution.moves.splice(0..0, prefix.iter().copied());
+            SolveOutcome::Solved(solution)
+        }
+        outcome => outcome,
+    })
+}
+
+// Iterator over the states reached by applying a sequence of moves, see 'playback'.
+pub struct Playback<'a> {
+    board: &'a Board,
+    state: State,
+    moves: core::slice::Iter<'a, Move>,
+}
 
-    None // Exhausted search, no solution found.
+impl Iterator for Playback<'_> {
+    type Item = (Move, State);
+
+    // Stops at the end of the moves or at the first illegal move.
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = *self.moves.next()?;
+        let Some(state) = apply_move(self.board, &self.state, &m) else {
+            self.moves = [].iter();
+            return None;
+        };
+        self.state = state;
+        Some((m, state))
+    }
+}
+
+// Yields each move together with the state after it has been applied.
+pub fn playback<'a>(board: &'a Board, state: State, moves: &'a [Move]) -> Playback<'a> {
+    Playback {
+        board,
+        state,
+        moves: moves.iter(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_layout_takes_the_bits_the_board_needs() {
+        let mut board = Board::new(5, 3);
+        board.set(1, 1, BoardPiece::Goal(0));
+        board.set(2, 1, BoardPiece::Goal(1));
+        let layout = StateLayout::new(&board, 3).unwrap();
+        // Three bits for x, two for y, two for up to two goals reached.
+        assert_eq!(layout.bits(), 3 * (3 + 2) + 2);
+        let state =
+            State::new(xy_to_pos(4, 2), xy_to_pos(0, 1), xy_to_pos(3, 0)).with_goals_reached(2);
+        assert_eq!(layout.unpack_state(layout.pack_state(&state)), state);
+    }
+
+    #[test]
+    fn state_layout_holds_any_number_of_pieces() {
+        let board = Board::new(MAX_SIDE, MAX_SIDE);
+        let layout = StateLayout::new(&board, 15).unwrap();
+        let positions: Vec<Position> = (0..15).map(|i| xy_to_pos(i, 15 - i)).collect();
+        let mut unpacked = [0; 15];
+        assert_eq!(
+            layout.unpack(layout.pack(positions.clone(), 0), &mut unpacked),
+            0
+        );
+        assert_eq!(unpacked.as_slice(), positions);
+        assert!(StateLayout::new(&board, 16).is_some());
+        assert!(StateLayout::new(&board, 17).is_none());
+    }
 }
@@ -0,0 +1,134 @@
+use std::io::{self, Read, Write};
+use std::time::Instant;
+
+use crate::analysis::{distances, StateSpace, UNSOLVABLE};
+use crate::canonical::board_hash;
+use crate::heuristic::{self, Heuristic};
+use crate::solver::*;
+
+const MAGIC: &[u8; 4] = b"SSTB";
+const VERSION: u8 = 1;
+
+// Exact number of moves left from every state of one board, so any puzzle on it is solved by
+// looking up each move in turn instead of searching. Building it searches the whole state space
+// backwards from the solved states once, which takes seconds and tens of bytes per state
+// while it runs. The table itself is two bytes per state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tablebase {
+    board: u64,
+    space: StateSpace,
+    // Indexed by slot of 'space'.
+    distances: Vec<u16>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Tablebase {
+    pub fn new(board: &Board) -> Self {
+        let space = StateSpace::new(board);
+        Self {
+            board: board_hash(board),
+            distances: distances(board, &space),
+            space,
+        }
+    }
+
+    // Whether the tablebase was built for 'board'. Boards that are rotated or reflected don't
+    // match.
+    pub fn fits(&self, board: &Board) -> bool {
+        self.board == board_hash(board)
+    }
+
+    // Fewest moves solving the puzzle from 'state', None if it can't be solved or a piece is on a
+    // cell it can't stand on.
+    pub fn distance(&self, state: &State) -> Option<usize> {
+        match self.distances[self.space.index(state)?] {
+            UNSOLVABLE => None,
+            distance => Some(distance as usize),
+        }
+    }
+
+    // Optimal solution found by always moving to a state one move closer, tried in 'order'. None
+    // if the tablebase was built for another board or 'state' has pieces on top of each other or
+    // on cells they can't stand on, which the table doesn't cover.
+    pub fn solve(&self, board: &Board, state: State, order: &TieBreak) -> Option<SolveOutcome> {
+        let start = Instant::now();
+        if !self.fits(board) || self.space.index(&state).is_none() {
+            return None;
+        }
+        let mut stats = SearchStats::default();
+        let Some(mut left) = self.distance(&state) else {
+            return Some(SolveOutcome::Unsolvable(stats));
+        };
+        let (mut current, mut moves) = (state, Vec::with_capacity(left));
+        while left > 0 {
+            let neighbours = neighbourhood(board, &current, order);
+            stats.nodes_expanded += 1;
+            stats.nodes_generated += neighbours.len();
+            let (m, next) = neighbours
+                .into_iter()
+                .find(|(_, next)| self.distance(next) == Some(left - 1))
+                .expect("Tablebase has no move closer to the goal.");
+            moves.push(m);
+            (current, left) = (next, left - 1);
+        }
+        stats.duration = start.elapsed();
+        Some(SolveOutcome::Solved(Solution {
+            moves,
+            final_state: current,
+            stats,
+        }))
+    }
+
+    // Writes the magic bytes 'SSTB' and a version byte, then the board hash and the number of
+    // slots as u64s and every slot's distance as a u16 (u16::MAX if unsolvable), all little
+    // endian.
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&self.board.to_le_bytes())?;
+        out.write_all(&(self.distances.len() as u64).to_le_bytes())?;
+        let bytes: Vec<u8> = self
+            .distances
+            .iter()
+            .flat_map(|distance| distance.to_le_bytes())
+            .collect();
+        out.write_all(&bytes)
+    }
+
+    // Reads a tablebase written by 'write', failing if it was built for another board.
+    pub fn read(mut input: impl Read, board: &Board) -> io::Result<Self> {
+        let mut header = [0; 21];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid("not a tablebase"));
+        }
+        let space = StateSpace::new(board);
+        if u64::from_le_bytes(header[5..13].try_into().unwrap()) != board_hash(board) {
+            return Err(invalid("tablebase is for another board"));
+        }
+        if u64::from_le_bytes(header[13..].try_into().unwrap()) != space.slots() as u64 {
+            return Err(invalid("tablebase has the wrong number of states"));
+        }
+        let mut bytes = vec![0; space.slots() * 2];
+        input.read_exact(&mut bytes)?;
+        Ok(Self {
+            board: board_hash(board),
+            distances: bytes
+                .chunks_exact(2)
+                .map(|distance| u16::from_le_bytes([distance[0], distance[1]]))
+                .collect(),
+            space,
+        })
+    }
+}
+
+// Exact, so A* and IDA* guided by it go straight to an optimal solution.
+impl Heuristic for Tablebase {
+    fn estimate(&self, _board: &Board, state: &State) -> u32 {
+        self.distance(state)
+            .map_or(heuristic::UNSOLVABLE, |distance| distance as u32)
+    }
+}
@@ -0,0 +1,231 @@
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::test_runner::{TestCaseError, TestCaseResult};
+
+use crate::compact::{decode_compact, encode_compact};
+use crate::search::Algorithm;
+use crate::solver::*;
+use crate::tools::{puzzle_from_string, puzzle_to_string, Puzzle};
+use crate::validate::validate;
+
+pub fn directions() -> impl Strategy<Value = Direction> {
+    prop_oneof![
+        Just(Direction::Up),
+        Just(Direction::Down),
+        Just(Direction::Left),
+        Just(Direction::Right),
+    ]
+}
+
+pub fn pieces() -> impl Strategy<Value = PieceType> {
+    prop_oneof![
+        Just(PieceType::Main),
+        Just(PieceType::HelperOne),
+        Just(PieceType::HelperTwo),
+    ]
+}
+
+// Up to 'max' moves of any piece, legal or not.
+pub fn moves(max: usize) -> impl Strategy<Value = Vec<Move>> {
+    vec((pieces(), directions()), 0..=max)
+}
+
+// Mostly empty cells, the start, goals and portals are placed separately. No pushable blockers,
+// which fail 'validate'.
+fn cells() -> impl Strategy<Value = BoardPiece> {
+    let helpers = prop_oneof![Just(PieceType::HelperOne), Just(PieceType::HelperTwo)];
+    let slants = prop_oneof![Just(Slant::Slash), Just(Slant::Backslash)];
+    prop_oneof![
+        12 => Just(BoardPiece::Empty),
+        1 => Just(BoardPiece::Blocker),
+        1 => Just(BoardPiece::Void),
+        1 => (slants, proptest::option::of(pieces()))
+            .prop_map(|(slant, key)| BoardPiece::Mirror(slant, key)),
+        1 => directions().prop_map(BoardPiece::OneWay),
+        1 => helpers.prop_map(BoardPiece::HelperGoal),
+        1 => proptest::option::of(pieces()).prop_map(BoardPiece::Lava),
+    ]
+}
+
+// Puzzles that pass 'validate', on boards of 2 to 'max_side' cells a side, which can't be above
+// 10. Any cell kind, walls, movement and up to two portals show up, and the main piece is on the
+// start with no goals reached, so each one survives 'puzzle_to_string'.
+pub fn puzzles(max_side: usize) -> impl Strategy<Value = Puzzle> {
+    assert!(
+        (2..=10).contains(&max_side),
+        "Boards are 2 to 10 cells a side."
+    );
+    (2..=max_side, 2..=max_side)
+        .prop_flat_map(|(width, height)| {
+            let cells = width * height;
+            let movement = prop_oneof![
+                Just(Movement::Slide),
+                Just(Movement::Step),
+                Just(Movement::Sticky),
+            ];
+            (
+                (Just(width), Just(height), any::<bool>(), movement),
+                vec(self::cells(), cells),
+                vec((0..width, 0..height, directions()), 0..=cells / 4),
+                // Goals, portals and the order cells are handed out to them and the pieces.
+                (
+                    1..=3u8,
+                    0..=2usize,
+                    Just((0..cells).collect::<Vec<_>>()).prop_shuffle(),
+                ),
+            )
+        })
+        .prop_filter_map(
+            "puzzle has to pass validate",
+            |((width, height, toroidal, movement), cells, walls, (goals, portals, order))| {
+                let mut board = Board::new(width, height);
+                // Walls wrap around the edges only if the board is toroidal when they are added.
+                board.set_toroidal(toroidal);
+                board.set_movement(movement);
+                for (i, cell) in cells.into_iter().enumerate() {
+                    board.set(i % width, i / width, cell);
+                }
+                for (x, y, side) in walls {
+                    board.add_wall(x, y, side);
+                }
+
+                let mut order = order.into_iter().map(|cell| (cell % width, cell / width));
+                // Leaves room for the start and both helpers.
+                let goals = goals.min((width * height - 3) as u8);
+                for (goal, (x, y)) in (0..goals).zip(&mut order) {
+                    board.set(x, y, BoardPiece::Goal(goal));
+                }
+                let mut place = |piece: Option<BoardPiece>| {
+                    let (x, y) = order.next()?;
+                    match piece {
+                        Some(piece) => board.set(x, y, piece),
+                        None if matches!(
+                            board.get(x, y),
+                            BoardPiece::Blocker | BoardPiece::Void | BoardPiece::Lava(_)
+                        ) =>
+                        {
+                            board.set(x, y, BoardPiece::Empty)
+                        }
+                        None => {}
+                    }
+                    Some(xy_to_pos(x, y))
+                };
+                let main = place(Some(BoardPiece::Start))?;
+                let (helper_one, helper_two) = (place(None)?, place(None)?);
+                let mut ends = Vec::new();
+                for _ in 0..portals * 2 {
+                    match place(Some(BoardPiece::Empty)) {
+                        Some(end) => ends.push(end),
+                        None => break,
+                    }
+                }
+                for pair in ends.chunks_exact(2) {
+                    board.add_portal(pair[0], pair[1]);
+                }
+
+                let state = State::new(main, helper_one, helper_two);
+                validate(&board, &state)
+                    .is_empty()
+                    .then_some(Puzzle { board, state })
+            },
+        )
+}
+
+// Writing 'puzzle' with 'puzzle_to_string' and parsing it back gives the same board and state.
+// Meant for puzzles from 'puzzles', and to be used with '?' inside 'proptest!'.
+pub fn check_round_trip(puzzle: &Puzzle) -> TestCaseResult {
+    let written = puzzle_to_string(&puzzle.board, &puzzle.state);
+    let (board, state) = puzzle_from_string(&written)
+        .map_err(|err| TestCaseError::fail(format!("Failed to parse {written}: {err}")))?;
+    prop_assert!(board == puzzle.board, "Board changed parsing {written}");
+    prop_assert_eq!(state, puzzle.state, "State changed parsing {}", written);
+    prop_assert_eq!(puzzle_to_string(&board, &state), written);
+    Ok(())
+}
+
+// Same as 'check_round_trip' for 'encode_compact' and 'decode_compact'.
+pub fn check_compact_round_trip(puzzle: &Puzzle) -> TestCaseResult {
+    let encoded = encode_compact(&puzzle.board, &puzzle.state);
+    let decoded = decode_compact(&encoded);
+    prop_assert!(decoded.is_some(), "Failed to decode {}", encoded);
+    let (board, state) = decoded.unwrap();
+    prop_assert!(board == puzzle.board, "Board changed decoding {encoded}");
+    prop_assert_eq!(state, puzzle.state, "State changed decoding {}", encoded);
+    Ok(())
+}
+
+// Every move in 'moves' is legal from 'state' and the last one leaves the puzzle solved.
+pub fn check_solution(board: &Board, state: State, moves: &[Move]) -> TestCaseResult {
+    let mut end = state;
+    for (i, m) in moves.iter().enumerate() {
+        match apply_move(board, &end, m) {
+            Some(next) => end = next,
+            None => return Err(TestCaseError::fail(format!("Move {i} {m:?} is illegal."))),
+        }
+    }
+    prop_assert!(
+        is_solved(board, &end),
+        "Moves {:?} don't solve the puzzle.",
+        moves
+    );
+    Ok(())
+}
+
+// Each algorithm in 'Algorithm::ALL' and 'Algorithm::EXPERIMENTAL' that finishes within 'options'
+// agrees on whether the puzzle can be solved and on the optimal number of moves, and its solution
+// passes 'check_solution'. Searches stopped by a limit are left out.
+pub fn check_solvers_agree(board: &Board, state: State, options: &SolveOptions) -> TestCaseResult {
+    let mut agreed: Option<(Algorithm, Option<usize>)> = None;
+    for algorithm in Algorithm::ALL.into_iter().chain(Algorithm::EXPERIMENTAL) {
+        let search = algorithm.with_options(options.clone());
+        let Ok(solution) = search.search(board, state).into_result() else {
+            continue;
+        };
+        if let Some(solution) = &solution {
+            check_solution(board, state, &solution.moves)?;
+        }
+        let length = solution.map(|solution| solution.moves.len());
+        match agreed {
+            Some((first, agreed)) => prop_assert_eq!(
+                length,
+                agreed,
+                "{} and {} found different optimal lengths",
+                algorithm,
+                first
+            ),
+            None => agreed = Some((algorithm, length)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn puzzles_survive_the_string_format(puzzle in puzzles(8)) {
+            check_round_trip(&puzzle)?;
+        }
+
+        #[test]
+        fn puzzles_survive_the_compact_format(puzzle in puzzles(8)) {
+            check_compact_round_trip(&puzzle)?;
+        }
+    }
+
+    proptest! {
+        // Searches are slow unoptimized, so fewer and smaller ones than for the formats.
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn solvers_agree(puzzle in puzzles(8)) {
+            let options = SolveOptions {
+                max_nodes: Some(10_000),
+                ..SolveOptions::default()
+            };
+            check_solvers_agree(&puzzle.board, puzzle.state, &options)?;
+        }
+    }
+}
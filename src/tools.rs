@@ -1,84 +1,408 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+use core::fmt::{self, Write};
+
 use crate::solver::*;
+use crate::validate::misnumbered_goal;
+
+// Why a puzzle string could not be read, see 'puzzle_from_string'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // Entry of this kind ended early or has a field that can't be read.
+    InvalidEntry(String),
+    // Board sides have to be 1 to 'MAX_SIDE' cells.
+    InvalidSize { width: usize, height: usize },
+    // Cell of an entry is off the board, or the entry came before the 'map' entry.
+    OutOfBounds { x: usize, y: usize },
+    // Goals are numbered from 0 without gaps, this index is missing or used twice.
+    GoalIndex(u8),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidEntry(kind) => write!(f, "invalid '{kind}' entry"),
+            ParseError::InvalidSize { width, height } => {
+                write!(
+                    f,
+                    "{width}x{height} board, sides must be 1 to {MAX_SIDE} cells"
+                )
+            }
+            ParseError::OutOfBounds { x, y } => write!(f, "cell {x},{y} is outside the board"),
+            ParseError::GoalIndex(index) => write!(f, "goal {index} is missing or repeated"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+fn field<'a>(items: &mut impl Iterator<Item = &'a str>, kind: &str) -> Result<&'a str, ParseError> {
+    items
+        .next()
+        .ok_or_else(|| ParseError::InvalidEntry(kind.to_string()))
+}
+
+fn number<'a>(items: &mut impl Iterator<Item = &'a str>, kind: &str) -> Result<usize, ParseError> {
+    field(items, kind)?
+        .parse()
+        .map_err(|_| ParseError::InvalidEntry(kind.to_string()))
+}
+
+// Coordinates of a cell on 'board'.
+fn cell<'a>(
+    board: &Board,
+    items: &mut impl Iterator<Item = &'a str>,
+    kind: &str,
+) -> Result<(usize, usize), ParseError> {
+    let (x, y) = (number(items, kind)?, number(items, kind)?);
+    if x >= board.width() || y >= board.height() {
+        return Err(ParseError::OutOfBounds { x, y });
+    }
+    Ok((x, y))
+}
+
+fn side<'a>(
+    items: &mut impl Iterator<Item = &'a str>,
+    kind: &str,
+) -> Result<Direction, ParseError> {
+    match field(items, kind)? {
+        "N" => Ok(Direction::Up),
+        "S" => Ok(Direction::Down),
+        "W" => Ok(Direction::Left),
+        "E" => Ok(Direction::Right),
+        _ => Err(ParseError::InvalidEntry(kind.to_string())),
+    }
+}
+
+// Optional piece a mirror or lava is keyed to, 'main' or a helper index.
+fn key<'a, I: Iterator<Item = &'a str>>(items: &mut core::iter::Peekable<I>) -> Option<PieceType> {
+    match items.next_if(|item| matches!(*item, "main" | "0" | "1")) {
+        Some("main") => Some(PieceType::Main),
+        Some("0") => Some(PieceType::HelperTwo),
+        Some("1") => Some(PieceType::HelperOne),
+        _ => None,
+    }
+}
 
-pub fn puzzle_from_string(input: &str) -> (Board, State) {
-    let items: Vec<&str> = input.split(":").collect();
-    let mut board =
-        vec![vec![BoardPiece::Empty; items[1].parse().unwrap()]; items[2].parse().unwrap()];
-    let mut state = (0, 0, 0, 0);
+// Parses the colon separated format, 'map:<width>:<height>' followed by '<kind>:<x>:<y>' entries.
+// Goals take an optional trailing index giving the order they are visited in, 'goal:<x>:<y>' is
+// the same as 'goal:<x>:<y>:0'. Helper goals take an optional index of the helper they belong to,
+// counting 'helper_robot' entries in the order they appear, defaulting to the first one. Walls are
+// given as 'wall:<x>:<y>:<side>' with side one of N, S, E or W. Mirrors are given as
+// 'mirror:<x>:<y>:<slant>' with slant '/' or '\', optionally followed by the piece that passes
+// straight through it, 'main' or a helper index. One-way cells are given as 'oneway:<x>:<y>:<side>'
+// and can only be entered moving towards that side. Portal pairs are given as
+// 'portal:<x>:<y>:<twin x>:<twin y>' and blockers the main piece can push as 'pushable:<x>:<y>'.
+// Cells missing from the board are given as 'void:<x>:<y>' and lava as 'lava:<x>:<y>', optionally
+// followed by the only piece it is fatal to like mirrors.
+// A 'toroidal' entry right after the map makes pieces wrap around the edges, 'movement:step' makes
+// pieces move a single cell at a time instead of sliding and 'movement:sticky' makes them stop as
+// soon as they are next to another piece. Entries of unknown kinds are skipped, but malformed
+// entries, cells off the board and goals not numbered 0, 1, 2 and on fail the whole puzzle.
+pub fn puzzle_from_string(input: &str) -> Result<(Board, State), ParseError> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse").entered();
+    let mut items = input.split(':').peekable();
+    let mut board = Board::default();
+    let mut state = State::default();
     let mut first_helper_found = false;
-    for parts in items.chunks(3) {
-        match parts[0] {
+    while let Some(kind) = items.next() {
+        match kind {
+            "map" => {
+                let (width, height) = (number(&mut items, kind)?, number(&mut items, kind)?);
+                if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+                    return Err(ParseError::InvalidSize { width, height });
+                }
+                board = Board::new(width, height);
+            }
+            "toroidal" => board.set_toroidal(true),
+            "movement" => {
+                board.set_movement(match field(&mut items, kind)? {
+                    "slide" => Movement::Slide,
+                    "step" => Movement::Step,
+                    "sticky" => Movement::Sticky,
+                    _ => return Err(ParseError::InvalidEntry(kind.to_string())),
+                });
+            }
             "main_robot" => {
-                let x = parts[1].parse::<u8>().unwrap();
-                let y = parts[2].parse::<u8>().unwrap();
-                board[y as usize][x as usize] = BoardPiece::Start;
-                state.0 = (x << 4) + y;
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::Start);
+                state = state.with_pos(&PieceType::Main, xy_to_pos(x, y));
             }
             "helper_robot" => {
-                if first_helper_found {
-                    let x = parts[1].parse::<u8>().unwrap();
-                    let y = parts[2].parse::<u8>().unwrap();
-                    state.1 = (x << 4) + y;
+                let (x, y) = cell(&board, &mut items, kind)?;
+                let helper = if first_helper_found {
+                    PieceType::HelperOne
                 } else {
                     first_helper_found = true;
-                    let x = parts[1].parse::<u8>().unwrap();
-                    let y = parts[2].parse::<u8>().unwrap();
-                    state.2 = (x << 4) + y;
-                }
+                    PieceType::HelperTwo
+                };
+                state = state.with_pos(&helper, xy_to_pos(x, y));
             }
             "goal" => {
-                let (x, y) = (
-                    parts[1].parse::<usize>().unwrap(),
-                    parts[2].parse::<usize>().unwrap(),
-                );
-                board[y][x] = BoardPiece::Goal;
+                let (x, y) = cell(&board, &mut items, kind)?;
+                let index = items
+                    .next_if(|item| item.parse::<u8>().is_ok())
+                    .map_or(0, |index| index.parse().unwrap());
+                board.set(x, y, BoardPiece::Goal(index));
+            }
+            "helper_goal" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                // The first listed helper is stored as HelperTwo, see above.
+                let helper = match items.next_if(|item| item.parse::<u8>().is_ok()) {
+                    Some("1") => PieceType::HelperOne,
+                    _ => PieceType::HelperTwo,
+                };
+                board.set(x, y, BoardPiece::HelperGoal(helper));
             }
             "blocker" => {
-                let (x, y) = (
-                    parts[1].parse::<usize>().unwrap(),
-                    parts[2].parse::<usize>().unwrap(),
-                );
-                board[y][x] = BoardPiece::Blocker;
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::Blocker);
+            }
+            "void" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::Void);
+            }
+            "pushable" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::Pushable);
+            }
+            "mirror" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                let slant = match field(&mut items, kind)? {
+                    "/" => Slant::Slash,
+                    "\\" => Slant::Backslash,
+                    _ => return Err(ParseError::InvalidEntry(kind.to_string())),
+                };
+                board.set(x, y, BoardPiece::Mirror(slant, key(&mut items)));
+            }
+            "lava" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::Lava(key(&mut items)));
+            }
+            "wall" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.add_wall(x, y, side(&mut items, kind)?);
+            }
+            "portal" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                let (twin_x, twin_y) = cell(&board, &mut items, kind)?;
+                board.add_portal(xy_to_pos(x, y), xy_to_pos(twin_x, twin_y));
+            }
+            "oneway" => {
+                let (x, y) = cell(&board, &mut items, kind)?;
+                board.set(x, y, BoardPiece::OneWay(side(&mut items, kind)?));
             }
             _ => {}
         }
     }
-    (board, state)
+    // Checked once every entry is in, goals can be listed in any order.
+    if let Some(index) = misnumbered_goal(&board) {
+        return Err(ParseError::GoalIndex(index));
+    }
+    Ok((board, state))
 }
 
-// TODO: Improve print.
-fn print_move(m: &(PieceType, Direction)) {
-    let (piece, dir) = m;
-    match piece {
-        PieceType::Main => print!("Main "),
-        PieceType::HelperOne => print!("Helper1 "),
-        PieceType::HelperTwo => print!("Helper2 "),
+fn side_name(side: &Direction) -> &'static str {
+    match side {
+        Direction::Up => "N",
+        Direction::Down => "S",
+        Direction::Left => "W",
+        Direction::Right => "E",
     }
-    match dir {
-        Direction::Up => print!("Up"),
-        Direction::Down => print!("Down"),
-        Direction::Left => print!("Left"),
-        Direction::Right => print!("Right"),
+}
+
+// Index of a helper in the puzzle format, where the first 'helper_robot' is HelperTwo.
+fn helper_index(helper: &PieceType) -> &'static str {
+    match helper {
+        PieceType::HelperOne => "1",
+        _ => "0",
+    }
+}
+
+fn key_name(key: &Option<PieceType>) -> &'static str {
+    match key {
+        Some(PieceType::Main) => ":main",
+        Some(PieceType::HelperOne) => ":1",
+        Some(PieceType::HelperTwo) => ":0",
+        None => "",
     }
-    println!();
 }
 
-pub fn print_moves(moves: &Vec<(PieceType, Direction)>) {
+// Writes a puzzle in the format read by 'puzzle_from_string'. The start cell is where the main
+// piece is, so only states with the main piece on the start and no goals reached parse back the
+// same.
+pub fn puzzle_to_string(board: &Board, state: &State) -> String {
+    let mut out = format!("map:{}:{}", board.width(), board.height());
+    if board.is_toroidal() {
+        out += ":toroidal";
+    }
+    match board.movement() {
+        Movement::Slide => {}
+        Movement::Step => out += ":movement:step",
+        Movement::Sticky => out += ":movement:sticky",
+    }
+    for (x, y, cell) in board.cells() {
+        let _ = match cell {
+            BoardPiece::Goal(index) => write!(out, ":goal:{x}:{y}:{index}"),
+            BoardPiece::HelperGoal(helper) => {
+                write!(out, ":helper_goal:{x}:{y}:{}", helper_index(helper))
+            }
+            BoardPiece::Mirror(slant, key) => {
+                let slant = match slant {
+                    Slant::Slash => "/",
+                    Slant::Backslash => "\\",
+                };
+                write!(out, ":mirror:{x}:{y}:{slant}{}", key_name(key))
+            }
+            BoardPiece::Lava(key) => write!(out, ":lava:{x}:{y}{}", key_name(key)),
+            BoardPiece::OneWay(side) => write!(out, ":oneway:{x}:{y}:{}", side_name(side)),
+            BoardPiece::Blocker => write!(out, ":blocker:{x}:{y}"),
+            BoardPiece::Pushable => write!(out, ":pushable:{x}:{y}"),
+            BoardPiece::Void => write!(out, ":void:{x}:{y}"),
+            _ => Ok(()),
+        };
+        for side in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            if board.walls(x, y) & wall_bit(&side) != 0 {
+                let _ = write!(out, ":wall:{x}:{y}:{}", side_name(&side));
+            }
+        }
+    }
+    for (a, b) in board.portals() {
+        let _ = write!(
+            out,
+            ":portal:{}:{}:{}:{}",
+            pos_to_x(a),
+            pos_to_y(a),
+            pos_to_x(b),
+            pos_to_y(b)
+        );
+    }
+    for (kind, pos) in [
+        ("main_robot", state.main_pos()),
+        ("helper_robot", state.helper_two_pos()),
+        ("helper_robot", state.helper_one_pos()),
+    ] {
+        let _ = write!(out, ":{kind}:{}:{}", pos_to_x(&pos), pos_to_y(&pos));
+    }
+    out
+}
+
+// Board with a state, as generated for the fuzz targets and property tests. Those only make ones
+// that pass 'validate': the main piece on the start and the helpers on other cells they can stand
+// on.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub board: Board,
+    pub state: State,
+}
+
+// Board rows spelled out, so failing inputs can be rebuilt from the fuzzer output.
+impl core::fmt::Debug for Puzzle {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let board = &self.board;
+        let mut cells = vec![Vec::new(); board.height()];
+        let mut walls = vec![Vec::new(); board.height()];
+        for (x, y, cell) in board.cells() {
+            cells[y].push(cell.clone());
+            walls[y].push(board.walls(x, y));
+        }
+        f.debug_struct("Puzzle")
+            .field("toroidal", &board.is_toroidal())
+            .field("movement", &board.movement())
+            .field("cells", &cells)
+            .field("walls", &walls)
+            .field("portals", &board.portals())
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+pub fn format_move(m: &Move) -> String {
+    let (piece, dir) = m;
+    let piece = match piece {
+        PieceType::Main => "Main",
+        PieceType::HelperOne => "Helper1",
+        PieceType::HelperTwo => "Helper2",
+    };
+    let dir = match dir {
+        Direction::Up => "Up",
+        Direction::Down => "Down",
+        Direction::Left => "Left",
+        Direction::Right => "Right",
+    };
+    format!("{piece} {dir}")
+}
+
+// Reads a move written by 'format_move'.
+pub fn parse_move(text: &str) -> Option<Move> {
+    let (piece, dir) = text.split_once(' ')?;
+    let piece = match piece {
+        "Main" => PieceType::Main,
+        "Helper1" => PieceType::HelperOne,
+        "Helper2" => PieceType::HelperTwo,
+        _ => return None,
+    };
+    let dir = match dir {
+        "Up" => Direction::Up,
+        "Down" => Direction::Down,
+        "Left" => Direction::Left,
+        "Right" => Direction::Right,
+        _ => return None,
+    };
+    Some((piece, dir))
+}
+
+#[cfg(feature = "std")]
+fn print_move(m: &Move) {
+    println!("{}", format_move(m));
+}
+
+#[cfg(feature = "std")]
+pub fn print_moves(moves: &[Move]) {
     for m in moves {
         print_move(m);
     }
 }
 
+#[cfg(feature = "std")]
+pub(crate) fn arrow(dir: &Direction) -> &'static str {
+    match dir {
+        Direction::Up => "^",
+        Direction::Down => "v",
+        Direction::Left => "<",
+        Direction::Right => ">",
+    }
+}
+
+// Rows of the board with the pieces of 'state' drawn in.
+#[cfg(feature = "std")]
+fn with_pieces(board: &Board, state: &State) -> Vec<Vec<BoardPiece>> {
+    let mut rows = vec![Vec::with_capacity(board.width()); board.height()];
+    for (_, y, piece) in board.cells() {
+        rows[y].push(piece.clone());
+    }
+    for (piece, pos) in [
+        (BoardPiece::BoardMain, state.main_pos()),
+        (BoardPiece::BoardHelper, state.helper_one_pos()),
+        (BoardPiece::BoardHelper, state.helper_two_pos()),
+    ] {
+        rows[pos_to_y(&pos)][pos_to_x(&pos)] = piece;
+    }
+    rows
+}
+
 // TODO: Cleanup
+#[cfg(feature = "std")]
 pub fn print_board(board: &Board, state: State) {
-    let mut new_board = board.clone();
+    let new_board = with_pieces(board, &state);
     println!("==========");
-    let (m_x, m_y) = (pos_to_x(&state.0), pos_to_y(&state.0));
-    let (h1_x, h1_y) = (pos_to_x(&state.1), pos_to_y(&state.1));
-    let (h2_x, h2_y) = (pos_to_x(&state.2), pos_to_y(&state.2));
-    new_board[m_y][m_x] = BoardPiece::BoardMain;
-    new_board[h1_y][h1_x] = BoardPiece::BoardHelper;
-    new_board[h2_y][h2_x] = BoardPiece::BoardHelper;
     for line in new_board {
         let mut output_line = "|".to_string();
         for piece in line {
@@ -86,14 +410,98 @@ pub fn print_board(board: &Board, state: State) {
                 BoardPiece::BoardMain => "M",
                 BoardPiece::BoardHelper => "H",
                 BoardPiece::Blocker => "#",
-                BoardPiece::Goal => "o",
+                BoardPiece::Pushable => "%",
+                BoardPiece::Void => "~",
+                BoardPiece::Lava(_) => "!",
+                BoardPiece::Goal(_) => "o",
+                BoardPiece::HelperGoal(_) => "x",
+                BoardPiece::Mirror(Slant::Slash, _) => "/",
+                BoardPiece::Mirror(Slant::Backslash, _) => "\\",
+                BoardPiece::OneWay(dir) => arrow(&dir),
                 BoardPiece::Empty => " ",
+                BoardPiece::Portal(_) => "@",
                 BoardPiece::Start => "+",
             };
-            output_line = output_line + sign;
+            output_line += sign;
         }
-        output_line = output_line + "|";
+        output_line += "|";
         println!("{output_line}");
     }
     println!("==========");
 }
+
+#[cfg(feature = "std")]
+const RESET: &str = "\x1b[0m";
+#[cfg(feature = "std")]
+const PATH_BG: &str = "\x1b[45m";
+
+// Prints the board using ANSI colors, highlighting the cells the main piece slides over in 'moves'.
+#[cfg(feature = "std")]
+pub fn print_board_colored(board: &Board, state: State, moves: &[Move]) {
+    let mut path = vec![vec![false; board.width()]; board.height()];
+    let mut current = state;
+    for (m, next) in playback(board, state, moves) {
+        if let PieceType::Main = m.0 {
+            for pos in slide_path(board, &current, &m.0, &m.1) {
+                path[pos_to_y(&pos)][pos_to_x(&pos)] = true;
+            }
+        }
+        current = next;
+    }
+
+    let new_board = with_pieces(board, &state);
+    println!("==========");
+    for (y, line) in new_board.iter().enumerate() {
+        let mut output_line = "|".to_string();
+        for (x, piece) in line.iter().enumerate() {
+            let sign = match piece {
+                BoardPiece::BoardMain => "\x1b[1;31mM",
+                BoardPiece::BoardHelper => "\x1b[1;34mH",
+                BoardPiece::Blocker => "\x1b[90m#",
+                BoardPiece::Pushable => "\x1b[37m%",
+                BoardPiece::Void => "\x1b[2m~",
+                BoardPiece::Lava(_) => "\x1b[1;31m!",
+                BoardPiece::Goal(_) => "\x1b[1;32mo",
+                BoardPiece::HelperGoal(_) => "\x1b[1;36mx",
+                BoardPiece::Mirror(Slant::Slash, _) => "\x1b[33m/",
+                BoardPiece::Mirror(Slant::Backslash, _) => "\x1b[33m\\",
+                BoardPiece::OneWay(Direction::Up) => "\x1b[33m^",
+                BoardPiece::OneWay(Direction::Down) => "\x1b[33mv",
+                BoardPiece::OneWay(Direction::Left) => "\x1b[33m<",
+                BoardPiece::OneWay(Direction::Right) => "\x1b[33m>",
+                BoardPiece::Empty if path[y][x] => "\x1b[1;35m.",
+                BoardPiece::Empty => " ",
+                BoardPiece::Portal(_) => "\x1b[1;35m@",
+                BoardPiece::Start => "\x1b[1;33m+",
+            };
+            if path[y][x] {
+                output_line += PATH_BG;
+            }
+            output_line += sign;
+            output_line += RESET;
+        }
+        output_line += "|";
+        println!("{output_line}");
+    }
+    println!("==========");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goals_have_to_be_numbered_without_gaps() {
+        let puzzle = "map:4:4:main_robot:0:0:helper_robot:3:0:helper_robot:0:3:goal:2:2";
+        assert!(puzzle_from_string(&format!("{puzzle}:goal:1:1:1")).is_ok());
+        let error = |puzzle: &str| puzzle_from_string(puzzle).err();
+        assert_eq!(
+            error(&format!("{puzzle}:goal:1:1:2")),
+            Some(ParseError::GoalIndex(1))
+        );
+        assert_eq!(
+            error(&format!("{puzzle}:goal:1:1:0")),
+            Some(ParseError::GoalIndex(0))
+        );
+    }
+}
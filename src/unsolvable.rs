@@ -0,0 +1,89 @@
+use alloc::vec;
+use core::fmt;
+
+use crate::solver::*;
+
+// Why a puzzle has no solution, see 'solve_or_explain'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsolvability {
+    // States the search went through before running out, every state reachable from the start.
+    pub states_exhausted: usize,
+    // Whether the main piece could visit every goal in order and get back to the start if the
+    // helpers were gone. If not, the board itself rules out a solution wherever the helpers are.
+    pub route_reachable_alone: bool,
+    // Cells the main piece can stop on moving alone from where it starts, itself included.
+    pub main_component: usize,
+}
+
+impl Unsolvability {
+    // Explains the outcome of a search of 'state' that ended without a solution, 'stats' being
+    // the statistics of that search.
+    pub fn new(board: &Board, state: &State, stats: &SearchStats) -> Self {
+        let goals = goal_count(board) as usize;
+        // Indexed by goals reached and position, every piece stacked on the main one so the
+        // helpers are never in the way.
+        let mut seen = vec![[false; 256]; goals + 1];
+        let start = state.main_pos();
+        seen[state.goals_reached() as usize][start as usize] = true;
+        let mut todo = vec![(state.goals_reached(), start)];
+        while let Some((reached, pos)) = todo.pop() {
+            let alone = State::new(pos, pos, pos).with_goals_reached(reached);
+            for dir in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                let Some((stop, _)) =
+                    slide(board, &alone, &[], &PieceType::Main, &pos, &dir, |_| {})
+                else {
+                    continue;
+                };
+                let reached = match board.at(&stop) {
+                    BoardPiece::Goal(index) if *index == reached => reached + 1,
+                    _ => reached,
+                };
+                if !core::mem::replace(&mut seen[reached as usize][stop as usize], true) {
+                    todo.push((reached, stop));
+                }
+            }
+        }
+        let route_reachable_alone = board
+            .cells()
+            .filter(|(_, _, cell)| **cell == BoardPiece::Start)
+            .any(|(x, y, _)| seen[goals][xy_to_pos(x, y) as usize]);
+        let main_component = (0..256)
+            .filter(|pos| seen.iter().any(|layer| layer[*pos]))
+            .count();
+        Self {
+            states_exhausted: stats.nodes_expanded,
+            route_reachable_alone,
+            main_component,
+        }
+    }
+}
+
+impl fmt::Display for Unsolvability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "all {} reachable states were searched; the main piece can stop on {} cells on its own",
+            self.states_exhausted, self.main_component
+        )?;
+        if self.route_reachable_alone {
+            write!(f, " and could finish without the helpers in the way")
+        } else {
+            write!(f, " and can't finish even without the helpers in the way")
+        }
+    }
+}
+
+// Searches the puzzle like 'solve_puzzle', explaining what went wrong when there is no solution.
+pub fn solve_or_explain(board: &Board, state: State) -> Result<Solution, Unsolvability> {
+    match solve_puzzle_with(board, state, &SolveOptions::default()) {
+        SolveOutcome::Solved(solution) => Ok(solution),
+        SolveOutcome::Unsolvable(stats) => Err(Unsolvability::new(board, &state, &stats)),
+        SolveOutcome::LimitReached(err, _) => panic!("Unlimited search failed: {err}."),
+        SolveOutcome::NotWithin(..) => unreachable!("Search without a depth limit."),
+    }
+}
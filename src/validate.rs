@@ -0,0 +1,208 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::solver::*;
+
+// Problem found in a puzzle that would make the solver panic or give a meaningless answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssue {
+    // The board has no cells.
+    MalformedBoard,
+    // No start cell, or the main piece is not on it.
+    MissingMainRobot,
+    // Nothing for the main piece to reach.
+    MissingGoal,
+    OutOfBounds(PieceType),
+    OverlappingPieces(PieceType, PieceType),
+    // Piece standing on a blocker, pushable blocker, void or lava fatal to it.
+    PieceOnBlocker(PieceType),
+    // Goal the piece it belongs to can never stop on, even with the other pieces placed anywhere.
+    UnreachableGoal { x: usize, y: usize },
+    // Pushable blockers only move in 'solve_pushing', every other search would treat them as
+    // fixed blockers.
+    PushableBlockers,
+    // Goals are numbered from 0 without gaps, this index is missing or used twice.
+    GoalIndex(u8),
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::MalformedBoard => write!(f, "board is empty"),
+            ValidationIssue::MissingMainRobot => write!(f, "main robot is missing"),
+            ValidationIssue::MissingGoal => write!(f, "puzzle has no goal"),
+            ValidationIssue::OutOfBounds(piece) => write!(f, "{piece:?} is outside the board"),
+            ValidationIssue::OverlappingPieces(a, b) => {
+                write!(f, "{a:?} and {b:?} are on the same cell")
+            }
+            ValidationIssue::PieceOnBlocker(piece) => write!(f, "{piece:?} is on a blocked cell"),
+            ValidationIssue::UnreachableGoal { x, y } => {
+                write!(f, "goal at {x},{y} can never be reached")
+            }
+            ValidationIssue::PushableBlockers => {
+                write!(f, "pushable blockers need the pushing search")
+            }
+            ValidationIssue::GoalIndex(index) => write!(f, "goal {index} is missing or repeated"),
+        }
+    }
+}
+
+// Cells 'piece' could stop on starting from 'start' if the other pieces could be put anywhere.
+// Any cell passed over on a slide is a possible stop, since a helper might be right behind it.
+fn stops(board: &Board, piece: &PieceType, start: Position) -> Vec<Vec<bool>> {
+    let mut seen = vec![vec![false; board.width()]; board.height()];
+    seen[pos_to_y(&start)][pos_to_x(&start)] = true;
+    let mut todo = vec![start];
+    while let Some(pos) = todo.pop() {
+        // Every piece on the same cell, so only the moving one is ever in the way.
+        let alone = State::new(pos, pos, pos);
+        for dir in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            let _ = slide(board, &alone, &[], piece, &pos, &dir, |next| {
+                let seen = &mut seen[pos_to_y(&next)][pos_to_x(&next)];
+                if !*seen {
+                    *seen = true;
+                    todo.push(next);
+                }
+            });
+        }
+    }
+    seen
+}
+
+// First goal index that is missing or repeated, if the goals aren't numbered 0, 1, 2 and on. The
+// main piece reaches goals in that order, so it could never get past such an index.
+pub(crate) fn misnumbered_goal(board: &Board) -> Option<u8> {
+    let mut indices: Vec<u8> = board
+        .cells()
+        .filter_map(|(_, _, piece)| match piece {
+            BoardPiece::Goal(index) => Some(*index),
+            _ => None,
+        })
+        .collect();
+    indices.sort_unstable();
+    (0..)
+        .zip(indices)
+        .find(|(expected, index)| index != expected)
+        .map(|(expected, index)| expected.min(index))
+}
+
+// Checks a parsed puzzle for problems, giving every issue found. An empty result means the puzzle
+// is safe to solve, not that it has a solution.
+pub fn validate(board: &Board, state: &State) -> Vec<ValidationIssue> {
+    if board.width() == 0 {
+        return vec![ValidationIssue::MalformedBoard];
+    }
+
+    let mut issues = Vec::new();
+    let pieces = [
+        (PieceType::Main, state.main_pos()),
+        (PieceType::HelperOne, state.helper_one_pos()),
+        (PieceType::HelperTwo, state.helper_two_pos()),
+    ];
+    let in_bounds =
+        |pos: &Position| pos_to_x(pos) < board.width() && pos_to_y(pos) < board.height();
+
+    for (i, (piece, pos)) in pieces.iter().enumerate() {
+        if !in_bounds(pos) {
+            issues.push(ValidationIssue::OutOfBounds(*piece));
+            continue;
+        }
+        if matches!(
+            board.at(pos),
+            BoardPiece::Blocker | BoardPiece::Pushable | BoardPiece::Void
+        ) || board.at(pos).is_fatal_to(piece)
+        {
+            issues.push(ValidationIssue::PieceOnBlocker(*piece));
+        }
+        for (other, other_pos) in &pieces[i + 1..] {
+            if pos == other_pos {
+                issues.push(ValidationIssue::OverlappingPieces(*piece, *other));
+            }
+        }
+    }
+
+    let main = state.main_pos();
+    let main_on_start = in_bounds(&main) && *board.at(&main) == BoardPiece::Start;
+    if !main_on_start {
+        issues.push(ValidationIssue::MissingMainRobot);
+    }
+    if goal_count(board) == 0 {
+        issues.push(ValidationIssue::MissingGoal);
+    }
+    if let Some(index) = misnumbered_goal(board) {
+        issues.push(ValidationIssue::GoalIndex(index));
+    }
+    if board
+        .cells()
+        .any(|(_, _, piece)| *piece == BoardPiece::Pushable)
+    {
+        issues.push(ValidationIssue::PushableBlockers);
+    }
+    if !issues.is_empty() {
+        // Reachability is meaningless with pieces misplaced or blockers that move.
+        return issues;
+    }
+
+    let reachable = |piece: &PieceType| stops(board, piece, state.pos(piece));
+    let (main, helper_one, helper_two) = (
+        reachable(&PieceType::Main),
+        reachable(&PieceType::HelperOne),
+        reachable(&PieceType::HelperTwo),
+    );
+    for (x, y, piece) in board.cells() {
+        let reached = match piece {
+            BoardPiece::Goal(_) => main[y][x],
+            BoardPiece::HelperGoal(PieceType::HelperOne) => helper_one[y][x],
+            BoardPiece::HelperGoal(_) => helper_two[y][x],
+            _ => true,
+        };
+        if !reached {
+            issues.push(ValidationIssue::UnreachableGoal { x, y });
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_with_goals(goals: &[u8]) -> (Board, State) {
+        let mut board = Board::new(8, 8);
+        board.set(0, 0, BoardPiece::Start);
+        for (x, index) in goals.iter().enumerate() {
+            board.set(x + 1, 7, BoardPiece::Goal(*index));
+        }
+        let state = State::new(xy_to_pos(0, 0), xy_to_pos(7, 0), xy_to_pos(0, 7));
+        (board, state)
+    }
+
+    #[test]
+    fn goals_numbered_in_order_pass() {
+        let (board, state) = board_with_goals(&[1, 0, 2]);
+        assert_eq!(misnumbered_goal(&board), None);
+        assert!(validate(&board, &state).is_empty());
+    }
+
+    #[test]
+    fn goal_index_gaps_are_rejected() {
+        let (board, state) = board_with_goals(&[0, 2]);
+        assert!(validate(&board, &state).contains(&ValidationIssue::GoalIndex(1)));
+        let (board, state) = board_with_goals(&[1]);
+        assert!(validate(&board, &state).contains(&ValidationIssue::GoalIndex(0)));
+    }
+
+    #[test]
+    fn repeated_goal_indices_are_rejected() {
+        let (board, state) = board_with_goals(&[0, 0]);
+        assert!(validate(&board, &state).contains(&ValidationIssue::GoalIndex(0)));
+        let (board, state) = board_with_goals(&[0, 1, 1]);
+        assert!(validate(&board, &state).contains(&ValidationIssue::GoalIndex(1)));
+    }
+}
@@ -0,0 +1,33 @@
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::solver::{solve_puzzle, Solution};
+use crate::tools::{format_move, puzzle_from_string};
+use crate::validate::validate;
+
+// Solves a puzzle in the colon separated map format, giving '{ moves: string[], length: number }'
+// or null if it has no solution. Throws if the puzzle can't be read or is invalid.
+#[wasm_bindgen]
+pub fn solve(puzzle: &str) -> Result<JsValue, JsError> {
+    let (board, state) = puzzle_from_string(puzzle)
+        .map_err(|err| JsError::new(&format!("invalid puzzle: {err}")))?;
+    if let Some(issue) = validate(&board, &state).first() {
+        return Err(JsError::new(&format!("invalid puzzle: {issue}")));
+    }
+    let Some(Solution { moves, .. }) = solve_puzzle(&board, state) else {
+        return Ok(JsValue::NULL);
+    };
+    let names = Array::new();
+    for m in &moves {
+        names.push(&JsValue::from_str(&format_move(m)));
+    }
+    let result = Object::new();
+    Reflect::set(&result, &"moves".into(), &names).unwrap();
+    Reflect::set(
+        &result,
+        &"length".into(),
+        &JsValue::from(moves.len() as u32),
+    )
+    .unwrap();
+    Ok(result.into())
+}
@@ -0,0 +1,133 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+use crate::solver::*;
+
+// Cost of every move, indexed by piece (Main, HelperOne, HelperTwo) and direction
+// (Up, Down, Left, Right).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveCosts {
+    pub costs: [[u32; 4]; 3],
+}
+
+impl MoveCosts {
+    pub fn uniform(cost: u32) -> Self {
+        Self {
+            costs: [[cost; 4]; 3],
+        }
+    }
+
+    pub fn per_piece(main: u32, helper: u32) -> Self {
+        Self {
+            costs: [[main; 4], [helper; 4], [helper; 4]],
+        }
+    }
+
+    pub fn cost(&self, m: &Move) -> u32 {
+        let piece = match m.0 {
+            PieceType::Main => 0,
+            PieceType::HelperOne => 1,
+            PieceType::HelperTwo => 2,
+        };
+        let dir = match m.1 {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3,
+        };
+        self.costs[piece][dir]
+    }
+}
+
+impl Default for MoveCosts {
+    fn default() -> Self {
+        Self::uniform(1)
+    }
+}
+
+struct Node<K> {
+    key: K,
+    m: Option<Move>,
+    prev: usize,
+}
+
+fn moves<K>(nodes: &[Node<K>], mut index: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(m) = nodes[index].m {
+        moves.push(m);
+        index = nodes[index].prev;
+    }
+    moves.reverse();
+    moves
+}
+
+// Uniform-cost search over search keys 'K' that extend a State with whatever the cost depends on.
+// 'state_of' gives the board state of a key, 'step' gives the key reached by a move and its cost.
+pub(crate) fn uniform_cost<K: Copy + Eq + Hash>(
+    board: &Board,
+    start: K,
+    state_of: impl Fn(&K) -> State,
+    step: impl Fn(&K, &Move, State) -> (K, u32),
+) -> Option<(K, Vec<Move>, u32)> {
+    let order = TieBreak::default();
+    let win = WinCondition::new(board);
+    let mut best = HashMap::new();
+    let mut nodes = vec![Node {
+        key: start,
+        m: None,
+        prev: 0,
+    }];
+    // Node indices increase with insertion, so equal costs pop in FIFO order.
+    let mut heap = BinaryHeap::new();
+    best.insert(start, 0);
+    heap.push(Reverse((0, 0)));
+
+    while let Some(Reverse((cost, index))) = heap.pop() {
+        let key = nodes[index].key;
+        if best[&key] < cost {
+            continue; // Stale entry, a cheaper path was found after this was queued.
+        }
+        let state = state_of(&key);
+        if win.is_met(board, &state) {
+            return Some((key, moves(&nodes, index), cost));
+        }
+        for (m, next) in neighbourhood(board, &state, &order) {
+            let (next, move_cost) = step(&key, &m, next);
+            let next_cost = cost + move_cost;
+            match best.entry(next) {
+                Entry::Occupied(mut entry) if *entry.get() > next_cost => {
+                    entry.insert(next_cost);
+                }
+                Entry::Occupied(_) => continue,
+                Entry::Vacant(entry) => {
+                    entry.insert(next_cost);
+                }
+            }
+            nodes.push(Node {
+                key: next,
+                m: Some(m),
+                prev: index,
+            });
+            heap.push(Reverse((next_cost, nodes.len() - 1)));
+        }
+    }
+
+    None
+}
+
+// Uniform-cost search for the solution with the lowest total move cost, giving the final state,
+// the moves and their total cost. Equal cost solutions are broken by the default TieBreak order.
+pub fn solve_weighted(
+    board: &Board,
+    state: State,
+    costs: &MoveCosts,
+) -> Option<(State, Vec<Move>, u32)> {
+    uniform_cost(
+        board,
+        state,
+        |state| *state,
+        |_, m, next| (next, costs.cost(m)),
+    )
+}